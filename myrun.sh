@@ -13,4 +13,5 @@
     --left 484 \
     --top 153 \
     --right 502 \
-    --bottom 181
+    --bottom 181 \
+    --output ./hoge/result
@@ -2,24 +2,41 @@ pub mod curvature_generator;
 pub mod differential;
 pub mod dim;
 pub mod distance_map_generator;
+pub mod distance_regularization;
+pub mod evolution_result;
+pub mod fast_marching;
 pub mod front;
+pub mod gaussian;
+pub mod gradient_vector_flow;
 pub mod grid;
+pub mod grid_file;
 pub mod grid_range;
 pub mod indexer;
 pub mod initial_front;
 pub mod inside_estimator;
 pub mod level_set_method;
+pub mod morphological_snakes;
+pub mod multiphase;
 pub mod neighboring_point;
+pub mod observer;
 pub mod parameters;
 pub mod point;
 pub mod position;
+pub mod preprocessing;
+pub mod region_speed;
+pub mod shape_model;
+pub mod shape_prior;
 pub mod space_size;
+pub mod sparse_field;
 pub mod speed;
 pub mod speed_factor;
 pub mod status;
 pub mod stopping_condition;
+pub mod stopping_criterion;
+pub mod topology;
 pub mod types;
 pub mod upwind;
 pub mod upwind_scheme;
 pub mod util;
+pub mod weno_scheme;
 pub mod zero_level_set_detector;
@@ -0,0 +1,84 @@
+use crate::core::differential::{DifferentialDouble2d, DifferentialDouble3d, DifferentialMethod};
+use crate::core::indexer::{Indexer2d, Indexer3d};
+use crate::core::point::{Point2d, Point3d};
+use std::cell::RefCell;
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+/// distance regularization term of DRLSE (Li et al. 2010), which drives |grad(phi)| to 1
+/// near the zero-levelset and to 0 far from it without reinitialization.
+pub trait DistanceRegularizationMethod<Indexer, IntPoint> {
+    fn new(indexer: Rc<Indexer>, phi: Rc<RefCell<Vec<f64>>>) -> Self;
+    /// div(d_p(|grad(phi)|) grad(phi)) at p, which must not be on the edge of the space.
+    fn calculate(&mut self, p: &IntPoint) -> f64;
+}
+
+/// d_p(s) = p'(s) / s of the double-well potential
+/// p(s) = (1 - cos(2 pi s)) / (2 pi)^2 for s <= 1 and (s - 1)^2 / 2 for s >= 1.
+pub fn diffusion_rate(s: f64) -> f64 {
+    if s == 0.0 {
+        1.0
+    } else if s <= 1.0 {
+        (2.0 * PI * s).sin() / (2.0 * PI * s)
+    } else {
+        (s - 1.0) / s
+    }
+}
+
+/// sums the fluxes d_p(|grad(phi)|) dphi/dn through the faces of the center of a 3x3(x3)
+/// neighborhood, evaluating the gradient on each face from the two points it separates.
+fn divergence(values: &[f64], dimension: usize) -> f64 {
+    let strides = [1, 3, 9];
+    let center = values.len() / 2;
+    let mut sum = 0.0;
+    for axis in 0..dimension {
+        for sign in [-1, 1] {
+            let neighbor = (center as i32 + sign * strides[axis]) as usize;
+            let normal = values[neighbor] - values[center];
+            let mut norm2 = normal * normal;
+            for other in (0..dimension).filter(|o| *o != axis) {
+                let s = strides[other] as usize;
+                let tangent = (values[center + s] - values[center - s] + values[neighbor + s]
+                    - values[neighbor - s])
+                    / 4.0;
+                norm2 += tangent * tangent;
+            }
+            sum += diffusion_rate(norm2.sqrt()) * normal;
+        }
+    }
+    sum
+}
+
+pub struct DistanceRegularization2d {
+    differential: DifferentialDouble2d,
+}
+
+impl DistanceRegularizationMethod<Indexer2d, Point2d<i32>> for DistanceRegularization2d {
+    fn new(indexer: Rc<Indexer2d>, phi: Rc<RefCell<Vec<f64>>>) -> Self {
+        Self {
+            differential: DifferentialDouble2d::new(indexer, phi),
+        }
+    }
+
+    fn calculate(&mut self, p: &Point2d<i32>) -> f64 {
+        self.differential.make_point(p);
+        divergence(&self.differential.values, 2)
+    }
+}
+
+pub struct DistanceRegularization3d {
+    differential: DifferentialDouble3d,
+}
+
+impl DistanceRegularizationMethod<Indexer3d, Point3d<i32>> for DistanceRegularization3d {
+    fn new(indexer: Rc<Indexer3d>, phi: Rc<RefCell<Vec<f64>>>) -> Self {
+        Self {
+            differential: DifferentialDouble3d::new(indexer, phi),
+        }
+    }
+
+    fn calculate(&mut self, p: &Point3d<i32>) -> f64 {
+        self.differential.make_point(p);
+        divergence(&self.differential.values, 3)
+    }
+}
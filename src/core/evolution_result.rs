@@ -0,0 +1,38 @@
+use crate::core::point::{Point2d, Point3d};
+
+pub struct EvolutionResult<IntPoint> {
+    /// auxiliary function at the end of the evolution
+    pub phi: Vec<f64>,
+
+    /// zero-levelset at the end of the evolution
+    pub front: Vec<IntPoint>,
+
+    /// number of iterations actually performed
+    pub iterations: usize,
+
+    /// time step used at each iteration
+    pub time_steps: Vec<f64>,
+
+    /// number of times phi was rebuilt
+    pub resets: usize,
+
+    /// number of sign changes vetoed at each iteration, empty without the topology preservation
+    pub vetoes: Vec<usize>,
+}
+
+pub type EvolutionResult2d = EvolutionResult<Point2d<i32>>;
+pub type EvolutionResult3d = EvolutionResult<Point3d<i32>>;
+
+pub struct MultiphaseResult<IntPoint> {
+    /// 0 for the background and k + 1 for the region owned by the phase k
+    pub labels: Vec<u8>,
+
+    /// result of each phase
+    pub phases: Vec<EvolutionResult<IntPoint>>,
+
+    /// number of iterations actually performed
+    pub iterations: usize,
+}
+
+pub type MultiphaseResult2d = MultiphaseResult<Point2d<i32>>;
+pub type MultiphaseResult3d = MultiphaseResult<Point3d<i32>>;
@@ -0,0 +1,259 @@
+use crate::core::indexer::FlatIndexer;
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+pub trait FastMarchingMethod<T> {
+    fn new(space_size: &T) -> Self;
+    /// overwrites phi on the band with signed distances to the zero-levelset of phi0.
+    /// the zero crossings of phi0 are interpolated linearly between grid points.
+    fn reinitialize(&mut self, phi0: &[f64], phi: &mut [f64], band: &[usize], second_order: bool);
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Label {
+    Outside,
+    Far,
+    Trial,
+    Known,
+}
+
+/// a trial point ordered so that BinaryHeap pops the smallest distance first
+struct Trial {
+    distance: f64,
+    index: usize,
+}
+
+impl PartialEq for Trial {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl Eq for Trial {}
+
+impl PartialOrd for Trial {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Trial {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+/// fast marching on a flat array of at most three axes
+struct FastMarching {
+    indexer: FlatIndexer,
+    labels: Vec<Label>,
+    distances: Vec<f64>,
+}
+
+impl FastMarching {
+    fn new(extents: Vec<i32>) -> Self {
+        let indexer = FlatIndexer::new(&extents);
+        let total = indexer.get_total();
+        Self {
+            indexer,
+            labels: vec![Label::Outside; total],
+            distances: vec![f64::MAX; total],
+        }
+    }
+
+    fn reinitialize(&mut self, phi0: &[f64], phi: &mut [f64], band: &[usize], second_order: bool) {
+        for i in band {
+            self.labels[*i] = Label::Far;
+            self.distances[*i] = f64::MAX;
+        }
+
+        let mut heap = BinaryHeap::new();
+        for i in band {
+            if let Some(d) = self.distance_to_crossing(phi0, *i) {
+                self.labels[*i] = Label::Known;
+                self.distances[*i] = d;
+            }
+        }
+        for i in band {
+            if self.labels[*i] == Label::Known {
+                self.update_neighbors(phi0, *i, second_order, &mut heap);
+            }
+        }
+
+        while let Some(Trial { distance, index }) = heap.pop() {
+            if self.labels[index] == Label::Known || distance > self.distances[index] {
+                continue;
+            }
+            self.labels[index] = Label::Known;
+            self.update_neighbors(phi0, index, second_order, &mut heap);
+        }
+
+        for i in band {
+            if self.labels[*i] == Label::Known {
+                let d = self.distances[*i];
+                phi[*i] = if phi0[*i] < 0.0 { -d } else { d };
+            }
+            self.labels[*i] = Label::Outside;
+        }
+    }
+
+    /// distance from a point adjacent to a sign change of phi0, combining the crossings of all axes.
+    fn distance_to_crossing(&self, phi0: &[f64], i: usize) -> Option<f64> {
+        if phi0[i] == 0.0 {
+            return Some(0.0);
+        }
+        let inside = phi0[i] < 0.0;
+        let mut sum = 0.0;
+        let mut found = false;
+        for axis in 0..self.indexer.get_extents().len() {
+            let mut nearest = f64::MAX;
+            for offset in [-1, 1] {
+                if let Some(j) = self.indexer.shift(i, axis, offset) {
+                    if (phi0[j] < 0.0) != inside {
+                        let theta = phi0[i] / (phi0[i] - phi0[j]);
+                        nearest = nearest.min(theta);
+                    }
+                }
+            }
+            if nearest < f64::MAX {
+                if nearest <= 0.0 {
+                    return Some(0.0);
+                }
+                found = true;
+                sum += 1.0 / (nearest * nearest);
+            }
+        }
+        if found {
+            Some(1.0 / sum.sqrt())
+        } else {
+            None
+        }
+    }
+
+    fn update_neighbors(
+        &mut self,
+        phi0: &[f64],
+        i: usize,
+        second_order: bool,
+        heap: &mut BinaryHeap<Trial>,
+    ) {
+        for axis in 0..self.indexer.get_extents().len() {
+            for offset in [-1, 1] {
+                if let Some(j) = self.indexer.shift(i, axis, offset) {
+                    if self.labels[j] == Label::Far || self.labels[j] == Label::Trial {
+                        let d = self.solve(phi0, j, second_order);
+                        if d < self.distances[j] {
+                            self.distances[j] = d;
+                            self.labels[j] = Label::Trial;
+                            heap.push(Trial {
+                                distance: d,
+                                index: j,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// upwind solution of |grad(d)| = 1 at i from the known neighbors.
+    fn solve(&self, phi0: &[f64], i: usize, second_order: bool) -> f64 {
+        // (c^2, b) of each axis, the axis term being c^2 (d - b)^2
+        let mut terms = Vec::<(f64, f64)>::with_capacity(self.indexer.get_extents().len());
+        for axis in 0..self.indexer.get_extents().len() {
+            let mut term: Option<(f64, f64)> = None;
+            for offset in [-1, 1] {
+                let j = match self.indexer.shift(i, axis, offset) {
+                    Some(j) if self.labels[j] == Label::Known => j,
+                    _ => continue,
+                };
+                let d1 = self.distances[j];
+                let mut candidate = (1.0, d1);
+                if second_order {
+                    match self.indexer.shift(j, axis, offset) {
+                        Some(k) if self.labels[k] == Label::Known => {
+                            // beyond the zero-levelset the distance continues with the opposite sign
+                            let d2 = if (phi0[k] < 0.0) == (phi0[i] < 0.0) {
+                                self.distances[k]
+                            } else {
+                                -self.distances[k]
+                            };
+                            if d2 <= d1 {
+                                candidate = (2.25, (4.0 * d1 - d2) / 3.0);
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                term = match term {
+                    Some(t) if t.1 <= candidate.1 => Some(t),
+                    _ => Some(candidate),
+                };
+            }
+            if let Some(t) = term {
+                terms.push(t);
+            }
+        }
+        terms.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let (c2, b) = terms[0];
+        let mut d = b + 1.0 / c2.sqrt();
+        let (mut sa, mut sb, mut sc) = (0.0, 0.0, 0.0);
+        for (k, (c2, b)) in terms.iter().enumerate() {
+            sa += c2;
+            sb += c2 * b;
+            sc += c2 * b * b;
+            if k == 0 {
+                continue;
+            }
+            if d <= *b {
+                break;
+            }
+            let discriminant = sb * sb - sa * (sc - 1.0);
+            if discriminant < 0.0 {
+                break;
+            }
+            d = (sb + discriminant.sqrt()) / sa;
+        }
+        d
+    }
+}
+
+pub struct FastMarching2d {
+    fast_marching: FastMarching,
+}
+
+impl FastMarchingMethod<SpaceSize2d> for FastMarching2d {
+    fn new(space_size: &SpaceSize2d) -> Self {
+        Self {
+            fast_marching: FastMarching::new(vec![space_size.width, space_size.height]),
+        }
+    }
+
+    fn reinitialize(&mut self, phi0: &[f64], phi: &mut [f64], band: &[usize], second_order: bool) {
+        self.fast_marching
+            .reinitialize(phi0, phi, band, second_order);
+    }
+}
+
+pub struct FastMarching3d {
+    fast_marching: FastMarching,
+}
+
+impl FastMarchingMethod<SpaceSize3d> for FastMarching3d {
+    fn new(space_size: &SpaceSize3d) -> Self {
+        Self {
+            fast_marching: FastMarching::new(vec![
+                space_size.width,
+                space_size.height,
+                space_size.depth,
+            ]),
+        }
+    }
+
+    fn reinitialize(&mut self, phi0: &[f64], phi: &mut [f64], band: &[usize], second_order: bool) {
+        self.fast_marching
+            .reinitialize(phi0, phi, band, second_order);
+    }
+}
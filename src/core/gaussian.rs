@@ -0,0 +1,107 @@
+use crate::core::indexer::FlatIndexer;
+
+/// normalized Gaussian kernel with the radius ceil(3 * sigma), the center at the radius.
+pub fn make_kernel(sigma: f64) -> Vec<f64> {
+    assert!(sigma > 0.0, "sigma must be positive");
+    let radius = (3.0 * sigma).ceil() as i32;
+    let kernel: Vec<f64> = (-radius..=radius)
+        .map(|k| (-((k * k) as f64) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f64 = kernel.iter().sum();
+    kernel.iter().map(|v| v / sum).collect()
+}
+
+/// convolves values laid out with x varying fastest by kernel along each axis in turn.
+/// the values outside the space are 0, so the result is not renormalized near the edges.
+pub fn convolve(values: &[f64], extents: &[i32], kernel: &[f64]) -> Vec<f64> {
+    let region: Vec<usize> = (0..values.len()).collect();
+    let mut result = vec![0.0; values.len()];
+    let mut scratch = vec![0.0; values.len()];
+    convolve_within(values, extents, kernel, &region, &mut result, &mut scratch);
+    result
+}
+
+/// convolve evaluated only at the points of region into result, with scratch holding the passes.
+/// both are as long as values, and their other points are left as they are.
+/// the result is exact at the points whose box of the kernel radius lies in region.
+pub fn convolve_within(
+    values: &[f64],
+    extents: &[i32],
+    kernel: &[f64],
+    region: &[usize],
+    result: &mut [f64],
+    scratch: &mut [f64],
+) {
+    let indexer = FlatIndexer::new(extents);
+    let radius = (kernel.len() / 2) as i32;
+    let last = extents.len() - 1;
+    for (axis, (extent, stride)) in extents.iter().zip(indexer.get_strides()).enumerate() {
+        let (extent, stride) = (*extent, *stride);
+        // the passes alternate between the buffers so that the last one writes result
+        let (source, target): (&[f64], &mut [f64]) = match (axis, (last - axis).is_multiple_of(2)) {
+            (0, true) => (values, &mut *result),
+            (0, false) => (values, &mut *scratch),
+            (_, true) => (&*scratch, &mut *result),
+            (_, false) => (&*result, &mut *scratch),
+        };
+        for i in region {
+            let c = indexer.coordinate(*i, axis);
+            let lower = (c - radius).max(0);
+            let upper = (c + radius).min(extent - 1);
+            let base = i - c as usize * stride;
+            target[*i] = (lower..=upper)
+                .map(|k| kernel[(k - c + radius) as usize] * source[base + k as usize * stride])
+                .sum();
+        }
+    }
+}
+
+/// the points in the box of radius around each of points, each once.
+/// is_marked is as long as the space and false everywhere before and after.
+pub fn dilate(
+    points: &[usize],
+    extents: &[i32],
+    radius: i32,
+    is_marked: &mut [bool],
+) -> Vec<usize> {
+    let mut dilated = Vec::with_capacity(points.len());
+    for i in points {
+        if !is_marked[*i] {
+            is_marked[*i] = true;
+            dilated.push(*i);
+        }
+    }
+    let indexer = FlatIndexer::new(extents);
+    for (axis, (extent, stride)) in extents.iter().zip(indexer.get_strides()).enumerate() {
+        let (extent, stride) = (*extent, *stride);
+        // the points added along this axis are not moved along it again
+        for n in 0..dilated.len() {
+            let i = dilated[n];
+            let c = indexer.coordinate(i, axis);
+            let base = i - c as usize * stride;
+            for k in (c - radius).max(0)..=(c + radius).min(extent - 1) {
+                let j = base + k as usize * stride;
+                if !is_marked[j] {
+                    is_marked[j] = true;
+                    dilated.push(j);
+                }
+            }
+        }
+    }
+    for i in &dilated {
+        is_marked[*i] = false;
+    }
+    dilated
+}
+
+/// values smoothed by the Gaussian of sigma, divided by the weights of the kernel inside the space
+/// so that the edges of the space do not look like the edges of the values.
+pub fn smooth(values: &[f64], extents: &[i32], sigma: f64) -> Vec<f64> {
+    let kernel = make_kernel(sigma);
+    let weights = convolve(&vec![1.0; values.len()], extents, &kernel);
+    convolve(values, extents, &kernel)
+        .iter()
+        .zip(&weights)
+        .map(|(v, w)| v / w)
+        .collect()
+}
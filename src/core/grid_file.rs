@@ -0,0 +1,49 @@
+/// header of the files holding values on a grid, all values little endian:
+/// - magic(4 bytes)
+/// - dimension as u32, 2 or 3
+/// - extents as u32 for each axis, x first
+pub struct GridHeader {
+    pub extents: Vec<i32>,
+    /// number of the points of the grid
+    pub total: usize,
+    /// position of the first byte after the header
+    pub offset: usize,
+}
+
+impl GridHeader {
+    /// None unless bytes start with magic, a dimension of 2 or 3
+    /// and positive extents whose product fits in i32.
+    pub fn read(bytes: &[u8], magic: &[u8; 4]) -> Option<Self> {
+        if bytes.get(0..4)? != magic {
+            return None;
+        }
+        let dimension = read_u32(bytes, 4)? as usize;
+        if dimension != 2 && dimension != 3 {
+            return None;
+        }
+        let read_extent = |axis: usize| -> Option<i32> {
+            let e = read_u32(bytes, 8 + 4 * axis)?;
+            i32::try_from(e).ok().filter(|e| *e > 0)
+        };
+        let extents: Vec<i32> = (0..dimension).map(read_extent).collect::<Option<_>>()?;
+        let total = extents.iter().try_fold(1i32, |t, e| t.checked_mul(*e))? as usize;
+        Some(Self {
+            extents,
+            total,
+            offset: 8 + 4 * dimension,
+        })
+    }
+}
+
+pub fn read_u32(bytes: &[u8], k: usize) -> Option<u32> {
+    let b = bytes.get(k..k + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// the f64 values of bytes, whose length is a multiple of 8.
+pub fn read_values(bytes: &[u8]) -> Vec<f64> {
+    bytes
+        .chunks_exact(8)
+        .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+        .collect()
+}
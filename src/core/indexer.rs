@@ -37,3 +37,62 @@ impl IndexerMethod<SpaceSize3d, Point3d<i32>> for Indexer3d {
         p.x + self.width * p.y + self.area * p.z
     }
 }
+
+/// flat indices of a space of at most three axes with x varying fastest,
+/// for the algorithms which run over the axes instead of Point2d/3d.
+pub struct FlatIndexer {
+    extents: Vec<i32>,
+    strides: Vec<usize>,
+}
+
+impl FlatIndexer {
+    pub fn new(extents: &[i32]) -> Self {
+        let mut strides = Vec::with_capacity(extents.len());
+        let mut stride = 1usize;
+        for e in extents {
+            strides.push(stride);
+            stride *= *e as usize;
+        }
+        Self {
+            extents: extents.to_vec(),
+            strides,
+        }
+    }
+
+    pub fn get_extents(&self) -> &[i32] {
+        &self.extents
+    }
+
+    pub fn get_strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn get_total(&self) -> usize {
+        self.extents.iter().map(|e| *e as usize).product()
+    }
+
+    /// coordinate of index along axis
+    pub fn coordinate(&self, index: usize, axis: usize) -> i32 {
+        ((index / self.strides[axis]) % self.extents[axis] as usize) as i32
+    }
+
+    /// index of the point at offset along axis, None outside the space
+    pub fn shift(&self, index: usize, axis: usize, offset: i32) -> Option<usize> {
+        let c = self.coordinate(index, axis) + offset;
+        if c < 0 || c >= self.extents[axis] {
+            None
+        } else {
+            Some((index as isize + offset as isize * self.strides[axis] as isize) as usize)
+        }
+    }
+
+    /// the neighbors of index along each axis in turn, None outside the space or beyond its axes
+    pub fn neighbors(&self, index: usize) -> [Option<usize>; 6] {
+        let mut points = [None; 6];
+        for axis in 0..self.extents.len() {
+            points[2 * axis] = self.shift(index, axis, -1);
+            points[2 * axis + 1] = self.shift(index, axis, 1);
+        }
+        points
+    }
+}
@@ -7,19 +7,38 @@ use crate::core::distance_map_generator::{
     DistanceMap2d, DistanceMap3d, DistanceMapGenerator2d, DistanceMapGenerator3d,
     DistanceMapGeneratorMethod, DistanceMapMethod, PointInfo2d, PointInfo3d, PointInfoMethod,
 };
+use crate::core::distance_regularization::{
+    DistanceRegularization2d, DistanceRegularization3d, DistanceRegularizationMethod,
+};
+use crate::core::evolution_result::EvolutionResult;
+use crate::core::fast_marching::{FastMarching2d, FastMarching3d, FastMarchingMethod};
+use crate::core::gradient_vector_flow::VectorField;
 use crate::core::grid::{Grid2d, Grid3d, GridMethod};
 use crate::core::grid_range::{GridRange2d, GridRange3d, GridRangeMethod};
 use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
 use crate::core::initial_front::{InitialFront2d, InitialFront3d};
 use crate::core::inside_estimator::{InsideEstimator2d, InsideEstimator3d, InsideEstimatorMethod};
-use crate::core::parameters::Parameters;
+use crate::core::observer::{IterationState, Observer, ObserverAction};
+use crate::core::parameters::{
+    Engine, Parameters, Reinitialization, ReinitializationTrigger, SpatialScheme, SpeedMode,
+    TimeIntegrator,
+};
 use crate::core::point::{Point2d, Point3d, PointMethod};
+use crate::core::region_speed::{ChanVeseSpeed, LocalBinaryFittingSpeed, LocalFittingImage};
+use crate::core::shape_prior::{ReferenceMap, ShapePrior2d, ShapePrior3d, ShapePriorMethod};
 use crate::core::space_size::{SpaceSize2d, SpaceSize3d, SpaceSizeMethod};
+use crate::core::sparse_field::{SparseField2d, SparseField3d, SparseFieldMethod};
 use crate::core::speed::Speed;
-use crate::core::speed_factor::{SpeedFactor2d, SpeedFactor3d, SpeedFactorMethod};
+use crate::core::speed_factor::{EdgeStopping, SpeedFactor2d, SpeedFactor3d, SpeedFactorMethod};
 use crate::core::status::Status;
 use crate::core::stopping_condition::StoppingCondition;
+use crate::core::stopping_criterion::StoppingCriterion;
+use crate::core::topology::{Topology2d, Topology3d, TopologyMethod};
 use crate::core::upwind_scheme::{UpwindScheme2d, UpwindScheme3d, UpwindSchemeMethod};
+use crate::core::weno_scheme::{WenoScheme2d, WenoScheme3d};
+use crate::core::zero_level_set_detector::{
+    ZeroLevelSetDetector2d, ZeroLevelSetDetector3d, ZeroLevelSetDetectorMethod,
+};
 use std::cell::RefCell;
 use std::marker::PhantomData;
 use std::rc::Rc;
@@ -41,18 +60,32 @@ pub struct LevelSetMethod<
     Grid,
     InsideEstimator,
     CurvatureGenerator,
+    ZeroLevelSetDetector,
+    WenoScheme,
+    FastMarching,
+    SparseField,
+    Topology,
+    ShapePrior,
+    DistanceRegularization,
 > where
     IntPoint: Copy + PointMethod<Type = IntPoint>,
     PointInfo: PointInfoMethod<IntPoint>,
     SpaceSize: SpaceSizeMethod,
     Indexer: IndexerMethod<SpaceSize, IntPoint>,
-    UpwindScheme: UpwindSchemeMethod<Indexer, IntPoint>,
+    UpwindScheme: UpwindSchemeMethod<SpaceSize, Indexer, IntPoint>,
     SpeedFactor: SpeedFactorMethod<Indexer, IntPoint, SpaceSize>,
     GridRange: GridRangeMethod<SpaceSize, Indexer, IntPoint, Self>,
     DistanceMapGenerator: DistanceMapGeneratorMethod<Indexer, DistanceMap, IntPoint, Self>,
     Grid: GridMethod<InitialFront, SpaceSize, Self, IntPoint>,
     InsideEstimator: InsideEstimatorMethod<Grid, IntPoint>,
     CurvatureGenerator: CurvatureGeneratorMethod<Indexer, IntPoint, DoublePoint>,
+    ZeroLevelSetDetector: ZeroLevelSetDetectorMethod<Indexer, IntPoint>,
+    WenoScheme: UpwindSchemeMethod<SpaceSize, Indexer, IntPoint>,
+    FastMarching: FastMarchingMethod<SpaceSize>,
+    SparseField: SparseFieldMethod<SpaceSize, IntPoint>,
+    Topology: TopologyMethod<Indexer, IntPoint>,
+    ShapePrior: ShapePriorMethod<SpaceSize, IntPoint>,
+    DistanceRegularization: DistanceRegularizationMethod<Indexer, IntPoint>,
 {
     phantom_initial_front: PhantomData<InitialFront>,
     phantom_distance_map: PhantomData<DistanceMap>,
@@ -91,11 +124,30 @@ pub struct LevelSetMethod<
     /// narrow band
     narrow_bands: Vec<IntPoint>,
 
+    /// points which have joined the narrow band during the current reset
+    activated_points: RefCell<Vec<IntPoint>>,
+
+    /// points owned by the competing level sets, where the front may not expand
+    exclusion: Option<Rc<RefCell<Vec<bool>>>>,
+
     /// input image(gray image)
     input_object: Rc<RefCell<Vec<u8>>>,
 
     upwind_scheme: UpwindScheme,
-    speed_factor: SpeedFactor,
+    /// shared with the level sets made by new_sharing_image
+    speed_factor: Rc<SpeedFactor>,
+
+    /// the optional engines below are built only for the parameters selecting them
+    weno_scheme: Option<WenoScheme>,
+    distance_regularization: Option<DistanceRegularization>,
+    chan_vese_speed: Option<ChanVeseSpeed>,
+    local_binary_fitting_speed: Option<LocalBinaryFittingSpeed>,
+    /// built at the first use
+    fast_marching: Option<FastMarching>,
+    sparse_field: Option<SparseField>,
+    topology: Option<Topology>,
+    /// built when the reference is set
+    shape_prior: Option<ShapePrior>,
 
     grid_range: GridRange,
 
@@ -105,12 +157,34 @@ pub struct LevelSetMethod<
 
     total_speed: f64,
 
-    stopping_condition: StoppingCondition,
+    stopping_criterion: Box<dyn StoppingCriterion<IntPoint>>,
     zero_count: i32,
     distance_map_generator: DistanceMapGenerator,
     curvature_generator: CurvatureGenerator,
+    zero_level_set_detector: ZeroLevelSetDetector,
 
     upper_distance: i32,
+
+    /// number of iterations performed so far
+    iteration: usize,
+
+    /// whether the narrow band has to be rebuilt at the next speed update
+    resets: bool,
+
+    /// number of rebuilds so far
+    reset_count: usize,
+
+    /// callbacks invoked after each iteration
+    observers: Vec<Box<dyn Observer<IntPoint>>>,
+
+    /// time step of the current iteration
+    time_step: f64,
+
+    /// time steps used so far, one per iteration
+    time_steps: Vec<f64>,
+
+    /// number of sign changes vetoed by the topology preservation, one per iteration
+    vetoes: Vec<usize>,
 }
 impl<
         SpaceSize,
@@ -127,6 +201,13 @@ impl<
         Grid,
         InsideEstimator,
         CurvatureGenerator,
+        ZeroLevelSetDetector,
+        WenoScheme,
+        FastMarching,
+        SparseField,
+        Topology,
+        ShapePrior,
+        DistanceRegularization,
     >
     LevelSetMethod<
         SpaceSize,
@@ -143,13 +224,20 @@ impl<
         Grid,
         InsideEstimator,
         CurvatureGenerator,
+        ZeroLevelSetDetector,
+        WenoScheme,
+        FastMarching,
+        SparseField,
+        Topology,
+        ShapePrior,
+        DistanceRegularization,
     >
 where
     IntPoint: Copy + PointMethod<Type = IntPoint>,
     PointInfo: PointInfoMethod<IntPoint>,
     SpaceSize: SpaceSizeMethod,
     Indexer: IndexerMethod<SpaceSize, IntPoint>,
-    UpwindScheme: UpwindSchemeMethod<Indexer, IntPoint>,
+    UpwindScheme: UpwindSchemeMethod<SpaceSize, Indexer, IntPoint>,
     SpeedFactor: SpeedFactorMethod<Indexer, IntPoint, SpaceSize>,
     GridRange: GridRangeMethod<SpaceSize, Indexer, IntPoint, Self>,
     DistanceMapGenerator: DistanceMapGeneratorMethod<Indexer, DistanceMap, IntPoint, Self>,
@@ -157,10 +245,107 @@ where
     Grid: GridMethod<InitialFront, SpaceSize, Self, IntPoint>,
     InsideEstimator: InsideEstimatorMethod<Grid, IntPoint>,
     CurvatureGenerator: CurvatureGeneratorMethod<Indexer, IntPoint, DoublePoint>,
+    ZeroLevelSetDetector: ZeroLevelSetDetectorMethod<Indexer, IntPoint>,
+    WenoScheme: UpwindSchemeMethod<SpaceSize, Indexer, IntPoint>,
+    FastMarching: FastMarchingMethod<SpaceSize>,
+    SparseField: SparseFieldMethod<SpaceSize, IntPoint>,
+    Topology: TopologyMethod<Indexer, IntPoint>,
+    ShapePrior: ShapePriorMethod<SpaceSize, IntPoint>,
+    DistanceRegularization: DistanceRegularizationMethod<Indexer, IntPoint>,
 {
-    pub fn new(parameters: Parameters, size: Rc<SpaceSize>, gray: Rc<RefCell<Vec<u8>>>) -> Self {
-        let statuses = Rc::new(RefCell::new(vec![Status::Farway; size.get_total()]));
+    /// the combinations of the parameters which this evolves as given.
+    pub fn validate_parameters(parameters: &Parameters) -> Result<(), String> {
+        if parameters.engine == Engine::Morphological {
+            return Err("the morphological engine runs in MorphologicalSnakes2d/3d".to_string());
+        }
+        let min_band_width = match parameters.spatial_scheme {
+            SpatialScheme::FirstOrder => UpwindScheme::MIN_BAND_WIDTH,
+            SpatialScheme::Weno5 => WenoScheme::MIN_BAND_WIDTH,
+        };
+        if parameters.wband < min_band_width {
+            return Err(format!(
+                "wband must be at least {} for {:?}",
+                min_band_width, parameters.spatial_scheme
+            ));
+        }
+        if parameters.engine == Engine::SparseField
+            && (parameters.spatial_scheme != SpatialScheme::FirstOrder
+                || parameters.time_integrator != TimeIntegrator::Euler
+                || parameters.adaptive_time_step)
+        {
+            return Err(
+                "the sparse field engine needs the first-order scheme, forward Euler and a fixed time step"
+                    .to_string(),
+            );
+        }
+        if parameters.speed_mode == SpeedMode::LocalBinaryFitting
+            && (parameters.lbf_sigma <= 0.0 || parameters.lbf_sigma.is_nan())
+        {
+            return Err(format!(
+                "lbf_sigma must be positive: {}",
+                parameters.lbf_sigma
+            ));
+        }
+        Ok(())
+    }
+
+    /// fails with the message of validate_parameters unless it accepts parameters.
+    pub fn new(
+        parameters: Parameters,
+        size: Rc<SpaceSize>,
+        gray: Rc<RefCell<Vec<u8>>>,
+    ) -> Result<Self, String> {
+        Self::validate_parameters(&parameters)?;
         let indexer = Rc::new(Indexer::new(&size));
+        let mut speed_factor = SpeedFactor::new(Rc::clone(&indexer), Rc::clone(&gray));
+        speed_factor.set_edge_stopping(EdgeStopping::from_parameters(&parameters));
+        if parameters.gvf_weight != 0.0 {
+            speed_factor.set_gradient_vector_flow(parameters.gvf_iterations, parameters.gvf_mu);
+        }
+        let local_fitting_image =
+            (parameters.speed_mode == SpeedMode::LocalBinaryFitting).then(|| {
+                Rc::new(LocalFittingImage::new(
+                    &gray.borrow(),
+                    size.get_extents(),
+                    parameters.lbf_sigma,
+                ))
+            });
+        Ok(Self::build(
+            parameters,
+            size,
+            gray,
+            indexer,
+            Rc::new(speed_factor),
+            local_fitting_image,
+        ))
+    }
+
+    /// another level set of the parameters and the image of other, such as a phase of Multiphase.
+    /// it shares the speed factors calculated by the initialization of other and the image terms
+    /// of the local binary fitting instead of calculating them again.
+    pub fn new_sharing_image(other: &Self) -> Self {
+        Self::build(
+            other.parameters.clone(),
+            Rc::clone(&other.size),
+            Rc::clone(&other.input_object),
+            Rc::clone(&other.indexer),
+            Rc::clone(&other.speed_factor),
+            other
+                .local_binary_fitting_speed
+                .as_ref()
+                .map(|speed| speed.get_image()),
+        )
+    }
+
+    fn build(
+        parameters: Parameters,
+        size: Rc<SpaceSize>,
+        gray: Rc<RefCell<Vec<u8>>>,
+        indexer: Rc<Indexer>,
+        speed_factor: Rc<SpeedFactor>,
+        local_fitting_image: Option<Rc<LocalFittingImage>>,
+    ) -> Self {
+        let statuses = Rc::new(RefCell::new(vec![Status::Farway; size.get_total()]));
         let phi = Rc::new(RefCell::new(vec![0.0; size.get_total()]));
         let initial_front = Grid::new();
         Self {
@@ -175,12 +360,36 @@ where
             dphi: Rc::new(RefCell::new(vec![0.0; size.get_total()])),
             speed: Rc::new(RefCell::new(vec![0.0; size.get_total()])),
             statuses: Rc::clone(&statuses),
-            upwind_scheme: UpwindScheme::new(Rc::clone(&indexer), Rc::clone(&phi)),
-            speed_factor: SpeedFactor::new(Rc::clone(&indexer), Rc::clone(&gray)),
+            upwind_scheme: UpwindScheme::new(&size, Rc::clone(&indexer), Rc::clone(&phi)),
+            speed_factor,
+            weno_scheme: (parameters.spatial_scheme == SpatialScheme::Weno5)
+                .then(|| WenoScheme::new(&size, Rc::clone(&indexer), Rc::clone(&phi))),
+            distance_regularization: (parameters.engine == Engine::Drlse)
+                .then(|| DistanceRegularization::new(Rc::clone(&indexer), Rc::clone(&phi))),
+            chan_vese_speed: (parameters.speed_mode == SpeedMode::ChanVese).then(|| {
+                ChanVeseSpeed::new(
+                    Rc::clone(&gray),
+                    parameters.lambda_inside,
+                    parameters.lambda_outside,
+                )
+            }),
+            local_binary_fitting_speed: local_fitting_image.map(|image| {
+                LocalBinaryFittingSpeed::new(
+                    image,
+                    parameters.lambda_inside,
+                    parameters.lambda_outside,
+                )
+            }),
+            fast_marching: None,
+            sparse_field: None,
+            topology: None,
+            shape_prior: None,
             grid_range: GridRange::new(&size),
             input_object: Rc::clone(&gray),
             front: Rc::new(RefCell::new(Vec::<IntPoint>::new())),
             narrow_bands: Vec::<IntPoint>::new(),
+            activated_points: RefCell::new(Vec::<IntPoint>::new()),
+            exclusion: None,
             normals: Vec::<DoublePoint>::new(),
             inside_estimator_for_space_without_edge: InsideEstimator::from_grid(
                 Grid::create_space_without_edge(Rc::clone(&size)),
@@ -190,7 +399,7 @@ where
             ),
             inside_estimator_for_initial_front: InsideEstimator::new(),
             total_speed: 0.0,
-            stopping_condition: StoppingCondition::new(),
+            stopping_criterion: Box::new(StoppingCondition::new()),
             zero_count: 0,
             distance_map_generator: DistanceMapGenerator::new(
                 parameters.wband,
@@ -198,7 +407,18 @@ where
                 Rc::clone(&statuses),
             ),
             curvature_generator: CurvatureGenerator::new(Rc::clone(&indexer), Rc::clone(&phi)),
+            zero_level_set_detector: ZeroLevelSetDetector::new(
+                Rc::clone(&phi),
+                Rc::clone(&indexer),
+            ),
             upper_distance: (parameters.wband - parameters.wreset).pow(2),
+            iteration: 0,
+            resets: false,
+            reset_count: 0,
+            observers: Vec::new(),
+            time_step: parameters.time_step,
+            time_steps: Vec::new(),
+            vetoes: Vec::new(),
         }
     }
 
@@ -215,8 +435,11 @@ where
         self.set_speed_function(true);
     }
 
+    /// the shared speed factors are left as the level set sharing them has calculated them.
     pub fn calculate_speed_factors(&mut self) {
-        self.speed_factor.calculate_all(&self.size);
+        if let Some(speed_factor) = Rc::get_mut(&mut self.speed_factor) {
+            speed_factor.calculate_all(&self.size);
+        }
     }
 
     pub fn initailze_distance_map(&mut self) {
@@ -323,15 +546,52 @@ where
     pub fn set_speed_on_front(&mut self) -> f64 {
         let mut fs = 0.0;
         self.zero_count = 0;
+        // the sparse field updates the region means from the sign changes of each step
+        if let (Some(chan_vese_speed), None) = (self.chan_vese_speed.as_mut(), &self.sparse_field) {
+            chan_vese_speed.update(&self.phi.borrow());
+        }
+        if let Some(local_binary_fitting_speed) = self.local_binary_fitting_speed.as_mut() {
+            let front: Vec<usize> = self
+                .front
+                .borrow()
+                .iter()
+                .map(|p| self.indexer.get(p) as usize)
+                .collect();
+            local_binary_fitting_speed.update(&self.phi.borrow(), &front);
+        }
         for p in self.front.borrow().iter() {
             if self.inside_estimator_for_space_without_edge.is_inside(p) {
                 let i = self.indexer.get(&p) as usize;
-                let mut speed = self.speed_factor.get_value(p);
                 let kappa = self.curvature_generator.generate(p);
                 //p.print();
-                speed *= (self.parameters.constant_speed - self.parameters.gain * kappa);
+                let mut speed = match (&self.chan_vese_speed, &self.local_binary_fitting_speed) {
+                    (Some(chan_vese_speed), _) => {
+                        chan_vese_speed.get_value(i) - self.parameters.gain * kappa
+                    }
+                    (_, Some(local_binary_fitting_speed)) => {
+                        local_binary_fitting_speed.get_value(i) - self.parameters.gain * kappa
+                    }
+                    (None, None) => {
+                        self.speed_factor.get_value(p)
+                            * (self.parameters.constant_speed - self.parameters.gain * kappa)
+                    }
+                };
+                match &self.shape_prior {
+                    Some(shape_prior)
+                        if self.parameters.shape_prior_weight != 0.0
+                            && shape_prior.has_reference() =>
+                    {
+                        // pulls phi toward the reference, outward where the reference is inside
+                        let prior = shape_prior.get_value(p);
+                        speed +=
+                            self.parameters.shape_prior_weight * (self.phi.borrow()[i] - prior);
+                    }
+                    _ => (),
+                }
                 //println!("s:{}", speed);
-                if speed.abs() < self.parameters.speed_threshold {
+                if speed.abs() < self.parameters.speed_threshold
+                    || (speed > 0.0 && self.is_excluded(i))
+                {
                     speed = 0.0;
                     self.zero_count += 1;
                 } else {
@@ -344,6 +604,19 @@ where
         fs
     }
 
+    /// stops the front at the points where exclusion is true,
+    /// which the multiphase evolution updates from the other level sets.
+    pub fn set_exclusion(&mut self, exclusion: Rc<RefCell<Vec<bool>>>) {
+        self.exclusion = Some(exclusion);
+    }
+
+    fn is_excluded(&self, index: usize) -> bool {
+        match &self.exclusion {
+            Some(exclusion) => exclusion.borrow()[index],
+            None => false,
+        }
+    }
+
     pub fn copy_nearest_speed_to_narrow_band(&self, resets: bool) {
         let mut is_considerable = Vec::<Vec<bool>>::new();
         is_considerable.reserve(self.front.borrow().len());
@@ -399,8 +672,12 @@ where
                 let p = center.add(info.get_point());
                 if self.inside_estimator_for_space_with_edge.is_inside(&p) {
                     let index = self.indexer.get(&p) as usize;
-                    if self.statuses.borrow()[index] != Status::Front {
+                    let status = self.statuses.borrow()[index];
+                    if status != Status::Front {
                         if resets {
+                            if status == Status::Farway {
+                                self.activated_points.borrow_mut().push(p);
+                            }
                             if *distance > self.upper_distance {
                                 self.statuses.borrow_mut()[index] = Status::ResetBand;
                             } else {
@@ -456,39 +733,236 @@ where
         )
     }
 
+    /// rebuilds the narrow band from the front points and the activated points
+    /// in the same order as register_to_narrow_band_ without scanning the whole space.
+    fn update_narrow_band(&mut self) {
+        let statuses = self.statuses.borrow();
+        let mut bands: Vec<IntPoint> = self
+            .narrow_bands
+            .iter()
+            .filter(|p| statuses[self.indexer.get(p) as usize] == Status::Front)
+            .copied()
+            .collect();
+        bands.extend(self.front.borrow().iter());
+        bands.append(&mut self.activated_points.borrow_mut());
+        bands.sort_by_key(|p| self.indexer.get(p));
+        bands.dedup_by_key(|p| self.indexer.get(p));
+        self.narrow_bands = bands;
+    }
+
     pub fn set_speed_function(&mut self, resets: bool) -> bool {
+        // the distance map overwrites phi, so the other reinitializations need it beforehand.
+        let phi0 = match self.parameters.reinitialization {
+            Reinitialization::DistanceMap => None,
+            _ if resets => Some(self.phi.borrow().clone()),
+            _ => None,
+        };
+        let old_bands: Vec<usize> = match phi0 {
+            Some(_) => self
+                .narrow_bands
+                .iter()
+                .map(|p| self.indexer.get(p) as usize)
+                .collect(),
+            None => Vec::new(),
+        };
         self.clear_speed_within_narrow_band(resets);
         self.total_speed = self.set_speed_on_front();
         self.copy_nearest_speed_to_narrow_band(resets);
         if resets {
-            self.narrow_bands.clear();
-            self.grid_range.foreach_band(
-                &self.indexer,
-                Rc::clone(&self.statuses),
-                &mut self.narrow_bands,
-                Self::register_to_narrow_band,
-            );
+            self.update_narrow_band();
+        }
+        if let Some(phi0) = phi0 {
+            match self.parameters.reinitialization {
+                Reinitialization::Pde => {
+                    // the pde starts from phi before the reset where it was being evolved
+                    for i in old_bands {
+                        self.phi.borrow_mut()[i] = phi0[i];
+                    }
+                    self.reinitialize_pde(self.parameters.pde_iterations);
+                }
+                _ => self.reinitialize_by_fast_marching(&phi0),
+            }
         }
-        self.stopping_condition.add_total_speed(self.total_speed);
-        self.stopping_condition.is_satisfied()
+        self.check_stopping_criterion()
+    }
+
+    /// iterates phi_t = sign(phi0)(1 - |grad(phi)|) over the narrow band (Sussman et al.)
+    /// with the subcell fix of Russo and Smereka on the points next to the zero-levelset.
+    pub fn reinitialize_pde(&mut self, iterations: usize) {
+        // pseudo time step, stable for the sum of squares Hamiltonian up to three dimensions
+        const PSEUDO_TIME_STEP: f64 = 0.5;
+
+        let phi0 = self.phi.borrow().clone();
+        let mut deltas = vec![0.0; self.narrow_bands.len()];
+        for _ in 0..iterations {
+            for (k, p) in self.narrow_bands.iter().enumerate() {
+                deltas[k] = 0.0;
+                if !self.inside_estimator_for_space_without_edge.is_inside(p) {
+                    continue;
+                }
+                let index = self.indexer.get(p) as usize;
+                let sign = if phi0[index] > 0.0 {
+                    1.0
+                } else if phi0[index] < 0.0 {
+                    -1.0
+                } else {
+                    0.0
+                };
+                deltas[k] = match self.upwind_scheme.calculate_subcell_distance(p, &phi0) {
+                    Some(d) => sign * self.phi.borrow()[index].abs() - d,
+                    None if sign > 0.0 => self.upwind_scheme.calculate(p, Speed::Positive) - 1.0,
+                    None if sign < 0.0 => 1.0 - self.upwind_scheme.calculate(p, Speed::Negative),
+                    None => 0.0,
+                };
+            }
+            let mut phi = self.phi.borrow_mut();
+            for (p, delta) in self.narrow_bands.iter().zip(&deltas) {
+                phi[self.indexer.get(p) as usize] -= PSEUDO_TIME_STEP * delta;
+            }
+        }
+    }
+
+    /// replaces phi on the narrow band with signed distances to the zero-levelset of phi0.
+    pub fn reinitialize_by_fast_marching(&mut self, phi0: &[f64]) {
+        let band: Vec<usize> = self
+            .narrow_bands
+            .iter()
+            .map(|p| self.indexer.get(p) as usize)
+            .collect();
+        let second_order =
+            self.parameters.reinitialization == Reinitialization::FastMarchingSecondOrder;
+        self.fast_marching
+            .get_or_insert_with(|| FastMarching::new(&self.size))
+            .reinitialize(phi0, &mut self.phi.borrow_mut(), &band, second_order);
+    }
+
+    /// replaces the default StoppingCondition.
+    pub fn set_stopping_criterion(&mut self, criterion: Box<dyn StoppingCriterion<IntPoint>>) {
+        self.stopping_criterion = criterion;
+    }
+
+    fn check_stopping_criterion(&mut self) -> bool {
+        let phi = self.phi.borrow();
+        let front = self.front.borrow();
+        let statuses = self.statuses.borrow();
+        let state = IterationState {
+            iteration: self.iteration,
+            phi: &phi,
+            front: &front,
+            statuses: &statuses,
+            total_speed: self.total_speed,
+            zero_count: self.zero_count,
+        };
+        self.stopping_criterion.check(&state)
     }
 
     pub fn propagate_front(&mut self) {
-        for p in &self.narrow_bands {
+        match self.parameters.time_integrator {
+            TimeIntegrator::Euler => self.propagate_front_by_euler(),
+            TimeIntegrator::Rk2 => {
+                let phi_n = self.get_phi_on_narrow_band();
+                self.propagate_front_by_euler();
+                self.reevaluate_speed();
+                self.propagate_front_by_euler();
+                self.blend_phi_on_narrow_band(&phi_n, 1.0 / 2.0);
+            }
+            TimeIntegrator::Rk3 => {
+                let phi_n = self.get_phi_on_narrow_band();
+                self.propagate_front_by_euler();
+                self.reevaluate_speed();
+                self.propagate_front_by_euler();
+                self.blend_phi_on_narrow_band(&phi_n, 3.0 / 4.0);
+                self.reevaluate_speed();
+                self.propagate_front_by_euler();
+                self.blend_phi_on_narrow_band(&phi_n, 1.0 / 3.0);
+            }
+        }
+    }
+
+    /// recalculates the speed from the current phi without touching the labels.
+    fn reevaluate_speed(&mut self) {
+        self.clear_speed_within_narrow_band(false);
+        self.set_speed_on_front();
+        self.copy_nearest_speed_to_narrow_band(false);
+    }
+
+    fn get_phi_on_narrow_band(&self) -> Vec<f64> {
+        let phi = self.phi.borrow();
+        self.narrow_bands
+            .iter()
+            .map(|p| phi[self.indexer.get(p) as usize])
+            .collect()
+    }
+
+    /// phi = weight * phi_n + (1 - weight) * phi over the narrow band.
+    fn blend_phi_on_narrow_band(&self, phi_n: &[f64], weight: f64) {
+        let mut phi = self.phi.borrow_mut();
+        for (p, v) in self.narrow_bands.iter().zip(phi_n) {
+            let index = self.indexer.get(p) as usize;
+            phi[index] = weight * v + (1.0 - weight) * phi[index];
+        }
+    }
+
+    /// speed * |grad(phi)| * time_step at p, with the advection terms if enabled
+    /// and the distance regularization term with the Drlse engine.
+    fn calculate_change(&mut self, p: &IntPoint) -> f64 {
+        let index = self.indexer.get(p) as usize;
+        let speed = self.speed.borrow()[index];
+        let direction = if speed > 0.0 {
+            Some(Speed::Positive)
+        } else if speed < 0.0 {
+            Some(Speed::Negative)
+        } else {
+            None
+        };
+        let upwind_scheme = match (direction, self.weno_scheme.as_mut()) {
+            (Some(d), None) => self.upwind_scheme.calculate(p, d),
+            (Some(d), Some(weno_scheme)) => weno_scheme.calculate(p, d),
+            (None, _) => 0.0,
+        };
+        let mut change = speed * upwind_scheme * self.time_step;
+        if self.parameters.advection_gain != 0.0 {
+            // geodesic active contour: advection by -grad(g) toward edge valleys
+            let gradient = self.speed_factor.get_gradient(p);
+            let gain = self.parameters.advection_gain;
+            let advection = match self.weno_scheme.as_mut() {
+                None => self.upwind_scheme.calculate_advection(p, gradient, gain),
+                Some(weno_scheme) => weno_scheme.calculate_advection(p, gradient, gain),
+            };
+            change += advection * self.time_step;
+        }
+        if let Some(field) = self.speed_factor.get_vector_field() {
+            // advection by gvf_weight along the direction of the gradient vector flow,
+            // which reaches the edges far from the front too
+            let flow = field.get_vector(index);
+            let norm = flow.iter().map(|v| v * v).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                let gain = -self.parameters.gvf_weight / norm;
+                let advection = match self.weno_scheme.as_mut() {
+                    None => self.upwind_scheme.calculate_advection(p, flow, gain),
+                    Some(weno_scheme) => weno_scheme.calculate_advection(p, flow, gain),
+                };
+                change += advection * self.time_step;
+            }
+        }
+        if let Some(distance_regularization) = self.distance_regularization.as_mut() {
+            // phi_t = mu * div(d_p(|grad(phi)|) grad(phi)), while the change is subtracted
+            let regularization = distance_regularization.calculate(p);
+            change -= self.parameters.distance_regularization * regularization * self.time_step;
+        }
+        change
+    }
+
+    fn propagate_front_by_euler(&mut self) {
+        let narrow_bands = std::mem::take(&mut self.narrow_bands);
+        for p in &narrow_bands {
             if self.inside_estimator_for_space_without_edge.is_inside(p) {
                 let index = self.indexer.get(p) as usize;
-                let speed = self.speed.borrow()[index];
-                let mut upwind_scheme = 0.0;
-                if speed > 0.0 {
-                    upwind_scheme = self.upwind_scheme.calculate(p, Speed::Positive);
-                } else if speed < 0.0 {
-                    upwind_scheme = self.upwind_scheme.calculate(p, Speed::Negative);
-                } else {
-                    upwind_scheme = 0.0;
-                }
-                self.dphi.borrow_mut()[index] = speed * upwind_scheme * self.parameters.time_step;
+                let change = self.calculate_change(p);
+                self.dphi.borrow_mut()[index] = change;
             }
         }
+        self.narrow_bands = narrow_bands;
 
         for p in &self.narrow_bands {
             let index = self.indexer.get(p) as usize;
@@ -509,10 +983,453 @@ where
         self.front.borrow_mut().clear();
         for p in &self.narrow_bands {
             if self.inside_estimator_for_space_without_edge.is_inside(p) {
-                let index = self.indexer.get(p);
+                let index = self.indexer.get(p) as usize;
+                let status = self.statuses.borrow()[index];
+                if self.zero_level_set_detector.detect(p) {
+                    if status == Status::ResetBand {
+                        resets = true;
+                    }
+                    self.statuses.borrow_mut()[index] = Status::Front;
+                    self.front.borrow_mut().push(*p);
+                } else if status == Status::Front {
+                    self.statuses.borrow_mut()[index] = Status::Band;
+                }
             }
         }
-        return resets;
+        resets
+    }
+
+    /// prepares phi, the front and the narrow band from the initial front.
+    pub fn initialize(&mut self, initial_front: &InitialFront) {
+        self.initialize_distance_map();
+        self.initialize_along_front(initial_front);
+        self.initialize_over_all(initial_front);
+        self.calculate_speed_factors();
+        self.initialize_narrow_band();
+        match self.parameters.engine {
+            Engine::NarrowBand => (),
+            Engine::Morphological => unreachable!("rejected by validate_parameters"),
+            Engine::SparseField => self.initialize_sparse_field(),
+            Engine::Drlse => self.initialize_drlse_band(),
+        }
+        self.iteration = 0;
+        self.resets = false;
+        self.reset_count = 0;
+        self.time_steps.clear();
+        self.vetoes.clear();
+    }
+
+    /// advances the front by one time step.
+    /// returns true when the stopping condition is satisfied or an observer requests to stop.
+    pub fn step(&mut self) -> bool {
+        self.update_shape_pose();
+        if self.parameters.engine == Engine::SparseField {
+            return self.step_by_sparse_field();
+        }
+        self.time_step = self.calculate_time_step();
+        self.time_steps.push(self.time_step);
+        let phi_n = self
+            .parameters
+            .topology_preservation
+            .then(|| self.get_phi_on_narrow_band());
+        self.propagate_front();
+        if let Some(phi_n) = phi_n {
+            let narrow_bands = std::mem::take(&mut self.narrow_bands);
+            let vetoes = self.preserve_topology(&narrow_bands, &phi_n);
+            self.narrow_bands = narrow_bands;
+            self.vetoes.push(vetoes);
+        }
+        if self.parameters.engine == Engine::Drlse {
+            self.update_band_by_threshold();
+        }
+        let reaches_reset_band = self.create_labels();
+        self.iteration += 1;
+        self.resets = match self.parameters.engine {
+            // phi is kept close to a signed distance by the regularization instead
+            Engine::Drlse => false,
+            _ => reaches_reset_band || self.is_reinitialization_triggered(),
+        };
+        if self.resets {
+            self.reset_count += 1;
+        }
+        let is_satisfied = self.set_speed_function(self.resets);
+        let action = self.notify_observers();
+        is_satisfied || action == ObserverAction::Stop
+    }
+
+    /// starts the band of the Drlse engine from the signed distance to the initial front.
+    pub fn initialize_drlse_band(&mut self) {
+        let phi0 = self.phi.borrow().clone();
+        self.reinitialize_by_fast_marching(&phi0);
+        self.update_band_by_threshold();
+        self.reevaluate_speed();
+    }
+
+    /// keeps the points with |phi| < wband as the narrow band. the points leaving it hold
+    /// phi at +-wband, and the points next to it join with one more than the distance of
+    /// their nearest neighbor in the band, since the front moves by less than a cell per step.
+    fn update_band_by_threshold(&mut self) {
+        let wband = self.parameters.wband as f64;
+        let neighbors = self.distance_map_generator.get_distance_map().get_vec(&1);
+        let mut phi = self.phi.borrow_mut();
+        let mut statuses = self.statuses.borrow_mut();
+        let mut bands = Vec::with_capacity(self.narrow_bands.len());
+        for p in &self.narrow_bands {
+            let index = self.indexer.get(p) as usize;
+            if phi[index].abs() < wband {
+                if statuses[index] != Status::Front {
+                    statuses[index] = Status::Band;
+                }
+                bands.push(*p);
+            } else {
+                phi[index] = wband.copysign(phi[index]);
+                statuses[index] = Status::Farway;
+            }
+        }
+        let mut activated = Vec::new();
+        for p in &bands {
+            let distance = phi[self.indexer.get(p) as usize].abs() + 1.0;
+            for info in neighbors {
+                let q = p.add(info.get_point());
+                if !self.inside_estimator_for_space_with_edge.is_inside(&q) {
+                    continue;
+                }
+                let index = self.indexer.get(&q) as usize;
+                if statuses[index] == Status::Farway && distance < phi[index].abs() {
+                    phi[index] = distance.copysign(phi[index]);
+                    activated.push(q);
+                }
+            }
+        }
+        for q in &activated {
+            statuses[self.indexer.get(q) as usize] = Status::Band;
+        }
+        bands.append(&mut activated);
+        bands.sort_by_key(|p| self.indexer.get(p));
+        bands.dedup_by_key(|p| self.indexer.get(p));
+        self.narrow_bands = bands;
+    }
+
+    /// replaces the narrow band by the layers of the sparse field,
+    /// which start from the signed distance to the initial front.
+    pub fn initialize_sparse_field(&mut self) {
+        let band: Vec<usize> = self
+            .narrow_bands
+            .iter()
+            .map(|p| self.indexer.get(p) as usize)
+            .collect();
+        let phi0 = self.phi.borrow().clone();
+        self.fast_marching
+            .get_or_insert_with(|| FastMarching::new(&self.size))
+            .reinitialize(&phi0, &mut self.phi.borrow_mut(), &band, false);
+        self.sparse_field
+            .get_or_insert_with(|| SparseField::new(&self.size))
+            .initialize(&mut self.phi.borrow_mut(), &band);
+
+        // only the active layer is labeled from now on
+        for i in band {
+            self.statuses.borrow_mut()[i] = Status::Farway;
+        }
+        self.narrow_bands.clear();
+        self.set_front_from_active_layer();
+        if let Some(chan_vese_speed) = self.chan_vese_speed.as_mut() {
+            chan_vese_speed.update(&self.phi.borrow());
+        }
+    }
+
+    fn set_front_from_active_layer(&mut self) {
+        let active_layer = match &self.sparse_field {
+            Some(sparse_field) => sparse_field.get_active_layer(),
+            None => return,
+        };
+        let mut statuses = self.statuses.borrow_mut();
+        let mut front = self.front.borrow_mut();
+        for p in front.iter() {
+            statuses[self.indexer.get(p) as usize] = Status::Farway;
+        }
+        *front = active_layer;
+        for p in front.iter() {
+            statuses[self.indexer.get(p) as usize] = Status::Front;
+        }
+    }
+
+    /// one iteration of the sparse field method, which touches the layers only.
+    fn step_by_sparse_field(&mut self) -> bool {
+        self.total_speed = self.set_speed_on_front();
+        self.time_step = self.parameters.time_step;
+        let front = self.front.borrow().clone();
+        let mut changes = Vec::with_capacity(front.len());
+        for p in &front {
+            if self.inside_estimator_for_space_without_edge.is_inside(p) {
+                changes.push(self.calculate_change(p));
+            } else {
+                changes.push(0.0);
+            }
+        }
+        // the active layer may move by half a cell at most
+        let max_change = changes.iter().fold(0.0, |m: f64, c| m.max(c.abs()));
+        if max_change > 0.5 {
+            let scale = 0.5 / max_change;
+            for c in changes.iter_mut() {
+                *c *= scale;
+            }
+            self.time_step *= scale;
+        }
+        self.time_steps.push(self.time_step);
+        if self.parameters.topology_preservation {
+            let phi_n: Vec<f64> = {
+                let mut phi = self.phi.borrow_mut();
+                front
+                    .iter()
+                    .zip(&changes)
+                    .map(|(p, c)| {
+                        let index = self.indexer.get(p) as usize;
+                        let v = phi[index];
+                        phi[index] -= c;
+                        v
+                    })
+                    .collect()
+            };
+            let vetoes = self.preserve_topology(&front, &phi_n);
+            self.vetoes.push(vetoes);
+            let mut phi = self.phi.borrow_mut();
+            for (k, p) in front.iter().enumerate() {
+                let index = self.indexer.get(p) as usize;
+                changes[k] = phi_n[k] - phi[index];
+                phi[index] = phi_n[k];
+            }
+        }
+        if let Some(sparse_field) = self.sparse_field.as_mut() {
+            sparse_field.update(&mut self.phi.borrow_mut(), &changes);
+            if let Some(chan_vese_speed) = self.chan_vese_speed.as_mut() {
+                // phi changes its sign only on the layers, where the old active layer lies
+                for k in -2..=2 {
+                    chan_vese_speed.update_at(&self.phi.borrow(), sparse_field.get_layer(k));
+                }
+            }
+        }
+        self.set_front_from_active_layer();
+        self.iteration += 1;
+        let is_satisfied = self.check_stopping_criterion();
+        let action = self.notify_observers();
+        is_satisfied || action == ObserverAction::Stop
+    }
+
+    /// takes back the sign changes of phi from phi_n at the points which are not simple,
+    /// examining the points one by one. returns the number of vetoed points.
+    pub fn preserve_topology(&mut self, points: &[IntPoint], phi_n: &[f64]) -> usize {
+        // distance from the zero-levelset given to a vetoed point
+        const EPSILON: f64 = 1.0e-6;
+
+        let mut changes = Vec::new();
+        {
+            let mut phi = self.phi.borrow_mut();
+            for (k, p) in points.iter().enumerate() {
+                let index = self.indexer.get(p) as usize;
+                if (phi[index] < 0.0) != (phi_n[k] < 0.0) {
+                    changes.push((k, phi[index]));
+                    phi[index] = phi_n[k];
+                }
+            }
+        }
+        let topology = self
+            .topology
+            .get_or_insert_with(|| Topology::new(Rc::clone(&self.phi), Rc::clone(&self.indexer)));
+        let mut vetoes = 0;
+        for (k, value) in changes {
+            let p = &points[k];
+            let is_simple = topology.is_simple(p);
+            let index = self.indexer.get(p) as usize;
+            self.phi.borrow_mut()[index] = if is_simple {
+                value
+            } else if phi_n[k] < 0.0 {
+                vetoes += 1;
+                -EPSILON
+            } else {
+                vetoes += 1;
+                EPSILON
+            };
+        }
+        vetoes
+    }
+
+    /// the speed is pulled toward the reference with the weight shape_prior_weight.
+    /// fails with the message of ShapePriorMethod::set_reference.
+    pub fn set_shape_prior(&mut self, reference: ReferenceMap) -> Result<(), String> {
+        self.shape_prior
+            .get_or_insert_with(|| ShapePrior::new(&self.size))
+            .set_reference(reference)
+    }
+
+    /// None until set_shape_prior is called.
+    pub fn get_shape_prior(&mut self) -> Option<&mut ShapePrior> {
+        self.shape_prior.as_mut()
+    }
+
+    /// re-estimates the pose of the reference every shape_prior_pose_interval iterations.
+    fn update_shape_pose(&mut self) {
+        let interval = self.parameters.shape_prior_pose_interval;
+        match self.shape_prior.as_mut() {
+            Some(shape_prior) if interval > 0 && self.iteration.is_multiple_of(interval) => {
+                shape_prior.estimate_pose(&self.phi.borrow());
+            }
+            _ => (),
+        }
+    }
+
+    pub fn get_vetoes(&self) -> &Vec<usize> {
+        &self.vetoes
+    }
+
+    /// None unless the sparse field engine has been initialized.
+    pub fn get_sparse_field(&self) -> Option<&SparseField> {
+        self.sparse_field.as_ref()
+    }
+
+    /// the largest time step satisfying the CFL condition for the current speed,
+    /// bounded by Parameters::time_step. returns time_step as is unless adaptive_time_step is set.
+    pub fn calculate_time_step(&self) -> f64 {
+        if !self.parameters.adaptive_time_step {
+            return self.parameters.time_step;
+        }
+        // the unit normal and the unit direction of the gradient vector flow
+        // have at most sqrt(dimension) as the sum of their components
+        let sqrt_dimension = (self.size.get_dimension() as f64).sqrt();
+        let speed = self.speed.borrow();
+        let mut max_rate = 0.0;
+        for p in &self.narrow_bands {
+            let mut rate = speed[self.indexer.get(p) as usize].abs() * sqrt_dimension;
+            if self.parameters.advection_gain != 0.0 {
+                rate += self
+                    .speed_factor
+                    .get_gradient(p)
+                    .iter()
+                    .map(|g| (self.parameters.advection_gain * g).abs())
+                    .sum::<f64>();
+            }
+            if self.speed_factor.get_vector_field().is_some() {
+                rate += self.parameters.gvf_weight.abs() * sqrt_dimension;
+            }
+            if rate > max_rate {
+                max_rate = rate;
+            }
+        }
+        // the curvature term is diffusive and needs dt <= 1 / (2 * dimension * gain),
+        // and so is the distance regularization term whose diffusion rate is at most 1.
+        let mut diffusion = self.parameters.gain.abs();
+        if self.parameters.engine == Engine::Drlse {
+            diffusion += self.parameters.distance_regularization.abs();
+        }
+        max_rate += 2.0 * self.size.get_dimension() as f64 * diffusion;
+        if max_rate > 0.0 {
+            f64::min(
+                self.parameters.time_step,
+                self.parameters.cfl_number / max_rate,
+            )
+        } else {
+            self.parameters.time_step
+        }
+    }
+
+    /// whether the trigger other than the ResetBand requests a rebuild at the current iteration.
+    pub fn is_reinitialization_triggered(&mut self) -> bool {
+        match self.parameters.reinitialization_trigger {
+            ReinitializationTrigger::ResetBand => false,
+            ReinitializationTrigger::Interval => {
+                let interval = self.parameters.reinitialization_interval;
+                interval > 0 && self.iteration.is_multiple_of(interval)
+            }
+            ReinitializationTrigger::GradientDeviation => {
+                self.calculate_gradient_deviation() > self.parameters.gradient_deviation_tolerance
+            }
+        }
+    }
+
+    /// mean of ||grad(phi)| - 1| over the narrow band.
+    pub fn calculate_gradient_deviation(&mut self) -> f64 {
+        let mut sum = 0.0;
+        let mut count = 0;
+        for p in &self.narrow_bands {
+            if self.inside_estimator_for_space_without_edge.is_inside(p) {
+                let norm = self.upwind_scheme.calculate(p, Speed::Positive);
+                sum += (norm - 1.0).abs();
+                count += 1;
+            }
+        }
+        if count > 0 {
+            sum / count as f64
+        } else {
+            0.0
+        }
+    }
+
+    pub fn get_reset_count(&self) -> usize {
+        self.reset_count
+    }
+
+    pub fn get_time_steps(&self) -> &[f64] {
+        &self.time_steps
+    }
+
+    /// the gradient vector flow driving the front, None unless gvf_weight is set.
+    pub fn get_vector_field(&self) -> Option<&VectorField> {
+        self.speed_factor.get_vector_field()
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn Observer<IntPoint>>) {
+        self.observers.push(observer);
+    }
+
+    /// every observer is notified even if one of them has requested to stop.
+    fn notify_observers(&mut self) -> ObserverAction {
+        let phi = self.phi.borrow();
+        let front = self.front.borrow();
+        let statuses = self.statuses.borrow();
+        let state = IterationState {
+            iteration: self.iteration,
+            phi: &phi,
+            front: &front,
+            statuses: &statuses,
+            total_speed: self.total_speed,
+            zero_count: self.zero_count,
+        };
+        let mut action = ObserverAction::Continue;
+        for observer in self.observers.iter_mut() {
+            if observer.observe(&state) == ObserverAction::Stop {
+                action = ObserverAction::Stop;
+            }
+        }
+        action
+    }
+
+    /// evolves the front until the stopping condition is satisfied
+    /// or max_iterations steps have been performed.
+    pub fn run(&mut self, max_iterations: usize) -> EvolutionResult<IntPoint> {
+        while self.iteration < max_iterations {
+            if self.step() {
+                break;
+            }
+        }
+        self.make_result()
+    }
+
+    pub fn make_result(&self) -> EvolutionResult<IntPoint> {
+        EvolutionResult {
+            phi: self.phi.borrow().clone(),
+            front: self.front.borrow().clone(),
+            iterations: self.iteration,
+            time_steps: self.time_steps.clone(),
+            resets: self.reset_count,
+            vetoes: self.vetoes.clone(),
+        }
+    }
+
+    pub fn get_iteration(&self) -> usize {
+        self.iteration
+    }
+
+    pub fn get_resets(&self) -> bool {
+        self.resets
     }
 
     pub fn get_input_object(&self) -> Rc<RefCell<Vec<u8>>> {
@@ -535,6 +1452,13 @@ pub type LevelSetMethod2d = LevelSetMethod<
     Grid2d,
     InsideEstimator2d,
     CurvatureGenerator2d,
+    ZeroLevelSetDetector2d,
+    WenoScheme2d,
+    FastMarching2d,
+    SparseField2d,
+    Topology2d,
+    ShapePrior2d,
+    DistanceRegularization2d,
 >;
 
 pub type LevelSetMethod3d = LevelSetMethod<
@@ -552,4 +1476,11 @@ pub type LevelSetMethod3d = LevelSetMethod<
     Grid3d,
     InsideEstimator3d,
     CurvatureGenerator3d,
+    ZeroLevelSetDetector3d,
+    WenoScheme3d,
+    FastMarching3d,
+    SparseField3d,
+    Topology3d,
+    ShapePrior3d,
+    DistanceRegularization3d,
 >;
@@ -0,0 +1,522 @@
+use crate::core::evolution_result::EvolutionResult;
+use crate::core::indexer::{FlatIndexer, Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::initial_front::{InitialFront2d, InitialFront3d};
+use crate::core::parameters::{Parameters, SpeedMode};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d, SpaceSizeMethod};
+use crate::core::speed_factor::{EdgeStopping, SpeedFactor2d, SpeedFactor3d, SpeedFactorMethod};
+use crate::core::stopping_condition::StoppingCondition;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// morphological active contours (Marquez-Neila et al.) on a binary level set.
+/// the Edge speed mode runs the morphological GAC with the speed factors as the edge indicator
+/// and constant_speed as the balloon force, the ChanVese speed mode runs the morphological ACWE.
+/// the local binary fitting has no morphological counterpart and is rejected by new.
+/// the curvature term is morphological_smoothing applications of SI o IS or IS o SI in turn.
+/// phi of the result is -1 inside and 1 outside, and the front is the inside points
+/// next to the outside along an axis.
+pub trait MorphologicalSnakesMethod<SpaceSize, InitialFront, IntPoint> {
+    fn new(
+        parameters: Parameters,
+        size: Rc<SpaceSize>,
+        gray: Rc<RefCell<Vec<u8>>>,
+    ) -> Result<Self, String>
+    where
+        Self: Sized;
+    /// sets the inside of the initial front to 1 and the other points to 0.
+    fn initialize(&mut self, initial_front: &InitialFront);
+    /// returns true when the number of changed points has been the same three times in a row.
+    fn step(&mut self) -> bool;
+    fn run(&mut self, max_iterations: usize) -> EvolutionResult<IntPoint>;
+    /// 1 inside and 0 outside
+    fn get_levelset(&self) -> &[u8];
+}
+
+struct MorphologicalSnakes {
+    parameters: Parameters,
+    indexer: FlatIndexer,
+    gray: Vec<f64>,
+    /// edge indicator and its gradient, one value per axis, only for the morphological GAC
+    factors: Vec<f64>,
+    gradients: Vec<f64>,
+    levelset: Vec<u8>,
+    /// sums of the intensities and numbers of the points outside and inside
+    sums: [f64; 2],
+    counts: [usize; 2],
+    /// false on the edge of the space, where the level set is kept
+    is_interior: Vec<bool>,
+    /// interior points whose neighborhood is not uniform, the only ones the operators change
+    band: Vec<usize>,
+    is_in_band: Vec<bool>,
+    /// points changed in the current iteration with their values before it
+    touched: Vec<(usize, u8)>,
+    is_touched: Vec<bool>,
+    /// offsets of the 3x3(x3) neighborhood
+    neighborhood: Vec<isize>,
+    /// offsets of the lines in 2d or the planes in 3d through the center of the neighborhood
+    elements: Vec<Vec<isize>>,
+    /// SI o IS is applied next, otherwise IS o SI
+    is_sup_inf_next: bool,
+    condition: StoppingCondition,
+    iteration: usize,
+}
+
+impl MorphologicalSnakes {
+    const LINES: [fn(&[i32]) -> bool; 4] = [
+        |o| o[1] == 0,
+        |o| o[0] == 0,
+        |o| o[0] == o[1],
+        |o| o[0] == -o[1],
+    ];
+    const PLANES: [fn(&[i32]) -> bool; 9] = [
+        |o| o[0] == 0,
+        |o| o[1] == 0,
+        |o| o[2] == 0,
+        |o| o[0] == o[1],
+        |o| o[0] == -o[1],
+        |o| o[0] == o[2],
+        |o| o[0] == -o[2],
+        |o| o[1] == o[2],
+        |o| o[1] == -o[2],
+    ];
+
+    fn validate_parameters(parameters: &Parameters) -> Result<(), String> {
+        match parameters.speed_mode {
+            SpeedMode::Edge | SpeedMode::ChanVese => Ok(()),
+            SpeedMode::LocalBinaryFitting => Err(
+                "the morphological engine supports the edge and chan-vese speed modes".to_string(),
+            ),
+        }
+    }
+
+    fn new(parameters: Parameters, extents: Vec<i32>, gray: &[u8]) -> Result<Self, String> {
+        Self::validate_parameters(&parameters)?;
+        let indexer = FlatIndexer::new(&extents);
+        let total = indexer.get_total();
+        let is_interior = (0..total)
+            .map(|i| {
+                extents.iter().enumerate().all(|(axis, e)| {
+                    let c = indexer.coordinate(i, axis);
+                    c > 0 && c < e - 1
+                })
+            })
+            .collect();
+
+        // all the offsets of the neighborhood with x varying fastest
+        let offsets: Vec<Vec<i32>> = (0..3usize.pow(extents.len() as u32))
+            .map(|n| {
+                (0..extents.len())
+                    .map(|axis| ((n / 3usize.pow(axis as u32)) % 3) as i32 - 1)
+                    .collect()
+            })
+            .collect();
+        let to_index = |o: &Vec<i32>| -> isize {
+            o.iter()
+                .zip(indexer.get_strides())
+                .map(|(c, s)| *c as isize * *s as isize)
+                .sum()
+        };
+        let neighborhood = offsets.iter().map(to_index).collect();
+        let is_on_element: &[fn(&[i32]) -> bool] = if extents.len() == 2 {
+            &Self::LINES
+        } else {
+            &Self::PLANES
+        };
+        let elements = is_on_element
+            .iter()
+            .map(|f| offsets.iter().filter(|o| f(o)).map(to_index).collect())
+            .collect();
+
+        Ok(Self {
+            parameters,
+            indexer,
+            gray: gray.iter().map(|v| *v as f64).collect(),
+            factors: Vec::new(),
+            gradients: Vec::new(),
+            levelset: vec![0; total],
+            sums: [0.0; 2],
+            counts: [0; 2],
+            is_interior,
+            band: Vec::new(),
+            is_in_band: vec![false; total],
+            touched: Vec::new(),
+            is_touched: vec![false; total],
+            neighborhood,
+            elements,
+            is_sup_inf_next: true,
+            condition: StoppingCondition::new(),
+            iteration: 0,
+        })
+    }
+
+    fn is_gac(&self) -> bool {
+        self.parameters.speed_mode == SpeedMode::Edge
+    }
+
+    fn initialize(&mut self, lower: &[i32], upper: &[i32]) {
+        for (i, u) in self.levelset.iter_mut().enumerate() {
+            let is_inside = (0..lower.len()).all(|axis| {
+                let c = self.indexer.coordinate(i, axis);
+                lower[axis] < c && c < upper[axis]
+            });
+            *u = is_inside as u8;
+        }
+        self.sums = [0.0; 2];
+        self.counts = [0; 2];
+        for (u, v) in self.levelset.iter().zip(&self.gray) {
+            self.sums[*u as usize] += v;
+            self.counts[*u as usize] += 1;
+        }
+        self.is_in_band.fill(false);
+        self.band = (0..self.levelset.len())
+            .filter(|i| self.is_interior[*i] && !self.is_uniform(*i))
+            .collect();
+        for i in &self.band {
+            self.is_in_band[*i] = true;
+        }
+        self.is_sup_inf_next = true;
+        self.condition = StoppingCondition::new();
+        self.iteration = 0;
+    }
+
+    /// central difference of the level set along the axis of stride.
+    fn derivative(&self, i: usize, stride: usize) -> f64 {
+        0.5 * (self.levelset[i + stride] as f64 - self.levelset[i - stride] as f64)
+    }
+
+    /// true when the 3x3(x3) neighborhood of i has the same value,
+    /// where none of the operators changes the level set.
+    fn is_uniform(&self, i: usize) -> bool {
+        let u = self.levelset[i];
+        self.neighborhood
+            .iter()
+            .all(|o| self.levelset[(i as isize + o) as usize] == u)
+    }
+
+    /// sets the values computed from the level set before the operator
+    /// and moves the band along with the changed points.
+    fn apply(&mut self, updates: Vec<(usize, u8)>) {
+        let mut candidates = std::mem::take(&mut self.band);
+        for (i, u) in updates {
+            let old = self.levelset[i];
+            if old == u {
+                continue;
+            }
+            if !self.is_touched[i] {
+                self.is_touched[i] = true;
+                self.touched.push((i, old));
+            }
+            self.sums[old as usize] -= self.gray[i];
+            self.counts[old as usize] -= 1;
+            self.sums[u as usize] += self.gray[i];
+            self.counts[u as usize] += 1;
+            self.levelset[i] = u;
+            for o in &self.neighborhood {
+                let j = (i as isize + o) as usize;
+                if self.is_interior[j] && !self.is_in_band[j] {
+                    self.is_in_band[j] = true;
+                    candidates.push(j);
+                }
+            }
+        }
+        for i in candidates {
+            if self.is_uniform(i) {
+                self.is_in_band[i] = false;
+            } else {
+                self.band.push(i);
+            }
+        }
+    }
+
+    fn step(&mut self) -> bool {
+        if self.is_gac() {
+            self.balloon();
+            self.attract();
+        } else {
+            self.fit_regions();
+        }
+        for _ in 0..self.parameters.morphological_smoothing {
+            self.smooth();
+        }
+        let mut changed = 0;
+        for (i, old) in std::mem::take(&mut self.touched) {
+            self.is_touched[i] = false;
+            if self.levelset[i] != old {
+                changed += 1;
+            }
+        }
+        self.iteration += 1;
+        self.condition.add_total_speed(changed as f64);
+        self.condition.is_satisfied()
+    }
+
+    /// dilates the level set for a positive constant_speed and erodes it for a negative one
+    /// where the edge indicator times |constant_speed| exceeds morphological_threshold.
+    fn balloon(&mut self) {
+        let balloon = self.parameters.constant_speed;
+        if balloon == 0.0 {
+            return;
+        }
+        let updates = self
+            .band
+            .iter()
+            .filter(|i| self.factors[**i] * balloon.abs() > self.parameters.morphological_threshold)
+            .map(|i| {
+                let values = self
+                    .neighborhood
+                    .iter()
+                    .map(|o| self.levelset[(*i as isize + o) as usize]);
+                let u = if balloon > 0.0 {
+                    values.max().unwrap()
+                } else {
+                    values.min().unwrap()
+                };
+                (*i, u)
+            })
+            .collect();
+        self.apply(updates);
+    }
+
+    /// moves the front toward the valleys of the edge indicator.
+    fn attract(&mut self) {
+        let dimension = self.indexer.get_extents().len();
+        let mut updates = Vec::new();
+        for i in &self.band {
+            let g = &self.gradients[(dimension * i)..(dimension * (i + 1))];
+            let product: f64 = self
+                .indexer
+                .get_strides()
+                .iter()
+                .zip(g)
+                .map(|(s, d)| self.derivative(*i, *s) * d)
+                .sum();
+            if product > 0.0 {
+                updates.push((*i, 1));
+            } else if product < 0.0 {
+                updates.push((*i, 0));
+            }
+        }
+        self.apply(updates);
+    }
+
+    /// moves the front by the Chan-Vese energy with the means of the inside and the outside.
+    fn fit_regions(&mut self) {
+        let outside = self.sums[0] / self.counts[0].max(1) as f64;
+        let inside = self.sums[1] / self.counts[1].max(1) as f64;
+        let mut updates = Vec::new();
+        for i in &self.band {
+            if self
+                .indexer
+                .get_strides()
+                .iter()
+                .all(|s| self.derivative(*i, *s) == 0.0)
+            {
+                continue;
+            }
+            let v = self.gray[*i];
+            let energy = self.parameters.lambda_inside * (v - inside).powi(2)
+                - self.parameters.lambda_outside * (v - outside).powi(2);
+            if energy < 0.0 {
+                updates.push((*i, 1));
+            } else if energy > 0.0 {
+                updates.push((*i, 0));
+            }
+        }
+        self.apply(updates);
+    }
+
+    /// applies SI o IS and IS o SI in turn.
+    fn smooth(&mut self) {
+        let is_sup_inf = self.is_sup_inf_next;
+        self.is_sup_inf_next = !is_sup_inf;
+        self.apply_line_operator(!is_sup_inf);
+        self.apply_line_operator(is_sup_inf);
+    }
+
+    /// SI(u) = max over the elements of min over the element, and IS(u) its dual.
+    fn apply_line_operator(&mut self, is_sup_inf: bool) {
+        let updates = self
+            .band
+            .iter()
+            .map(|i| {
+                let values = self.elements.iter().map(|element| {
+                    let on_element = element
+                        .iter()
+                        .map(|o| self.levelset[(*i as isize + o) as usize]);
+                    if is_sup_inf {
+                        on_element.min().unwrap()
+                    } else {
+                        on_element.max().unwrap()
+                    }
+                });
+                let u = if is_sup_inf {
+                    values.max().unwrap()
+                } else {
+                    values.min().unwrap()
+                };
+                (*i, u)
+            })
+            .collect();
+        self.apply(updates);
+    }
+
+    fn get_phi(&self) -> Vec<f64> {
+        self.levelset
+            .iter()
+            .map(|u| if *u == 1 { -1.0 } else { 1.0 })
+            .collect()
+    }
+
+    /// indices of the inside points next to the outside along an axis in ascending order.
+    fn get_front(&self) -> Vec<usize> {
+        let mut front: Vec<usize> = self
+            .band
+            .iter()
+            .filter(|i| {
+                self.levelset[**i] == 1
+                    && self
+                        .indexer
+                        .get_strides()
+                        .iter()
+                        .any(|s| self.levelset[**i - s] == 0 || self.levelset[**i + s] == 0)
+            })
+            .cloned()
+            .collect();
+        front.sort_unstable();
+        front
+    }
+
+    fn make_result<IntPoint>(
+        &self,
+        to_point: impl Fn(usize) -> IntPoint,
+    ) -> EvolutionResult<IntPoint> {
+        EvolutionResult {
+            phi: self.get_phi(),
+            front: self.get_front().into_iter().map(to_point).collect(),
+            iterations: self.iteration,
+            // the operators move the front by whole points
+            time_steps: vec![1.0; self.iteration],
+            resets: 0,
+            vetoes: Vec::new(),
+        }
+    }
+}
+
+pub struct MorphologicalSnakes2d {
+    snakes: MorphologicalSnakes,
+}
+
+impl MorphologicalSnakesMethod<SpaceSize2d, InitialFront2d, Point2d<i32>>
+    for MorphologicalSnakes2d
+{
+    fn new(
+        parameters: Parameters,
+        size: Rc<SpaceSize2d>,
+        gray: Rc<RefCell<Vec<u8>>>,
+    ) -> Result<Self, String> {
+        let mut snakes = MorphologicalSnakes::new(parameters, size.get_extents(), &gray.borrow())?;
+        if snakes.is_gac() {
+            let indexer = Rc::new(Indexer2d::new(&size));
+            let mut speed_factor = SpeedFactor2d::new(Rc::clone(&indexer), Rc::clone(&gray));
+            speed_factor.set_edge_stopping(EdgeStopping::from_parameters(&snakes.parameters));
+            speed_factor.calculate_all(&size);
+            for j in 0..size.height {
+                for i in 0..size.width {
+                    let p = Point2d::<i32>::new(i, j);
+                    snakes.factors.push(speed_factor.get_value(&p));
+                    snakes
+                        .gradients
+                        .extend_from_slice(speed_factor.get_gradient(&p));
+                }
+            }
+        }
+        Ok(Self { snakes })
+    }
+
+    fn initialize(&mut self, initial_front: &InitialFront2d) {
+        let [a, b] = &initial_front.vertices;
+        self.snakes
+            .initialize(&[a.x.min(b.x), a.y.min(b.y)], &[a.x.max(b.x), a.y.max(b.y)]);
+    }
+
+    fn step(&mut self) -> bool {
+        self.snakes.step()
+    }
+
+    fn run(&mut self, max_iterations: usize) -> EvolutionResult<Point2d<i32>> {
+        while self.snakes.iteration < max_iterations {
+            if self.step() {
+                break;
+            }
+        }
+        let c = |i, axis| self.snakes.indexer.coordinate(i, axis);
+        self.snakes
+            .make_result(|i| Point2d::<i32>::new(c(i, 0), c(i, 1)))
+    }
+
+    fn get_levelset(&self) -> &[u8] {
+        &self.snakes.levelset
+    }
+}
+
+pub struct MorphologicalSnakes3d {
+    snakes: MorphologicalSnakes,
+}
+
+impl MorphologicalSnakesMethod<SpaceSize3d, InitialFront3d, Point3d<i32>>
+    for MorphologicalSnakes3d
+{
+    fn new(
+        parameters: Parameters,
+        size: Rc<SpaceSize3d>,
+        gray: Rc<RefCell<Vec<u8>>>,
+    ) -> Result<Self, String> {
+        let mut snakes = MorphologicalSnakes::new(parameters, size.get_extents(), &gray.borrow())?;
+        if snakes.is_gac() {
+            let indexer = Rc::new(Indexer3d::new(&size));
+            let mut speed_factor = SpeedFactor3d::new(Rc::clone(&indexer), Rc::clone(&gray));
+            speed_factor.set_edge_stopping(EdgeStopping::from_parameters(&snakes.parameters));
+            speed_factor.calculate_all(&size);
+            for k in 0..size.depth {
+                for j in 0..size.height {
+                    for i in 0..size.width {
+                        let p = Point3d::<i32>::new(i, j, k);
+                        snakes.factors.push(speed_factor.get_value(&p));
+                        snakes
+                            .gradients
+                            .extend_from_slice(speed_factor.get_gradient(&p));
+                    }
+                }
+            }
+        }
+        Ok(Self { snakes })
+    }
+
+    fn initialize(&mut self, initial_front: &InitialFront3d) {
+        let [a, b] = &initial_front.vertices;
+        self.snakes.initialize(
+            &[a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)],
+            &[a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)],
+        );
+    }
+
+    fn step(&mut self) -> bool {
+        self.snakes.step()
+    }
+
+    fn run(&mut self, max_iterations: usize) -> EvolutionResult<Point3d<i32>> {
+        while self.snakes.iteration < max_iterations {
+            if self.step() {
+                break;
+            }
+        }
+        let c = |i, axis| self.snakes.indexer.coordinate(i, axis);
+        self.snakes
+            .make_result(|i| Point3d::<i32>::new(c(i, 0), c(i, 1), c(i, 2)))
+    }
+
+    fn get_levelset(&self) -> &[u8] {
+        &self.snakes.levelset
+    }
+}
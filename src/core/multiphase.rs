@@ -0,0 +1,251 @@
+use crate::core::evolution_result::MultiphaseResult;
+use crate::core::indexer::FlatIndexer;
+use crate::core::initial_front::{InitialFront2d, InitialFront3d};
+use crate::core::level_set_method::{LevelSetMethod2d, LevelSetMethod3d};
+use crate::core::parameters::Parameters;
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// several level sets competing for the same image.
+/// each phase evolves its own phi, and its front stops where another phase owns the image.
+pub trait MultiphaseMethod<SpaceSize, InitialFront, IntPoint> {
+    fn new(parameters: Parameters, size: Rc<SpaceSize>, gray: Rc<RefCell<Vec<u8>>>) -> Self;
+    /// creates a phase for each initial front, MAX_PHASES at most.
+    /// fails if there are more or the parameters are rejected by LevelSetMethod.
+    fn initialize(&mut self, initial_fronts: &[InitialFront]) -> Result<(), String>;
+    /// steps every phase which has not stopped yet, true when all of them have stopped.
+    fn step(&mut self) -> bool;
+    fn run(&mut self, max_iterations: usize) -> MultiphaseResult<IntPoint>;
+    /// 0 for the background and k + 1 for the region owned by the phase k
+    fn get_labels(&self) -> Vec<u8>;
+    fn get_phase_count(&self) -> usize;
+}
+
+/// the labels of u8 tell apart this many phases at most.
+pub const MAX_PHASES: usize = u8::MAX as usize;
+
+/// assigns each point to the phase of the smallest negative phi.
+pub fn make_labels(phis: &[Rc<RefCell<Vec<f64>>>], total: usize) -> Vec<u8> {
+    let mut labels = vec![0u8; total];
+    let mut minimums = vec![0.0; total];
+    for (k, phi) in phis.iter().enumerate() {
+        for (i, v) in phi.borrow().iter().enumerate() {
+            if *v < minimums[i] {
+                minimums[i] = *v;
+                labels[i] = (k + 1) as u8;
+            }
+        }
+    }
+    labels
+}
+
+/// marks the points owned by a phase other than label and their axis neighbors.
+pub fn make_exclusion(labels: &[u8], label: u8, extents: &[i32], exclusion: &mut [bool]) {
+    exclusion.fill(false);
+    let indexer = FlatIndexer::new(extents);
+    for (i, l) in labels.iter().enumerate() {
+        if *l == 0 || *l == label {
+            continue;
+        }
+        exclusion[i] = true;
+        for j in indexer.neighbors(i).into_iter().flatten() {
+            exclusion[j] = true;
+        }
+    }
+}
+
+pub struct Multiphase2d {
+    parameters: Parameters,
+    size: Rc<SpaceSize2d>,
+    gray: Rc<RefCell<Vec<u8>>>,
+    phases: Vec<LevelSetMethod2d>,
+    exclusions: Vec<Rc<RefCell<Vec<bool>>>>,
+    stopped: Vec<bool>,
+    iteration: usize,
+}
+
+impl Multiphase2d {
+    fn update_exclusion(&self, labels: &[u8], k: usize) {
+        make_exclusion(
+            labels,
+            (k + 1) as u8,
+            &[self.size.width, self.size.height],
+            &mut self.exclusions[k].borrow_mut(),
+        );
+    }
+}
+
+impl MultiphaseMethod<SpaceSize2d, InitialFront2d, Point2d<i32>> for Multiphase2d {
+    fn new(parameters: Parameters, size: Rc<SpaceSize2d>, gray: Rc<RefCell<Vec<u8>>>) -> Self {
+        Self {
+            parameters,
+            size,
+            gray,
+            phases: Vec::new(),
+            exclusions: Vec::new(),
+            stopped: Vec::new(),
+            iteration: 0,
+        }
+    }
+
+    fn initialize(&mut self, initial_fronts: &[InitialFront2d]) -> Result<(), String> {
+        if initial_fronts.len() > MAX_PHASES {
+            return Err(format!("at most {} phases", MAX_PHASES));
+        }
+        self.phases.clear();
+        self.exclusions.clear();
+        for initial_front in initial_fronts {
+            let exclusion = Rc::new(RefCell::new(vec![false; self.size.total as usize]));
+            // the first phase calculates the terms of the image for all of them
+            let mut lsm = match self.phases.first() {
+                Some(first) => LevelSetMethod2d::new_sharing_image(first),
+                None => LevelSetMethod2d::new(
+                    self.parameters.clone(),
+                    Rc::clone(&self.size),
+                    Rc::clone(&self.gray),
+                )?,
+            };
+            lsm.set_exclusion(Rc::clone(&exclusion));
+            lsm.initialize(initial_front);
+            self.phases.push(lsm);
+            self.exclusions.push(exclusion);
+        }
+        self.stopped = vec![false; self.phases.len()];
+        self.iteration = 0;
+        Ok(())
+    }
+
+    fn step(&mut self) -> bool {
+        // every phase is bounded by the labels at the beginning of the step
+        let labels = self.get_labels();
+        for k in 0..self.phases.len() {
+            if !self.stopped[k] {
+                self.update_exclusion(&labels, k);
+                self.stopped[k] = self.phases[k].step();
+            }
+        }
+        self.iteration += 1;
+        self.stopped.iter().all(|s| *s)
+    }
+
+    fn run(&mut self, max_iterations: usize) -> MultiphaseResult<Point2d<i32>> {
+        while self.iteration < max_iterations {
+            if self.step() {
+                break;
+            }
+        }
+        MultiphaseResult {
+            labels: self.get_labels(),
+            phases: self.phases.iter().map(|lsm| lsm.make_result()).collect(),
+            iterations: self.iteration,
+        }
+    }
+
+    fn get_labels(&self) -> Vec<u8> {
+        let phis: Vec<_> = self.phases.iter().map(|lsm| lsm.get_phi()).collect();
+        make_labels(&phis, self.size.total as usize)
+    }
+
+    fn get_phase_count(&self) -> usize {
+        self.phases.len()
+    }
+}
+
+pub struct Multiphase3d {
+    parameters: Parameters,
+    size: Rc<SpaceSize3d>,
+    gray: Rc<RefCell<Vec<u8>>>,
+    phases: Vec<LevelSetMethod3d>,
+    exclusions: Vec<Rc<RefCell<Vec<bool>>>>,
+    stopped: Vec<bool>,
+    iteration: usize,
+}
+
+impl Multiphase3d {
+    fn update_exclusion(&self, labels: &[u8], k: usize) {
+        make_exclusion(
+            labels,
+            (k + 1) as u8,
+            &[self.size.width, self.size.height, self.size.depth],
+            &mut self.exclusions[k].borrow_mut(),
+        );
+    }
+}
+
+impl MultiphaseMethod<SpaceSize3d, InitialFront3d, Point3d<i32>> for Multiphase3d {
+    fn new(parameters: Parameters, size: Rc<SpaceSize3d>, gray: Rc<RefCell<Vec<u8>>>) -> Self {
+        Self {
+            parameters,
+            size,
+            gray,
+            phases: Vec::new(),
+            exclusions: Vec::new(),
+            stopped: Vec::new(),
+            iteration: 0,
+        }
+    }
+
+    fn initialize(&mut self, initial_fronts: &[InitialFront3d]) -> Result<(), String> {
+        if initial_fronts.len() > MAX_PHASES {
+            return Err(format!("at most {} phases", MAX_PHASES));
+        }
+        self.phases.clear();
+        self.exclusions.clear();
+        for initial_front in initial_fronts {
+            let exclusion = Rc::new(RefCell::new(vec![false; self.size.total as usize]));
+            // the first phase calculates the terms of the image for all of them
+            let mut lsm = match self.phases.first() {
+                Some(first) => LevelSetMethod3d::new_sharing_image(first),
+                None => LevelSetMethod3d::new(
+                    self.parameters.clone(),
+                    Rc::clone(&self.size),
+                    Rc::clone(&self.gray),
+                )?,
+            };
+            lsm.set_exclusion(Rc::clone(&exclusion));
+            lsm.initialize(initial_front);
+            self.phases.push(lsm);
+            self.exclusions.push(exclusion);
+        }
+        self.stopped = vec![false; self.phases.len()];
+        self.iteration = 0;
+        Ok(())
+    }
+
+    fn step(&mut self) -> bool {
+        // every phase is bounded by the labels at the beginning of the step
+        let labels = self.get_labels();
+        for k in 0..self.phases.len() {
+            if !self.stopped[k] {
+                self.update_exclusion(&labels, k);
+                self.stopped[k] = self.phases[k].step();
+            }
+        }
+        self.iteration += 1;
+        self.stopped.iter().all(|s| *s)
+    }
+
+    fn run(&mut self, max_iterations: usize) -> MultiphaseResult<Point3d<i32>> {
+        while self.iteration < max_iterations {
+            if self.step() {
+                break;
+            }
+        }
+        MultiphaseResult {
+            labels: self.get_labels(),
+            phases: self.phases.iter().map(|lsm| lsm.make_result()).collect(),
+            iterations: self.iteration,
+        }
+    }
+
+    fn get_labels(&self) -> Vec<u8> {
+        let phis: Vec<_> = self.phases.iter().map(|lsm| lsm.get_phi()).collect();
+        make_labels(&phis, self.size.total as usize)
+    }
+
+    fn get_phase_count(&self) -> usize {
+        self.phases.len()
+    }
+}
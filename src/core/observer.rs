@@ -0,0 +1,30 @@
+use crate::core::status::Status;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObserverAction {
+    Continue,
+    Stop,
+}
+
+/// read-only views of the level set method after an iteration
+pub struct IterationState<'a, IntPoint> {
+    pub iteration: usize,
+    pub phi: &'a [f64],
+    pub front: &'a [IntPoint],
+    pub statuses: &'a [Status],
+    pub total_speed: f64,
+    pub zero_count: i32,
+}
+
+pub trait Observer<IntPoint> {
+    fn observe(&mut self, state: &IterationState<IntPoint>) -> ObserverAction;
+}
+
+impl<IntPoint, F> Observer<IntPoint> for F
+where
+    F: FnMut(&IterationState<IntPoint>) -> ObserverAction,
+{
+    fn observe(&mut self, state: &IterationState<IntPoint>) -> ObserverAction {
+        self(state)
+    }
+}
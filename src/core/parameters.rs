@@ -1,4 +1,91 @@
 //use crate::interface::commandline_interface as cli;
+
+/// how the speed on the front is calculated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpeedMode {
+    /// speed factor 1/(1+|grad I|) times (constant_speed - gain * curvature)
+    Edge,
+    /// Chan-Vese region term minus gain * curvature
+    ChanVese,
+    /// local binary fitting term (Li et al.) with the Gaussian window of lbf_sigma
+    /// minus gain * curvature, for images with inhomogeneous intensities
+    LocalBinaryFitting,
+}
+
+/// edge-stopping function g(|grad I|) of the speed factors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EdgeFunction {
+    /// 1 / (1 + (|grad I| / edge_k)^edge_power)
+    Rational,
+    /// exp(-|grad I|^2 / edge_k^2)
+    Exponential,
+    /// 1 / (1 + exp(-(|grad I| - sigmoid_beta) / sigmoid_alpha)) as the sigmoid of ITK,
+    /// which falls at the edges for a negative sigmoid_alpha
+    Sigmoid,
+}
+
+/// spatial discretization of grad(phi)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpatialScheme {
+    /// first-order upwind differences
+    FirstOrder,
+    /// fifth-order WENO with the Godunov Hamiltonian, needs wband >= 3
+    Weno5,
+}
+
+/// time integration of propagate_front
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeIntegrator {
+    /// forward Euler
+    Euler,
+    /// second-order TVD Runge-Kutta
+    Rk2,
+    /// third-order TVD Runge-Kutta
+    Rk3,
+}
+
+/// how phi is rebuilt when the narrow band is reset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reinitialization {
+    /// square root of the integer squared distance to the nearest front point
+    DistanceMap,
+    /// first-order fast marching from the subcell zero crossings
+    FastMarching,
+    /// second-order fast marching from the subcell zero crossings
+    FastMarchingSecondOrder,
+    /// pde_iterations steps of the Sussman reinitialization equation
+    Pde,
+}
+
+/// when phi is rebuilt besides the front reaching the ResetBand,
+/// which always rebuilds it to keep the front inside the narrow band
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReinitializationTrigger {
+    /// only when the front reaches the ResetBand
+    ResetBand,
+    /// every reinitialization_interval iterations
+    Interval,
+    /// when the mean of ||grad(phi)| - 1| over the narrow band exceeds gradient_deviation_tolerance
+    GradientDeviation,
+}
+
+/// how the points around the front are maintained
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Engine {
+    /// narrow band rebuilt from the distance map around the front
+    NarrowBand,
+    /// sparse field layers updated incrementally (Whitaker), which keeps two layers
+    /// on each side and so accepts only the first-order upwind scheme, forward Euler
+    /// and a fixed time step
+    SparseField,
+    /// the points with |phi| < wband evolved with the distance regularization term
+    /// of DRLSE (Li et al.), which keeps phi close to a signed distance without resets
+    Drlse,
+    /// binary level set evolved by the morphological operators (Marquez-Neila et al.),
+    /// run by MorphologicalSnakes2d/3d instead of LevelSetMethod for fast previews
+    Morphological,
+}
+
 #[derive(Clone)]
 pub struct Parameters {
     pub wband: i32,
@@ -7,6 +94,55 @@ pub struct Parameters {
     pub gain: f64,
     pub constant_speed: f64,
     pub speed_threshold: f64,
+    pub speed_mode: SpeedMode,
+    pub lambda_inside: f64,
+    pub lambda_outside: f64,
+    /// standard deviation of the Gaussian window of the local binary fitting
+    pub lbf_sigma: f64,
+    /// weight of the geodesic active contour advection term, 0 disables it
+    pub advection_gain: f64,
+    pub edge_function: EdgeFunction,
+    /// standard deviation of the Gaussian smoothing of the gray image before grad I,
+    /// 0 disables it
+    pub edge_sigma: f64,
+    pub edge_k: f64,
+    pub edge_power: f64,
+    pub sigmoid_alpha: f64,
+    pub sigmoid_beta: f64,
+    /// speed of the advection along the gradient vector flow of the edge map 1 - g,
+    /// 0 disables it
+    pub gvf_weight: f64,
+    /// number of the diffusion iterations of the gradient vector flow
+    pub gvf_iterations: usize,
+    /// regularization of the gradient vector flow, larger for a smoother field
+    pub gvf_mu: f64,
+    pub spatial_scheme: SpatialScheme,
+    pub time_integrator: TimeIntegrator,
+    /// chooses the time step from the CFL condition at every iteration,
+    /// time_step is then used as its upper bound
+    pub adaptive_time_step: bool,
+    pub cfl_number: f64,
+    pub reinitialization: Reinitialization,
+    pub pde_iterations: usize,
+    pub reinitialization_trigger: ReinitializationTrigger,
+    pub reinitialization_interval: usize,
+    pub gradient_deviation_tolerance: f64,
+    pub engine: Engine,
+    /// weight of the distance regularization term of the Drlse engine
+    pub distance_regularization: f64,
+    /// forbids the sign changes of phi at non-simple points,
+    /// so that the number of connected components and holes is kept
+    pub topology_preservation: bool,
+    /// weight of the shape prior term, 0 disables it
+    pub shape_prior_weight: f64,
+    /// the pose of the reference shape is re-estimated every this many iterations,
+    /// 0 keeps the reference on the grid of the space
+    pub shape_prior_pose_interval: usize,
+    /// number of the curvature operators applied at each iteration of the Morphological engine
+    pub morphological_smoothing: usize,
+    /// the balloon force of the Morphological engine acts
+    /// where speed factor * |constant_speed| exceeds this
+    pub morphological_threshold: f64,
 }
 
 impl Parameters {
@@ -18,6 +154,36 @@ impl Parameters {
             gain: 0.0,
             constant_speed: 0.0,
             speed_threshold: 0.0,
+            speed_mode: SpeedMode::Edge,
+            lambda_inside: 1.0,
+            lambda_outside: 1.0,
+            lbf_sigma: 3.0,
+            advection_gain: 0.0,
+            edge_function: EdgeFunction::Rational,
+            edge_sigma: 0.0,
+            edge_k: 1.0,
+            edge_power: 1.0,
+            sigmoid_alpha: -10.0,
+            sigmoid_beta: 30.0,
+            gvf_weight: 0.0,
+            gvf_iterations: 80,
+            gvf_mu: 0.2,
+            spatial_scheme: SpatialScheme::FirstOrder,
+            time_integrator: TimeIntegrator::Euler,
+            adaptive_time_step: false,
+            cfl_number: 0.5,
+            reinitialization: Reinitialization::DistanceMap,
+            pde_iterations: 5,
+            reinitialization_trigger: ReinitializationTrigger::ResetBand,
+            reinitialization_interval: 10,
+            gradient_deviation_tolerance: 0.2,
+            engine: Engine::NarrowBand,
+            distance_regularization: 0.2,
+            topology_preservation: false,
+            shape_prior_weight: 0.0,
+            shape_prior_pose_interval: 0,
+            morphological_smoothing: 1,
+            morphological_threshold: 0.5,
         }
     }
 }
@@ -0,0 +1,369 @@
+use crate::core::gaussian;
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d, SpaceSizeMethod};
+use std::rc::Rc;
+
+/// a stage of the preprocessing of the gray image before the speeds are computed.
+/// the stages work on real values, which are rounded to u8 after the last one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    /// Gaussian smoothing of sigma
+    Gaussian { sigma: f64 },
+    /// median of the window of 2 * radius + 1 points along each axis
+    Median { radius: i32 },
+    /// Perona-Malik diffusion with the conductance exp(-(|grad I| / conductance)^2),
+    /// stable for time_step <= 1 / (2 * dimension), which parse requires
+    PeronaMalik {
+        iterations: usize,
+        time_step: f64,
+        conductance: f64,
+    },
+    /// curvature-anisotropic diffusion (Whitaker and Xue) |grad I| div(c(|grad I|) grad I / |grad I|)
+    /// with the same conductance and bound of time_step as PeronaMalik, which keeps the edges sharper
+    CurvatureDiffusion {
+        iterations: usize,
+        time_step: f64,
+        conductance: f64,
+    },
+    /// maps [lower, upper] to [0, 255] linearly and clamps the others
+    Window { lower: f64, upper: f64 },
+    /// maps the range of the image to [0, 255] linearly
+    Rescale,
+}
+
+impl Filter {
+    /// the window of the median, (2 * radius + 1)^dimension points, is sorted at every point.
+    pub const MAX_MEDIAN_RADIUS: i32 = 15;
+
+    /// parses "name" or "name:v1,v2,...":
+    /// "gaussian:sigma", "median:radius", "perona-malik:iterations,time_step,conductance",
+    /// "curvature-diffusion:iterations,time_step,conductance", "window:lower,upper" or "rescale".
+    /// dimension bounds the time_step of the diffusions.
+    pub fn parse(text: &str, dimension: usize) -> Result<Self, String> {
+        let (name, arguments) = match text.split_once(':') {
+            Some((name, arguments)) => (name.trim(), arguments),
+            None => (text.trim(), ""),
+        };
+        let values: Vec<f64> = arguments
+            .split(',')
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("invalid filter {}: {}", text, e))?;
+        let expected = match name {
+            "gaussian" | "median" => 1,
+            "perona-malik" | "curvature-diffusion" => 3,
+            "window" => 2,
+            "rescale" => 0,
+            _ => return Err(format!("unknown filter {}", text)),
+        };
+        if values.len() != expected {
+            return Err(format!("filter {} needs {} values", text, expected));
+        }
+        let is_positive = values.iter().all(|v| *v > 0.0);
+        let is_stable = values.len() == 3 && values[1] <= 1.0 / (2.0 * dimension as f64);
+        let filter = match name {
+            "gaussian" if is_positive => Filter::Gaussian { sigma: values[0] },
+            "median" if values[0] >= 0.0 && values[0].fract() == 0.0 => {
+                let radius = values[0] as i32;
+                if radius > Self::MAX_MEDIAN_RADIUS {
+                    return Err(format!(
+                        "the radius of filter {} must be at most {}",
+                        text,
+                        Self::MAX_MEDIAN_RADIUS
+                    ));
+                }
+                Filter::Median { radius }
+            }
+            "perona-malik" if is_positive && is_stable && values[0].fract() == 0.0 => {
+                Filter::PeronaMalik {
+                    iterations: values[0] as usize,
+                    time_step: values[1],
+                    conductance: values[2],
+                }
+            }
+            "curvature-diffusion" if is_positive && is_stable && values[0].fract() == 0.0 => {
+                Filter::CurvatureDiffusion {
+                    iterations: values[0] as usize,
+                    time_step: values[1],
+                    conductance: values[2],
+                }
+            }
+            "window" if values[0] < values[1] => Filter::Window {
+                lower: values[0],
+                upper: values[1],
+            },
+            "rescale" => Filter::Rescale,
+            _ => return Err(format!("invalid values of filter {}", text)),
+        };
+        Ok(filter)
+    }
+}
+
+/// parses one filter per line, skipping empty lines and the comments after '#'.
+pub fn parse_pipeline(text: &str, dimension: usize) -> Result<Vec<Filter>, String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| Filter::parse(line, dimension))
+        .collect()
+}
+
+pub trait PreprocessingMethod<SpaceSize> {
+    fn new(size: Rc<SpaceSize>) -> Self;
+    /// applies the filters in order.
+    fn apply(&self, filters: &[Filter], gray: &[u8]) -> Vec<u8>;
+}
+
+/// reads the neighbors of a point through the indexer.
+trait Neighborhood {
+    type Point;
+    fn get_extents(&self) -> Vec<i32>;
+    /// all the points in the order of their indices
+    fn get_points(&self) -> &[Self::Point];
+    fn get_index(&self, p: &Self::Point) -> usize;
+    /// the point moved by delta along axis, clamped to the space
+    fn shift(&self, p: &Self::Point, axis: usize, delta: i32) -> Self::Point;
+}
+
+fn apply_filters<N: Neighborhood>(neighborhood: &N, filters: &[Filter], gray: &[u8]) -> Vec<u8> {
+    let mut values: Vec<f64> = gray.iter().map(|v| *v as f64).collect();
+    for filter in filters {
+        values = match filter {
+            Filter::Gaussian { sigma } => {
+                gaussian::smooth(&values, &neighborhood.get_extents(), *sigma)
+            }
+            Filter::Median { radius } => median(neighborhood, &values, *radius),
+            Filter::PeronaMalik {
+                iterations,
+                time_step,
+                conductance,
+            } => (0..*iterations).fold(values, |v, _| {
+                diffuse_perona_malik(neighborhood, &v, *time_step, *conductance)
+            }),
+            Filter::CurvatureDiffusion {
+                iterations,
+                time_step,
+                conductance,
+            } => (0..*iterations).fold(values, |v, _| {
+                diffuse_by_curvature(neighborhood, &v, *time_step, *conductance)
+            }),
+            Filter::Window { lower, upper } => window(&values, *lower, *upper),
+            Filter::Rescale => {
+                let lower = values.iter().cloned().fold(f64::INFINITY, f64::min);
+                let upper = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                if lower < upper {
+                    window(&values, lower, upper)
+                } else {
+                    values
+                }
+            }
+        };
+    }
+    values
+        .iter()
+        .map(|v| v.round().clamp(0.0, 255.0) as u8)
+        .collect()
+}
+
+fn median<N: Neighborhood>(neighborhood: &N, values: &[f64], radius: i32) -> Vec<f64> {
+    let dimension = neighborhood.get_extents().len();
+    let width = (2 * radius + 1) as usize;
+    let size = width.pow(dimension as u32);
+    let mut samples = Vec::with_capacity(size);
+    neighborhood
+        .get_points()
+        .iter()
+        .map(|p| {
+            samples.clear();
+            for offset in 0..size {
+                // the digits of offset in base width are the deltas along the axes
+                let mut rest = offset;
+                let mut q = neighborhood.shift(p, 0, (rest % width) as i32 - radius);
+                for axis in 1..dimension {
+                    rest /= width;
+                    q = neighborhood.shift(&q, axis, (rest % width) as i32 - radius);
+                }
+                samples.push(values[neighborhood.get_index(&q)]);
+            }
+            samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            samples[samples.len() / 2]
+        })
+        .collect()
+}
+
+fn conductance_of(norm: f64, conductance: f64) -> f64 {
+    (-(norm / conductance).powi(2)).exp()
+}
+
+/// one explicit step of the Perona-Malik diffusion, without flux through the edges of the space.
+fn diffuse_perona_malik<N: Neighborhood>(
+    neighborhood: &N,
+    values: &[f64],
+    time_step: f64,
+    conductance: f64,
+) -> Vec<f64> {
+    let dimension = neighborhood.get_extents().len();
+    let value = |p: &N::Point| values[neighborhood.get_index(p)];
+    neighborhood
+        .get_points()
+        .iter()
+        .map(|p| {
+            let v = value(p);
+            let mut flux = 0.0;
+            for axis in 0..dimension {
+                for delta in [-1, 1] {
+                    let d = value(&neighborhood.shift(p, axis, delta)) - v;
+                    flux += conductance_of(d.abs(), conductance) * d;
+                }
+            }
+            v + time_step * flux
+        })
+        .collect()
+}
+
+/// one explicit step of the curvature-anisotropic diffusion.
+/// the flux through each face uses the gradient on the face from the two points it separates.
+fn diffuse_by_curvature<N: Neighborhood>(
+    neighborhood: &N,
+    values: &[f64],
+    time_step: f64,
+    conductance: f64,
+) -> Vec<f64> {
+    let dimension = neighborhood.get_extents().len();
+    let value = |p: &N::Point| values[neighborhood.get_index(p)];
+    let tangent = |p: &N::Point, axis: usize| {
+        value(&neighborhood.shift(p, axis, 1)) - value(&neighborhood.shift(p, axis, -1))
+    };
+    neighborhood
+        .get_points()
+        .iter()
+        .map(|p| {
+            let v = value(p);
+            let mut divergence = 0.0;
+            let mut norm2 = 0.0;
+            for axis in 0..dimension {
+                let d = 0.5 * tangent(p, axis);
+                norm2 += d * d;
+                for delta in [-1, 1] {
+                    let neighbor = neighborhood.shift(p, axis, delta);
+                    let normal = value(&neighbor) - v;
+                    let mut face2 = normal * normal;
+                    for other in (0..dimension).filter(|o| *o != axis) {
+                        let t = (tangent(p, other) + tangent(&neighbor, other)) / 4.0;
+                        face2 += t * t;
+                    }
+                    if face2 > 0.0 {
+                        let face = face2.sqrt();
+                        divergence += conductance_of(face, conductance) * normal / face;
+                    }
+                }
+            }
+            v + time_step * norm2.sqrt() * divergence
+        })
+        .collect()
+}
+
+fn window(values: &[f64], lower: f64, upper: f64) -> Vec<f64> {
+    values
+        .iter()
+        .map(|v| (255.0 * (v - lower) / (upper - lower)).clamp(0.0, 255.0))
+        .collect()
+}
+
+pub struct Preprocessing2d {
+    size: Rc<SpaceSize2d>,
+    indexer: Indexer2d,
+    points: Vec<Point2d<i32>>,
+}
+
+impl Neighborhood for Preprocessing2d {
+    type Point = Point2d<i32>;
+
+    fn get_extents(&self) -> Vec<i32> {
+        self.size.get_extents()
+    }
+
+    fn get_points(&self) -> &[Point2d<i32>] {
+        &self.points
+    }
+
+    fn get_index(&self, p: &Point2d<i32>) -> usize {
+        self.indexer.get(p) as usize
+    }
+
+    fn shift(&self, p: &Point2d<i32>, axis: usize, delta: i32) -> Point2d<i32> {
+        match axis {
+            0 => Point2d::<i32>::new((p.x + delta).clamp(0, self.size.width - 1), p.y),
+            _ => Point2d::<i32>::new(p.x, (p.y + delta).clamp(0, self.size.height - 1)),
+        }
+    }
+}
+
+impl PreprocessingMethod<SpaceSize2d> for Preprocessing2d {
+    fn new(size: Rc<SpaceSize2d>) -> Self {
+        let indexer = Indexer2d::new(&size);
+        let points = (0..size.height)
+            .flat_map(|y| (0..size.width).map(move |x| Point2d::<i32>::new(x, y)))
+            .collect();
+        Self {
+            size,
+            indexer,
+            points,
+        }
+    }
+
+    fn apply(&self, filters: &[Filter], gray: &[u8]) -> Vec<u8> {
+        apply_filters(self, filters, gray)
+    }
+}
+
+pub struct Preprocessing3d {
+    size: Rc<SpaceSize3d>,
+    indexer: Indexer3d,
+    points: Vec<Point3d<i32>>,
+}
+
+impl Neighborhood for Preprocessing3d {
+    type Point = Point3d<i32>;
+
+    fn get_extents(&self) -> Vec<i32> {
+        self.size.get_extents()
+    }
+
+    fn get_points(&self) -> &[Point3d<i32>] {
+        &self.points
+    }
+
+    fn get_index(&self, p: &Point3d<i32>) -> usize {
+        self.indexer.get(p) as usize
+    }
+
+    fn shift(&self, p: &Point3d<i32>, axis: usize, delta: i32) -> Point3d<i32> {
+        match axis {
+            0 => Point3d::<i32>::new((p.x + delta).clamp(0, self.size.width - 1), p.y, p.z),
+            1 => Point3d::<i32>::new(p.x, (p.y + delta).clamp(0, self.size.height - 1), p.z),
+            _ => Point3d::<i32>::new(p.x, p.y, (p.z + delta).clamp(0, self.size.depth - 1)),
+        }
+    }
+}
+
+impl PreprocessingMethod<SpaceSize3d> for Preprocessing3d {
+    fn new(size: Rc<SpaceSize3d>) -> Self {
+        let indexer = Indexer3d::new(&size);
+        let points = (0..size.depth)
+            .flat_map(|z| (0..size.height).map(move |y| (y, z)))
+            .flat_map(|(y, z)| (0..size.width).map(move |x| Point3d::<i32>::new(x, y, z)))
+            .collect();
+        Self {
+            size,
+            indexer,
+            points,
+        }
+    }
+
+    fn apply(&self, filters: &[Filter], gray: &[u8]) -> Vec<u8> {
+        apply_filters(self, filters, gray)
+    }
+}
@@ -0,0 +1,288 @@
+use crate::core::gaussian;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// region-based speed of the Chan-Vese model.
+/// the inside(phi < 0) and outside(phi >= 0) mean intensities are
+/// recalculated from the whole image by update, or from the points changing their signs by update_at.
+pub struct ChanVeseSpeed {
+    gray: Rc<RefCell<Vec<u8>>>,
+    lambda_inside: f64,
+    lambda_outside: f64,
+    inside_mean: f64,
+    outside_mean: f64,
+    /// the signs of phi at the last update, empty before the first one
+    is_inside: Vec<bool>,
+    /// the sums of intensities and the numbers of points of the inside and outside
+    inside: (f64, usize),
+    outside: (f64, usize),
+}
+
+impl ChanVeseSpeed {
+    /// squared differences of intensities are normalized by this value.
+    const NORMALIZER: f64 = 255.0 * 255.0;
+
+    pub fn new(gray: Rc<RefCell<Vec<u8>>>, lambda_inside: f64, lambda_outside: f64) -> Self {
+        Self {
+            gray,
+            lambda_inside,
+            lambda_outside,
+            inside_mean: 0.0,
+            outside_mean: 0.0,
+            is_inside: Vec::new(),
+            inside: (0.0, 0),
+            outside: (0.0, 0),
+        }
+    }
+
+    pub fn update(&mut self, phi: &[f64]) {
+        self.is_inside = phi.iter().map(|v| *v < 0.0).collect();
+        self.inside = (0.0, 0);
+        self.outside = (0.0, 0);
+        for (is_inside, g) in self.is_inside.iter().zip(self.gray.borrow().iter()) {
+            let region = if *is_inside {
+                &mut self.inside
+            } else {
+                &mut self.outside
+            };
+            region.0 += *g as f64;
+            region.1 += 1;
+        }
+        self.update_means();
+    }
+
+    /// update where phi may have changed its sign at points only since the last update,
+    /// which is done instead if there has been none.
+    pub fn update_at(&mut self, phi: &[f64], points: &[usize]) {
+        if self.is_inside.is_empty() {
+            self.update(phi);
+            return;
+        }
+        let gray = self.gray.borrow();
+        for i in points.iter().copied() {
+            let is_inside = phi[i] < 0.0;
+            if is_inside == self.is_inside[i] {
+                continue;
+            }
+            self.is_inside[i] = is_inside;
+            let g = gray[i] as f64;
+            let (to, from) = if is_inside {
+                (&mut self.inside, &mut self.outside)
+            } else {
+                (&mut self.outside, &mut self.inside)
+            };
+            to.0 += g;
+            to.1 += 1;
+            from.0 -= g;
+            from.1 -= 1;
+        }
+        drop(gray);
+        self.update_means();
+    }
+
+    fn update_means(&mut self) {
+        if self.inside.1 > 0 {
+            self.inside_mean = self.inside.0 / self.inside.1 as f64;
+        }
+        if self.outside.1 > 0 {
+            self.outside_mean = self.outside.0 / self.outside.1 as f64;
+        }
+    }
+
+    /// positive where the intensity is closer to the inside mean,
+    /// which moves the front outward.
+    pub fn get_value(&self, index: usize) -> f64 {
+        let g = self.gray.borrow()[index] as f64;
+        let di = g - self.inside_mean;
+        let dout = g - self.outside_mean;
+        (self.lambda_outside * dout * dout - self.lambda_inside * di * di) / Self::NORMALIZER
+    }
+
+    pub fn get_inside_mean(&self) -> f64 {
+        self.inside_mean
+    }
+
+    pub fn get_outside_mean(&self) -> f64 {
+        self.outside_mean
+    }
+}
+
+/// the terms of the local binary fitting which depend on the image only,
+/// shared by the level sets segmenting the same image.
+pub struct LocalFittingImage {
+    extents: Vec<i32>,
+    kernel: Vec<f64>,
+    gray: Vec<f64>,
+    /// K * 1 and K * I
+    window: Vec<f64>,
+    windowed_gray: Vec<f64>,
+}
+
+impl LocalFittingImage {
+    pub fn new(gray: &[u8], extents: Vec<i32>, sigma: f64) -> Self {
+        let kernel = gaussian::make_kernel(sigma);
+        let gray: Vec<f64> = gray.iter().map(|g| *g as f64).collect();
+        let window = gaussian::convolve(&vec![1.0; gray.len()], &extents, &kernel);
+        let windowed_gray = gaussian::convolve(&gray, &extents, &kernel);
+        Self {
+            extents,
+            kernel,
+            gray,
+            window,
+            windowed_gray,
+        }
+    }
+
+    fn convolve_within(
+        &self,
+        values: &[f64],
+        region: &[usize],
+        result: &mut [f64],
+        scratch: &mut [f64],
+    ) {
+        gaussian::convolve_within(values, &self.extents, &self.kernel, region, result, scratch);
+    }
+}
+
+/// region-based speed of the local binary fitting model (Li et al.).
+/// the inside and outside means are weighted by a Gaussian window around each point,
+/// so that intensities varying slowly over the image do not disturb the fitting.
+/// the buffers below span the space, and each update writes them around its points only.
+pub struct LocalBinaryFittingSpeed {
+    image: Rc<LocalFittingImage>,
+    lambda_inside: f64,
+    lambda_outside: f64,
+    is_marked: Vec<bool>,
+    /// H(-phi) and H(-phi) I, and their convolutions by K
+    heaviside: Vec<f64>,
+    heaviside_gray: Vec<f64>,
+    windowed_heaviside: Vec<f64>,
+    windowed_heaviside_gray: Vec<f64>,
+    inside_means: Vec<f64>,
+    outside_means: Vec<f64>,
+    /// f^2, K * f and K * f^2 of the means f, and the intermediate passes of the convolutions
+    squares: Vec<f64>,
+    windowed_means: Vec<f64>,
+    windowed_squares: Vec<f64>,
+    scratch: Vec<f64>,
+    /// integral of K(y - x) |I(x) - f(y)|^2 over y for the inside and outside means f
+    inside_errors: Vec<f64>,
+    outside_errors: Vec<f64>,
+}
+
+impl LocalBinaryFittingSpeed {
+    /// squared differences of intensities are normalized by this value.
+    const NORMALIZER: f64 = 255.0 * 255.0;
+
+    /// keeps the means finite where the window contains no point of a region.
+    const EPSILON: f64 = 1.0e-10;
+
+    pub fn new(image: Rc<LocalFittingImage>, lambda_inside: f64, lambda_outside: f64) -> Self {
+        let total = image.gray.len();
+        Self {
+            image,
+            lambda_inside,
+            lambda_outside,
+            is_marked: vec![false; total],
+            heaviside: vec![0.0; total],
+            heaviside_gray: vec![0.0; total],
+            windowed_heaviside: vec![0.0; total],
+            windowed_heaviside_gray: vec![0.0; total],
+            inside_means: vec![0.0; total],
+            outside_means: vec![0.0; total],
+            squares: vec![0.0; total],
+            windowed_means: vec![0.0; total],
+            windowed_squares: vec![0.0; total],
+            scratch: vec![0.0; total],
+            inside_errors: vec![0.0; total],
+            outside_errors: vec![0.0; total],
+        }
+    }
+
+    pub fn get_image(&self) -> Rc<LocalFittingImage> {
+        Rc::clone(&self.image)
+    }
+
+    /// recalculates the errors at points, which are valid there only, and the means around them.
+    /// the work covers the points within twice the kernel radius of them, not the whole image.
+    pub fn update(&mut self, phi: &[f64], points: &[usize]) {
+        let image = Rc::clone(&self.image);
+        // the errors at points need the means in the kernel box around them,
+        // which need the windowed sums in the kernel box around those
+        let radius = (image.kernel.len() / 2) as i32;
+        let around_points = gaussian::dilate(points, &image.extents, radius, &mut self.is_marked);
+        let region = gaussian::dilate(&around_points, &image.extents, radius, &mut self.is_marked);
+
+        for i in &region {
+            let h = if phi[*i] < 0.0 { 1.0 } else { 0.0 };
+            self.heaviside[*i] = h;
+            self.heaviside_gray[*i] = h * image.gray[*i];
+        }
+        image.convolve_within(
+            &self.heaviside,
+            &region,
+            &mut self.windowed_heaviside,
+            &mut self.scratch,
+        );
+        image.convolve_within(
+            &self.heaviside_gray,
+            &region,
+            &mut self.windowed_heaviside_gray,
+            &mut self.scratch,
+        );
+
+        for i in around_points.iter().copied() {
+            let (h, hg) = (self.windowed_heaviside[i], self.windowed_heaviside_gray[i]);
+            self.inside_means[i] = hg / (h + Self::EPSILON);
+            self.outside_means[i] =
+                (image.windowed_gray[i] - hg) / (image.window[i] - h + Self::EPSILON);
+        }
+        self.calculate_errors(true, &around_points, points);
+        self.calculate_errors(false, &around_points, points);
+    }
+
+    /// I^2 (K * 1) - 2 I (K * f) + K * f^2 at points from the inside or outside means f around them.
+    fn calculate_errors(&mut self, is_inside: bool, around_points: &[usize], points: &[usize]) {
+        let image = Rc::clone(&self.image);
+        let (means, errors) = match is_inside {
+            true => (&self.inside_means, &mut self.inside_errors),
+            false => (&self.outside_means, &mut self.outside_errors),
+        };
+        for i in around_points {
+            self.squares[*i] = means[*i] * means[*i];
+        }
+        image.convolve_within(
+            means,
+            around_points,
+            &mut self.windowed_means,
+            &mut self.scratch,
+        );
+        image.convolve_within(
+            &self.squares,
+            around_points,
+            &mut self.windowed_squares,
+            &mut self.scratch,
+        );
+        for i in points.iter().copied() {
+            let g = image.gray[i];
+            errors[i] = g * g * image.window[i] - 2.0 * g * self.windowed_means[i]
+                + self.windowed_squares[i];
+        }
+    }
+
+    /// positive where the intensity fits the local inside means better,
+    /// which moves the front outward.
+    pub fn get_value(&self, index: usize) -> f64 {
+        (self.lambda_outside * self.outside_errors[index]
+            - self.lambda_inside * self.inside_errors[index])
+            / Self::NORMALIZER
+    }
+
+    pub fn get_inside_mean(&self, index: usize) -> f64 {
+        self.inside_means[index]
+    }
+
+    pub fn get_outside_mean(&self, index: usize) -> f64 {
+        self.outside_means[index]
+    }
+}
@@ -0,0 +1,339 @@
+use crate::core::fast_marching::{FastMarching2d, FastMarching3d, FastMarchingMethod};
+use crate::core::grid_file::{self, GridHeader};
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::shape_prior::{jacobi_eigen, ReferenceMap};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+
+/// point distribution of signed distance maps: the mean shape and its principal modes.
+///
+/// file format, all values little endian:
+/// - magic "LSPM"(4 bytes)
+/// - dimension as u32, 2 or 3
+/// - extents as u32 for each axis, x first
+/// - number of modes K as u32
+/// - K eigenvalues as f64 in descending order
+/// - mean phi as f64, x varying fastest
+/// - K modes as f64 in the layout of the mean, each of unit length
+pub trait ShapeModelMethod<T, P>: Sized {
+    /// builds the model from aligned binary masks(non-zero inside) with at most mode_count modes.
+    fn build(space_size: &T, masks: &[Vec<u8>], mode_count: usize) -> Result<Self, String>;
+    fn load(path: &std::path::Path) -> Result<Self, String>;
+    fn save(&self, path: &std::path::Path) -> Result<(), String>;
+    fn get_space_size(&self) -> &T;
+    fn get_mean(&self) -> &[f64];
+    fn get_eigenvalues(&self) -> &[f64];
+    fn get_modes(&self) -> &[Vec<f64>];
+    /// mean + sum of weights[k] * mode k at p
+    fn get_value(&self, p: &P, weights: &[f64]) -> f64;
+    /// the mean shape as a reference of the shape prior
+    fn to_reference_map(&self) -> ReferenceMap;
+}
+
+struct ShapeModel {
+    extents: Vec<i32>,
+    mean: Vec<f64>,
+    eigenvalues: Vec<f64>,
+    modes: Vec<Vec<f64>>,
+}
+
+impl ShapeModel {
+    const MAGIC: &'static [u8; 4] = b"LSPM";
+
+    /// principal component analysis through the Gram matrix of the samples
+    fn build(extents: Vec<i32>, phis: &[Vec<f64>], mode_count: usize) -> Result<Self, String> {
+        if phis.is_empty() {
+            return Err(String::from("no training shapes"));
+        }
+        let n = phis.len();
+        let total = phis[0].len();
+        let mut mean = vec![0.0; total];
+        for phi in phis {
+            for (m, v) in mean.iter_mut().zip(phi) {
+                *m += v / n as f64;
+            }
+        }
+        let deviations: Vec<Vec<f64>> = phis
+            .iter()
+            .map(|phi| phi.iter().zip(&mean).map(|(v, m)| v - m).collect())
+            .collect();
+
+        let mut gram = vec![0.0; n * n];
+        for i in 0..n {
+            for j in i..n {
+                let g: f64 = deviations[i]
+                    .iter()
+                    .zip(&deviations[j])
+                    .map(|(a, b)| a * b)
+                    .sum();
+                gram[i * n + j] = g;
+                gram[j * n + i] = g;
+            }
+        }
+        let (values, vectors) = jacobi_eigen(&gram, n);
+
+        let mut eigenvalues = Vec::new();
+        let mut modes = Vec::new();
+        // variations at the level of round-off in the mean are no modes
+        let energy: f64 = mean.iter().map(|m| m * m).sum();
+        let largest = values.first().copied().unwrap_or(0.0);
+        for (k, value) in values.iter().enumerate().take(mode_count.min(n - 1)) {
+            if *value <= 1.0e-12 * (largest + energy) {
+                break;
+            }
+            let mut mode = vec![0.0; total];
+            for (i, deviation) in deviations.iter().enumerate() {
+                let w = vectors[i * n + k];
+                for (m, d) in mode.iter_mut().zip(deviation) {
+                    *m += w * d;
+                }
+            }
+            let length = mode.iter().map(|m| m * m).sum::<f64>().sqrt();
+            mode.iter_mut().for_each(|m| *m /= length);
+            eigenvalues.push(value / (n - 1) as f64);
+            modes.push(mode);
+        }
+        Ok(Self {
+            extents,
+            mean,
+            eigenvalues,
+            modes,
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let total = self.mean.len();
+        let mut bytes =
+            Vec::with_capacity(12 + 4 * self.extents.len() + 8 * total * (1 + self.modes.len()));
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&(self.extents.len() as u32).to_le_bytes());
+        for e in &self.extents {
+            bytes.extend_from_slice(&(*e as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.modes.len() as u32).to_le_bytes());
+        let values = self
+            .eigenvalues
+            .iter()
+            .chain(&self.mean)
+            .chain(self.modes.iter().flatten());
+        for v in values {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8], dimension: usize) -> Option<Self> {
+        let header = GridHeader::read(bytes, Self::MAGIC)?;
+        if header.extents.len() != dimension {
+            return None;
+        }
+        let (extents, total) = (header.extents, header.total);
+        let count = grid_file::read_u32(bytes, header.offset)? as usize;
+        let offset = header.offset + 4;
+        // count eigenvalues, the mean and count modes
+        let length = total
+            .checked_mul(count.checked_add(1)?)?
+            .checked_add(count)?
+            .checked_mul(8)?
+            .checked_add(offset)?;
+        if bytes.len() != length {
+            return None;
+        }
+        let values = grid_file::read_values(&bytes[offset..]);
+        Some(Self {
+            extents,
+            eigenvalues: values[..count].to_vec(),
+            mean: values[count..(count + total)].to_vec(),
+            modes: values[(count + total)..]
+                .chunks_exact(total)
+                .map(|m| m.to_vec())
+                .collect(),
+        })
+    }
+
+    fn load(path: &std::path::Path, dimension: usize) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{:?}: {}", path, e))?;
+        Self::from_bytes(&bytes, dimension)
+            .ok_or(format!("{:?}: not a {}D shape model", path, dimension))
+    }
+
+    fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        std::fs::write(path, self.to_bytes()).map_err(|e| format!("{:?}: {}", path, e))
+    }
+
+    fn get_value(&self, index: usize, weights: &[f64]) -> f64 {
+        self.modes
+            .iter()
+            .zip(weights)
+            .fold(self.mean[index], |v, (mode, w)| v + w * mode[index])
+    }
+
+    fn to_reference_map(&self) -> ReferenceMap {
+        ReferenceMap {
+            extents: self.extents.clone(),
+            phi: self.mean.clone(),
+        }
+    }
+}
+
+/// phi0 of a mask, whose zero-levelset lies halfway between inside and outside points
+fn make_phi0(mask: &[u8]) -> Result<Vec<f64>, String> {
+    let inside = mask.iter().filter(|v| **v != 0).count();
+    if inside == 0 || inside == mask.len() {
+        return Err(String::from("a mask has no boundary"));
+    }
+    Ok(mask
+        .iter()
+        .map(|v| if *v != 0 { -1.0 } else { 1.0 })
+        .collect())
+}
+
+/// signed distance map of a binary mask(non-zero inside) by fast marching.
+pub fn signed_distance_2d(space_size: &SpaceSize2d, mask: &[u8]) -> Result<Vec<f64>, String> {
+    let phi0 = make_phi0(mask)?;
+    let mut phi = phi0.clone();
+    let band: Vec<usize> = (0..phi.len()).collect();
+    FastMarching2d::new(space_size).reinitialize(&phi0, &mut phi, &band, false);
+    Ok(phi)
+}
+
+/// signed distance map of a binary mask(non-zero inside) by fast marching.
+pub fn signed_distance_3d(space_size: &SpaceSize3d, mask: &[u8]) -> Result<Vec<f64>, String> {
+    let phi0 = make_phi0(mask)?;
+    let mut phi = phi0.clone();
+    let band: Vec<usize> = (0..phi.len()).collect();
+    FastMarching3d::new(space_size).reinitialize(&phi0, &mut phi, &band, false);
+    Ok(phi)
+}
+
+pub struct ShapeModel2d {
+    space_size: SpaceSize2d,
+    indexer: Indexer2d,
+    model: ShapeModel,
+}
+
+impl ShapeModelMethod<SpaceSize2d, Point2d<i32>> for ShapeModel2d {
+    fn build(
+        space_size: &SpaceSize2d,
+        masks: &[Vec<u8>],
+        mode_count: usize,
+    ) -> Result<Self, String> {
+        let mut phis = Vec::with_capacity(masks.len());
+        for mask in masks {
+            if mask.len() != space_size.total as usize {
+                return Err(String::from("a mask differs in size"));
+            }
+            phis.push(signed_distance_2d(space_size, mask)?);
+        }
+        let extents = vec![space_size.width, space_size.height];
+        Ok(Self {
+            space_size: space_size.clone(),
+            indexer: Indexer2d::new(space_size),
+            model: ShapeModel::build(extents, &phis, mode_count)?,
+        })
+    }
+
+    fn load(path: &std::path::Path) -> Result<Self, String> {
+        let model = ShapeModel::load(path, 2)?;
+        let space_size = SpaceSize2d::new(model.extents[0], model.extents[1]);
+        Ok(Self {
+            indexer: Indexer2d::new(&space_size),
+            space_size,
+            model,
+        })
+    }
+
+    fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        self.model.save(path)
+    }
+
+    fn get_space_size(&self) -> &SpaceSize2d {
+        &self.space_size
+    }
+
+    fn get_mean(&self) -> &[f64] {
+        &self.model.mean
+    }
+
+    fn get_eigenvalues(&self) -> &[f64] {
+        &self.model.eigenvalues
+    }
+
+    fn get_modes(&self) -> &[Vec<f64>] {
+        &self.model.modes
+    }
+
+    fn get_value(&self, p: &Point2d<i32>, weights: &[f64]) -> f64 {
+        self.model.get_value(self.indexer.get(p) as usize, weights)
+    }
+
+    fn to_reference_map(&self) -> ReferenceMap {
+        self.model.to_reference_map()
+    }
+}
+
+pub struct ShapeModel3d {
+    space_size: SpaceSize3d,
+    indexer: Indexer3d,
+    model: ShapeModel,
+}
+
+impl ShapeModelMethod<SpaceSize3d, Point3d<i32>> for ShapeModel3d {
+    fn build(
+        space_size: &SpaceSize3d,
+        masks: &[Vec<u8>],
+        mode_count: usize,
+    ) -> Result<Self, String> {
+        let mut phis = Vec::with_capacity(masks.len());
+        for mask in masks {
+            if mask.len() != space_size.total as usize {
+                return Err(String::from("a mask differs in size"));
+            }
+            phis.push(signed_distance_3d(space_size, mask)?);
+        }
+        let extents = vec![space_size.width, space_size.height, space_size.depth];
+        Ok(Self {
+            space_size: space_size.clone(),
+            indexer: Indexer3d::new(space_size),
+            model: ShapeModel::build(extents, &phis, mode_count)?,
+        })
+    }
+
+    fn load(path: &std::path::Path) -> Result<Self, String> {
+        let model = ShapeModel::load(path, 3)?;
+        let space_size = SpaceSize3d::new(model.extents[0], model.extents[1], model.extents[2]);
+        Ok(Self {
+            indexer: Indexer3d::new(&space_size),
+            space_size,
+            model,
+        })
+    }
+
+    fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        self.model.save(path)
+    }
+
+    fn get_space_size(&self) -> &SpaceSize3d {
+        &self.space_size
+    }
+
+    fn get_mean(&self) -> &[f64] {
+        &self.model.mean
+    }
+
+    fn get_eigenvalues(&self) -> &[f64] {
+        &self.model.eigenvalues
+    }
+
+    fn get_modes(&self) -> &[Vec<f64>] {
+        &self.model.modes
+    }
+
+    fn get_value(&self, p: &Point3d<i32>, weights: &[f64]) -> f64 {
+        self.model.get_value(self.indexer.get(p) as usize, weights)
+    }
+
+    fn to_reference_map(&self) -> ReferenceMap {
+        self.model.to_reference_map()
+    }
+}
@@ -0,0 +1,413 @@
+use crate::core::grid_file::{self, GridHeader};
+use crate::core::indexer::FlatIndexer;
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+
+/// signed distance map of a reference shape(phi < 0 inside) on its own grid.
+///
+/// file format, all values little endian:
+/// - magic "LSDM"(4 bytes)
+/// - dimension as u32, 2 or 3
+/// - extents as u32 for each axis, x first
+/// - phi as f64, x varying fastest
+#[derive(Clone)]
+pub struct ReferenceMap {
+    pub extents: Vec<i32>,
+    pub phi: Vec<f64>,
+}
+
+impl ReferenceMap {
+    const MAGIC: &'static [u8; 4] = b"LSDM";
+
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{:?}: {}", path, e))?;
+        let invalid = || format!("{:?}: not a signed distance map", path);
+        let header = GridHeader::read(&bytes, Self::MAGIC).ok_or_else(invalid)?;
+        let length = header
+            .total
+            .checked_mul(8)
+            .and_then(|n| n.checked_add(header.offset))
+            .ok_or_else(invalid)?;
+        if bytes.len() != length {
+            return Err(invalid());
+        }
+        Ok(Self {
+            extents: header.extents,
+            phi: grid_file::read_values(&bytes[header.offset..]),
+        })
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        let mut bytes = Vec::with_capacity(8 + 4 * self.extents.len() + 8 * self.phi.len());
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&(self.extents.len() as u32).to_le_bytes());
+        for e in &self.extents {
+            bytes.extend_from_slice(&(*e as u32).to_le_bytes());
+        }
+        for v in &self.phi {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        std::fs::write(path, bytes).map_err(|e| format!("{:?}: {}", path, e))
+    }
+}
+
+/// similarity transform x = scale * rotation * u + translation
+/// from the reference coordinates u to the space coordinates x.
+#[derive(Clone, Debug)]
+pub struct Pose {
+    pub translation: Vec<f64>,
+    /// row-major dimension x dimension matrix
+    pub rotation: Vec<f64>,
+    pub scale: f64,
+}
+
+impl Pose {
+    pub fn identity(dimension: usize) -> Self {
+        let mut rotation = vec![0.0; dimension * dimension];
+        for k in 0..dimension {
+            rotation[k * dimension + k] = 1.0;
+        }
+        Self {
+            translation: vec![0.0; dimension],
+            rotation,
+            scale: 1.0,
+        }
+    }
+
+    /// u = rotation^T * (x - translation) / scale
+    pub fn to_reference(&self, x: &[f64]) -> Vec<f64> {
+        let d = x.len();
+        (0..d)
+            .map(|i| {
+                (0..d)
+                    .map(|j| self.rotation[j * d + i] * (x[j] - self.translation[j]))
+                    .sum::<f64>()
+                    / self.scale
+            })
+            .collect()
+    }
+}
+
+/// shape prior pulling phi toward the reference signed distance map placed by a pose.
+pub trait ShapePriorMethod<T, P> {
+    fn new(space_size: &T) -> Self;
+    /// fails unless the reference has the dimension of the space and a value per point of its grid.
+    fn set_reference(&mut self, reference: ReferenceMap) -> Result<(), String>;
+    fn has_reference(&self) -> bool;
+    /// the signed distance of the placed reference at p
+    fn get_value(&self, p: &P) -> f64;
+    /// aligns the centroid, the volume and the principal axes of the reference to phi < 0.
+    fn estimate_pose(&mut self, phi: &[f64]);
+    fn get_pose(&self) -> &Pose;
+    fn set_pose(&mut self, pose: Pose);
+}
+
+/// centroid, volume and principal axes(columns, descending variance) of phi < 0
+struct Moments {
+    centroid: Vec<f64>,
+    volume: f64,
+    axes: Vec<f64>,
+}
+
+fn calculate_moments(phi: &[f64], extents: &[i32]) -> Option<Moments> {
+    let d = extents.len();
+    let indexer = FlatIndexer::new(extents);
+    let points: Vec<Vec<f64>> = (0..phi.len())
+        .filter(|i| phi[*i] < 0.0)
+        .map(|i| {
+            (0..d)
+                .map(|axis| indexer.coordinate(i, axis) as f64)
+                .collect()
+        })
+        .collect();
+    if points.is_empty() {
+        return None;
+    }
+    let n = points.len() as f64;
+    let centroid: Vec<f64> = (0..d)
+        .map(|k| points.iter().map(|x| x[k]).sum::<f64>() / n)
+        .collect();
+    let mut covariance = vec![0.0; d * d];
+    for x in &points {
+        for i in 0..d {
+            for j in 0..d {
+                covariance[i * d + j] += (x[i] - centroid[i]) * (x[j] - centroid[j]) / n;
+            }
+        }
+    }
+    let (_, mut axes) = jacobi_eigen(&covariance, d);
+    // the sign of each axis follows the skewness of the shape along it
+    for k in 0..d {
+        let skewness: f64 = points
+            .iter()
+            .map(|x| {
+                (0..d)
+                    .map(|i| (x[i] - centroid[i]) * axes[i * d + k])
+                    .sum::<f64>()
+                    .powi(3)
+            })
+            .sum();
+        if skewness < 0.0 {
+            for i in 0..d {
+                axes[i * d + k] = -axes[i * d + k];
+            }
+        }
+    }
+    if determinant(&axes, d) < 0.0 {
+        for i in 0..d {
+            axes[i * d + d - 1] = -axes[i * d + d - 1];
+        }
+    }
+    Some(Moments {
+        centroid,
+        volume: n,
+        axes,
+    })
+}
+
+/// eigenvalues of a symmetric row-major d x d matrix in descending order and
+/// the eigenvectors as the columns in the same order (cyclic Jacobi).
+pub fn jacobi_eigen(matrix: &[f64], d: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut a = matrix.to_vec();
+    let mut v = Pose::identity(d).rotation;
+    let norm: f64 = a.iter().map(|x| x * x).sum();
+    for _ in 0..50 {
+        let off: f64 = (0..d)
+            .flat_map(|i| (0..d).filter(move |j| *j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i * d + j] * a[i * d + j])
+            .sum();
+        if off <= 1.0e-24 * norm {
+            break;
+        }
+        for p in 0..d {
+            for q in (p + 1)..d {
+                if a[p * d + q].abs() < 1.0e-30 {
+                    continue;
+                }
+                let theta = 0.5 * (2.0 * a[p * d + q]).atan2(a[q * d + q] - a[p * d + p]);
+                let (s, c) = theta.sin_cos();
+                for k in 0..d {
+                    let (akp, akq) = (a[k * d + p], a[k * d + q]);
+                    a[k * d + p] = c * akp - s * akq;
+                    a[k * d + q] = s * akp + c * akq;
+                }
+                for k in 0..d {
+                    let (apk, aqk) = (a[p * d + k], a[q * d + k]);
+                    a[p * d + k] = c * apk - s * aqk;
+                    a[q * d + k] = s * apk + c * aqk;
+                }
+                for k in 0..d {
+                    let (vkp, vkq) = (v[k * d + p], v[k * d + q]);
+                    v[k * d + p] = c * vkp - s * vkq;
+                    v[k * d + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..d).collect();
+    order.sort_by(|i, j| a[j * d + j].total_cmp(&a[i * d + i]));
+    let mut sorted = vec![0.0; d * d];
+    for (k, o) in order.iter().enumerate() {
+        for i in 0..d {
+            sorted[i * d + k] = v[i * d + o];
+        }
+    }
+    (order.iter().map(|o| a[o * d + o]).collect(), sorted)
+}
+
+fn determinant(m: &[f64], d: usize) -> f64 {
+    match d {
+        2 => m[0] * m[3] - m[1] * m[2],
+        _ => {
+            m[0] * (m[4] * m[8] - m[5] * m[7]) - m[1] * (m[3] * m[8] - m[5] * m[6])
+                + m[2] * (m[3] * m[7] - m[4] * m[6])
+        }
+    }
+}
+
+struct ShapePrior {
+    extents: Vec<i32>,
+    reference: Option<ReferenceMap>,
+    /// moments of the reference, None if it has no inside
+    reference_moments: Option<Moments>,
+    pose: Pose,
+}
+
+impl ShapePrior {
+    fn new(extents: Vec<i32>) -> Self {
+        let d = extents.len();
+        Self {
+            extents,
+            reference: None,
+            reference_moments: None,
+            pose: Pose::identity(d),
+        }
+    }
+
+    fn set_reference(&mut self, reference: ReferenceMap) -> Result<(), String> {
+        let d = self.extents.len();
+        if reference.extents.len() != d {
+            return Err(format!("the reference must be {}D", d));
+        }
+        let total = reference.extents.iter().try_fold(1usize, |t, e| {
+            usize::try_from(*e).ok().and_then(|e| t.checked_mul(e))
+        });
+        if total != Some(reference.phi.len()) || reference.phi.is_empty() {
+            return Err("the reference must have a value per point of its grid".to_string());
+        }
+        self.reference_moments = calculate_moments(&reference.phi, &reference.extents);
+        self.reference = Some(reference);
+        Ok(())
+    }
+
+    /// multilinear interpolation of the reference, clamped to its grid
+    fn get_value(&self, x: &[f64]) -> f64 {
+        let reference = match &self.reference {
+            Some(r) => r,
+            None => return 0.0,
+        };
+        let u = self.pose.to_reference(x);
+        let d = u.len();
+        let mut base = vec![0usize; d];
+        let mut fraction = vec![0.0; d];
+        for k in 0..d {
+            let c = u[k].clamp(0.0, (reference.extents[k] - 1) as f64);
+            let b = (c.floor() as i32).min(reference.extents[k] - 2).max(0);
+            base[k] = b as usize;
+            fraction[k] = if reference.extents[k] > 1 {
+                c - b as f64
+            } else {
+                0.0
+            };
+        }
+        let mut value = 0.0;
+        for corner in 0..(1 << d) {
+            let mut weight = 1.0;
+            let mut index = 0usize;
+            let mut stride = 1usize;
+            for k in 0..d {
+                let upper = (corner >> k) & 1 == 1 && reference.extents[k] > 1;
+                weight *= if upper {
+                    fraction[k]
+                } else {
+                    1.0 - fraction[k]
+                };
+                index += (base[k] + upper as usize) * stride;
+                stride *= reference.extents[k] as usize;
+            }
+            value += weight * reference.phi[index];
+        }
+        self.pose.scale * value
+    }
+
+    fn estimate_pose(&mut self, phi: &[f64]) {
+        let (current, target) = match (
+            calculate_moments(phi, &self.extents),
+            &self.reference_moments,
+        ) {
+            (Some(c), Some(t)) => (c, t),
+            _ => return,
+        };
+        let d = self.extents.len();
+        let scale = (current.volume / target.volume).powf(1.0 / d as f64);
+        let mut rotation = vec![0.0; d * d];
+        for i in 0..d {
+            for j in 0..d {
+                rotation[i * d + j] = (0..d)
+                    .map(|k| current.axes[i * d + k] * target.axes[j * d + k])
+                    .sum();
+            }
+        }
+        let translation = (0..d)
+            .map(|i| {
+                current.centroid[i]
+                    - scale
+                        * (0..d)
+                            .map(|j| rotation[i * d + j] * target.centroid[j])
+                            .sum::<f64>()
+            })
+            .collect();
+        self.pose = Pose {
+            translation,
+            rotation,
+            scale,
+        };
+    }
+}
+
+pub struct ShapePrior2d {
+    shape_prior: ShapePrior,
+}
+
+impl ShapePriorMethod<SpaceSize2d, Point2d<i32>> for ShapePrior2d {
+    fn new(space_size: &SpaceSize2d) -> Self {
+        Self {
+            shape_prior: ShapePrior::new(vec![space_size.width, space_size.height]),
+        }
+    }
+
+    fn set_reference(&mut self, reference: ReferenceMap) -> Result<(), String> {
+        self.shape_prior.set_reference(reference)
+    }
+
+    fn has_reference(&self) -> bool {
+        self.shape_prior.reference.is_some()
+    }
+
+    fn get_value(&self, p: &Point2d<i32>) -> f64 {
+        self.shape_prior.get_value(&[p.x as f64, p.y as f64])
+    }
+
+    fn estimate_pose(&mut self, phi: &[f64]) {
+        self.shape_prior.estimate_pose(phi);
+    }
+
+    fn get_pose(&self) -> &Pose {
+        &self.shape_prior.pose
+    }
+
+    fn set_pose(&mut self, pose: Pose) {
+        self.shape_prior.pose = pose;
+    }
+}
+
+pub struct ShapePrior3d {
+    shape_prior: ShapePrior,
+}
+
+impl ShapePriorMethod<SpaceSize3d, Point3d<i32>> for ShapePrior3d {
+    fn new(space_size: &SpaceSize3d) -> Self {
+        Self {
+            shape_prior: ShapePrior::new(vec![
+                space_size.width,
+                space_size.height,
+                space_size.depth,
+            ]),
+        }
+    }
+
+    fn set_reference(&mut self, reference: ReferenceMap) -> Result<(), String> {
+        self.shape_prior.set_reference(reference)
+    }
+
+    fn has_reference(&self) -> bool {
+        self.shape_prior.reference.is_some()
+    }
+
+    fn get_value(&self, p: &Point3d<i32>) -> f64 {
+        self.shape_prior
+            .get_value(&[p.x as f64, p.y as f64, p.z as f64])
+    }
+
+    fn estimate_pose(&mut self, phi: &[f64]) {
+        self.shape_prior.estimate_pose(phi);
+    }
+
+    fn get_pose(&self) -> &Pose {
+        &self.shape_prior.pose
+    }
+
+    fn set_pose(&mut self, pose: Pose) {
+        self.shape_prior.pose = pose;
+    }
+}
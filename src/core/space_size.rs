@@ -1,5 +1,8 @@
 pub trait SpaceSizeMethod {
     fn get_total(&self) -> usize;
+    fn get_dimension(&self) -> usize;
+    /// number of points along x, y(, z)
+    fn get_extents(&self) -> Vec<i32>;
 }
 
 #[derive(Clone)]
@@ -23,6 +26,14 @@ impl SpaceSizeMethod for SpaceSize2d {
     fn get_total(&self) -> usize {
         self.total as usize
     }
+
+    fn get_dimension(&self) -> usize {
+        2
+    }
+
+    fn get_extents(&self) -> Vec<i32> {
+        vec![self.width, self.height]
+    }
 }
 
 #[derive(Clone)]
@@ -48,4 +59,12 @@ impl SpaceSizeMethod for SpaceSize3d {
     fn get_total(&self) -> usize {
         self.total as usize
     }
+
+    fn get_dimension(&self) -> usize {
+        3
+    }
+
+    fn get_extents(&self) -> Vec<i32> {
+        vec![self.width, self.height, self.depth]
+    }
 }
@@ -0,0 +1,253 @@
+use crate::core::indexer::FlatIndexer;
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+
+/// sparse field method of Whitaker: phi is kept only on the layers L-2, ..., L2 around the
+/// zero-levelset, which are maintained incrementally so that each update touches the layers only.
+pub trait SparseFieldMethod<T, P> {
+    fn new(space_size: &T) -> Self;
+    /// builds the layers from phi, which has to be a signed distance on the band.
+    fn initialize(&mut self, phi: &mut [f64], band: &[usize]);
+    /// subtracts changes, given in the order of get_active_layer, from phi on the active layer
+    /// and moves the points between the layers. every change has to be within [-0.5, 0.5].
+    fn update(&mut self, phi: &mut [f64], changes: &[f64]);
+    /// points of the active layer L0
+    fn get_active_layer(&self) -> Vec<P>;
+    /// indices of the points of the layer in -2..=2
+    fn get_layer(&self, layer: i32) -> &[usize];
+}
+
+const FAR_INSIDE: i8 = -3;
+const FAR_OUTSIDE: i8 = 3;
+
+/// the range of phi on the layer k is [k - 0.5, k + 0.5].
+fn layer_of(value: f64) -> i8 {
+    let k = value.round();
+    if k < -2.0 {
+        FAR_INSIDE
+    } else if k > 2.0 {
+        FAR_OUTSIDE
+    } else {
+        k as i8
+    }
+}
+
+struct SparseField {
+    indexer: FlatIndexer,
+    labels: Vec<i8>,
+    /// layers[k + 2] holds the indices of L(k)
+    layers: Vec<Vec<usize>>,
+}
+
+impl SparseField {
+    fn new(extents: Vec<i32>) -> Self {
+        let indexer = FlatIndexer::new(&extents);
+        let total = indexer.get_total();
+        Self {
+            indexer,
+            labels: vec![FAR_OUTSIDE; total],
+            layers: vec![Vec::new(); 5],
+        }
+    }
+
+    fn layer(&self, k: i8) -> &Vec<usize> {
+        &self.layers[(k + 2) as usize]
+    }
+
+    fn initialize(&mut self, phi: &mut [f64], band: &[usize]) {
+        for (i, v) in phi.iter().enumerate() {
+            self.labels[i] = if *v < 0.0 { FAR_INSIDE } else { FAR_OUTSIDE };
+        }
+        for layer in self.layers.iter_mut() {
+            layer.clear();
+        }
+        for i in band {
+            let k = layer_of(phi[*i]);
+            self.labels[*i] = k;
+            if k.abs() <= 2 {
+                self.layers[(k + 2) as usize].push(*i);
+            }
+        }
+        // completes L-2, L-1, L1, L2 where the band is too thin for them
+        for k in [0, 1] {
+            for sign in [-1, 1] {
+                let from = if k == 0 { 0 } else { sign * k };
+                let to = sign * (k + 1);
+                let far = if sign < 0 { FAR_INSIDE } else { FAR_OUTSIDE };
+                for i in self.layer(from).clone() {
+                    for j in self.indexer.neighbors(i).into_iter().flatten() {
+                        if self.labels[j] == far {
+                            self.labels[j] = to;
+                            phi[j] = phi[i] + sign as f64;
+                            self.layers[(to + 2) as usize].push(j);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// the value of i from its neighbors in the layer closer to the active layer,
+    /// None if there are no such neighbors.
+    fn value_from_inner_layer(&self, phi: &[f64], i: usize, inner: i8, sign: f64) -> Option<f64> {
+        let mut value: Option<f64> = None;
+        for j in self.indexer.neighbors(i).into_iter().flatten() {
+            if self.labels[j] == inner {
+                let v = phi[j] + sign;
+                value = Some(match value {
+                    Some(w) if sign < 0.0 => w.max(v),
+                    Some(w) => w.min(v),
+                    None => v,
+                });
+            }
+        }
+        value
+    }
+
+    fn update(&mut self, phi: &mut [f64], changes: &[f64]) {
+        // status lists: points moving to the layer k are in moves[k + 2], to the far region in removes
+        let mut moves: Vec<Vec<usize>> = vec![Vec::new(); 5];
+        let mut removes = Vec::new();
+
+        // the active layer
+        let mut kept: Vec<Vec<usize>> = vec![Vec::new(); 5];
+        for (i, change) in self.layer(0).iter().zip(changes) {
+            phi[*i] -= change;
+            match layer_of(phi[*i]) {
+                0 => kept[2].push(*i),
+                k => moves[(k.signum() + 2) as usize].push(*i),
+            }
+        }
+
+        // the other layers from the active layer outward, the labels are not changed yet
+        for k in [-1i8, 1, -2, 2] {
+            let inner = k - k.signum();
+            let sign = k.signum() as f64;
+            for i in self.layer(k).clone() {
+                match self.value_from_inner_layer(phi, i, inner, sign) {
+                    None if k.abs() == 2 => removes.push(i),
+                    None => {
+                        // at least 1.5 away from the zero-levelset without active neighbors
+                        phi[i] = k as f64 + 0.5 * sign;
+                        moves[(k + k.signum() + 2) as usize].push(i);
+                    }
+                    Some(v) => {
+                        phi[i] = v;
+                        let to = layer_of(v);
+                        if to == k {
+                            kept[(k + 2) as usize].push(i);
+                        } else if to.abs() > 2 {
+                            removes.push(i);
+                        } else if to.abs() < k.abs() {
+                            moves[(inner + 2) as usize].push(i);
+                        } else {
+                            moves[(k + k.signum() + 2) as usize].push(i);
+                        }
+                    }
+                }
+            }
+        }
+
+        for i in removes {
+            let far = if phi[i] < 0.0 {
+                FAR_INSIDE
+            } else {
+                FAR_OUTSIDE
+            };
+            self.labels[i] = far;
+            phi[i] = far as f64;
+        }
+        for k in [0i8, -1, 1, -2, 2] {
+            for i in std::mem::take(&mut moves[(k + 2) as usize]) {
+                self.labels[i] = k;
+                kept[(k + 2) as usize].push(i);
+                if k.abs() == 1 {
+                    // the far neighbors of a new L(-1)/L(1) point form L(-2)/L(2)
+                    let far = if k < 0 { FAR_INSIDE } else { FAR_OUTSIDE };
+                    for j in self.indexer.neighbors(i).into_iter().flatten() {
+                        if self.labels[j] == far {
+                            self.labels[j] = 2 * k;
+                            phi[j] = phi[i] + k as f64;
+                            kept[(2 * k + 2) as usize].push(j);
+                        }
+                    }
+                }
+            }
+        }
+        self.layers = kept;
+    }
+}
+
+pub struct SparseField2d {
+    sparse_field: SparseField,
+}
+
+impl SparseFieldMethod<SpaceSize2d, Point2d<i32>> for SparseField2d {
+    fn new(space_size: &SpaceSize2d) -> Self {
+        Self {
+            sparse_field: SparseField::new(vec![space_size.width, space_size.height]),
+        }
+    }
+
+    fn initialize(&mut self, phi: &mut [f64], band: &[usize]) {
+        self.sparse_field.initialize(phi, band);
+    }
+
+    fn update(&mut self, phi: &mut [f64], changes: &[f64]) {
+        self.sparse_field.update(phi, changes);
+    }
+
+    fn get_active_layer(&self) -> Vec<Point2d<i32>> {
+        self.sparse_field
+            .layer(0)
+            .iter()
+            .map(|i| {
+                let c = |axis| self.sparse_field.indexer.coordinate(*i, axis);
+                Point2d::<i32>::new(c(0), c(1))
+            })
+            .collect()
+    }
+
+    fn get_layer(&self, layer: i32) -> &[usize] {
+        self.sparse_field.layer(layer as i8)
+    }
+}
+
+pub struct SparseField3d {
+    sparse_field: SparseField,
+}
+
+impl SparseFieldMethod<SpaceSize3d, Point3d<i32>> for SparseField3d {
+    fn new(space_size: &SpaceSize3d) -> Self {
+        Self {
+            sparse_field: SparseField::new(vec![
+                space_size.width,
+                space_size.height,
+                space_size.depth,
+            ]),
+        }
+    }
+
+    fn initialize(&mut self, phi: &mut [f64], band: &[usize]) {
+        self.sparse_field.initialize(phi, band);
+    }
+
+    fn update(&mut self, phi: &mut [f64], changes: &[f64]) {
+        self.sparse_field.update(phi, changes);
+    }
+
+    fn get_active_layer(&self) -> Vec<Point3d<i32>> {
+        self.sparse_field
+            .layer(0)
+            .iter()
+            .map(|i| {
+                let c = |axis| self.sparse_field.indexer.coordinate(*i, axis);
+                Point3d::<i32>::new(c(0), c(1), c(2))
+            })
+            .collect()
+    }
+
+    fn get_layer(&self, layer: i32) -> &[usize] {
+        self.sparse_field.layer(layer as i8)
+    }
+}
@@ -1,38 +1,127 @@
-use crate::core::differential::{Differential2d, Differential3d, DifferentialMethod};
+use crate::core::differential::{DifferentialDouble2d, DifferentialDouble3d, DifferentialMethod};
+use crate::core::gaussian;
+use crate::core::gradient_vector_flow::VectorField;
 use crate::core::indexer::{Indexer2d, Indexer3d};
+use crate::core::parameters::{EdgeFunction, Parameters};
 use crate::core::point::{Point2d, Point3d};
-use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d, SpaceSizeMethod};
 use std::cell::RefCell;
 use std::rc::Rc;
 
 use super::speed::Speed;
+
+/// edge-stopping function of the speed factors and the Gaussian smoothing before grad I.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeStopping {
+    pub function: EdgeFunction,
+    /// no smoothing for 0
+    pub sigma: f64,
+    pub k: f64,
+    pub power: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl EdgeStopping {
+    pub fn from_parameters(parameters: &Parameters) -> Self {
+        Self {
+            function: parameters.edge_function,
+            sigma: parameters.edge_sigma,
+            k: parameters.edge_k,
+            power: parameters.edge_power,
+            alpha: parameters.sigmoid_alpha,
+            beta: parameters.sigmoid_beta,
+        }
+    }
+
+    /// g(|grad I|)
+    pub fn get_value(&self, norm: f64) -> f64 {
+        match self.function {
+            EdgeFunction::Rational => 1.0 / (1.0 + (norm / self.k).powf(self.power)),
+            EdgeFunction::Exponential => (-(norm * norm) / (self.k * self.k)).exp(),
+            EdgeFunction::Sigmoid => 1.0 / (1.0 + (-(norm - self.beta) / self.alpha).exp()),
+        }
+    }
+
+    /// the gray image smoothed by the Gaussian of sigma.
+    pub fn smooth(&self, gray: &[u8], extents: &[i32]) -> Vec<f64> {
+        let values: Vec<f64> = gray.iter().map(|v| *v as f64).collect();
+        if self.sigma == 0.0 {
+            return values;
+        }
+        gaussian::smooth(&values, extents, self.sigma)
+    }
+}
+
 pub trait SpeedFactorMethod<T, P, S> {
     fn new(indexer: Rc<T>, gray: Rc<RefCell<Vec<u8>>>) -> Self;
+    /// replaces 1/(1+|grad I|) without smoothing, used until this is called.
+    fn set_edge_stopping(&mut self, edge_stopping: EdgeStopping);
+    /// makes calculate_all diffuse the gradient of the edge map 1 - g
+    /// by iterations of the gradient vector flow with mu.
+    fn set_gradient_vector_flow(&mut self, iterations: usize, mu: f64);
     fn get_value(&self, p: &P) -> f64;
+    /// gradient of the factors by central differences, one value per axis.
+    /// it is zero next to the edge of the space.
+    fn get_gradient(&self, p: &P) -> &[f64];
     fn calculate_all(&mut self, space_size: &Rc<S>);
+    /// None unless set_gradient_vector_flow has been called before calculate_all.
+    fn get_vector_field(&self) -> Option<&VectorField>;
 }
 
 pub struct SpeedFactor2d {
     indexer: Rc<Indexer2d>,
-    differential: Differential2d<u8>,
+    gray: Rc<RefCell<Vec<u8>>>,
+    /// gray image smoothed by edge_stopping
+    smoothed: Rc<RefCell<Vec<f64>>>,
+    differential: DifferentialDouble2d,
+    edge_stopping: EdgeStopping,
     factors: Vec<f64>,
+    gradients: Vec<f64>,
+    /// iterations and mu of the gradient vector flow
+    gvf: Option<(usize, f64)>,
+    vector_field: Option<VectorField>,
 }
 
 impl SpeedFactorMethod<Indexer2d, Point2d<i32>, SpaceSize2d> for SpeedFactor2d {
     fn new(indexer: Rc<Indexer2d>, gray: Rc<RefCell<Vec<u8>>>) -> Self {
+        let smoothed = Rc::new(RefCell::new(Vec::new()));
         Self {
             indexer: Rc::clone(&indexer),
-            differential: Differential2d::<u8>::new(Rc::clone(&indexer), Rc::clone(&gray)),
+            gray: Rc::clone(&gray),
+            smoothed: Rc::clone(&smoothed),
+            differential: DifferentialDouble2d::new(Rc::clone(&indexer), smoothed),
+            edge_stopping: EdgeStopping::from_parameters(&Parameters::new()),
             factors: Vec::<f64>::new(),
+            gradients: Vec::<f64>::new(),
+            gvf: None,
+            vector_field: None,
         }
     }
 
+    fn set_edge_stopping(&mut self, edge_stopping: EdgeStopping) {
+        self.edge_stopping = edge_stopping;
+    }
+
+    fn set_gradient_vector_flow(&mut self, iterations: usize, mu: f64) {
+        self.gvf = Some((iterations, mu));
+    }
+
     fn get_value(&self, p: &Point2d<i32>) -> f64 {
         use crate::core::indexer::IndexerMethod;
         self.factors[self.indexer.get(p) as usize]
     }
 
+    fn get_gradient(&self, p: &Point2d<i32>) -> &[f64] {
+        use crate::core::indexer::IndexerMethod;
+        let i = 2 * self.indexer.get(p) as usize;
+        &self.gradients[i..(i + 2)]
+    }
+
     fn calculate_all(&mut self, space_size: &Rc<SpaceSize2d>) {
+        *self.smoothed.borrow_mut() = self
+            .edge_stopping
+            .smooth(&self.gray.borrow(), &space_size.get_extents());
         let w = space_size.width as usize;
         let h = space_size.height as usize;
         self.factors.resize(w * h, 0.0);
@@ -43,6 +132,21 @@ impl SpeedFactorMethod<Indexer2d, Point2d<i32>, SpaceSize2d> for SpeedFactor2d {
                 self.factors[wj + i] = self.calculate(&p);
             }
         }
+
+        self.gradients.resize(2 * w * h, 0.0);
+        for j in 2..(h.max(2) - 2) {
+            let wj = w * j;
+            for i in 2..(w.max(2) - 2) {
+                let c = wj + i;
+                self.gradients[2 * c] = 0.5 * (self.factors[c + 1] - self.factors[c - 1]);
+                self.gradients[2 * c + 1] = 0.5 * (self.factors[c + w] - self.factors[c - w]);
+            }
+        }
+        self.vector_field = self.diffuse_gradients(&space_size.get_extents());
+    }
+
+    fn get_vector_field(&self) -> Option<&VectorField> {
+        self.vector_field.as_ref()
     }
 }
 
@@ -51,30 +155,74 @@ impl SpeedFactor2d {
         self.differential.make_point(p);
         let dx = self.differential.fx();
         let dy = self.differential.fy();
-        1.0 / (1.0 + (dx * dx + dy * dy).sqrt())
+        self.edge_stopping.get_value((dx * dx + dy * dy).sqrt())
+    }
+
+    /// the gradient vector flow of grad(1 - g) = -grad(g)
+    fn diffuse_gradients(&self, extents: &[i32]) -> Option<VectorField> {
+        let (iterations, mu) = self.gvf?;
+        let edge_gradient: Vec<f64> = self.gradients.iter().map(|g| -g).collect();
+        Some(VectorField::diffuse(
+            extents,
+            &edge_gradient,
+            iterations,
+            mu,
+        ))
     }
 }
 pub struct SpeedFactor3d {
     indexer: Rc<Indexer3d>,
-    differential: Differential3d<u8>,
+    gray: Rc<RefCell<Vec<u8>>>,
+    /// gray image smoothed by edge_stopping
+    smoothed: Rc<RefCell<Vec<f64>>>,
+    differential: DifferentialDouble3d,
+    edge_stopping: EdgeStopping,
     factors: Vec<f64>,
+    gradients: Vec<f64>,
+    /// iterations and mu of the gradient vector flow
+    gvf: Option<(usize, f64)>,
+    vector_field: Option<VectorField>,
 }
 
 impl SpeedFactorMethod<Indexer3d, Point3d<i32>, SpaceSize3d> for SpeedFactor3d {
     fn new(indexer: Rc<Indexer3d>, gray: Rc<RefCell<Vec<u8>>>) -> Self {
+        let smoothed = Rc::new(RefCell::new(Vec::new()));
         Self {
             indexer: Rc::clone(&indexer),
-            differential: Differential3d::<u8>::new(Rc::clone(&indexer), Rc::clone(&gray)),
+            gray: Rc::clone(&gray),
+            smoothed: Rc::clone(&smoothed),
+            differential: DifferentialDouble3d::new(Rc::clone(&indexer), smoothed),
+            edge_stopping: EdgeStopping::from_parameters(&Parameters::new()),
             factors: Vec::<f64>::new(),
+            gradients: Vec::<f64>::new(),
+            gvf: None,
+            vector_field: None,
         }
     }
 
+    fn set_edge_stopping(&mut self, edge_stopping: EdgeStopping) {
+        self.edge_stopping = edge_stopping;
+    }
+
+    fn set_gradient_vector_flow(&mut self, iterations: usize, mu: f64) {
+        self.gvf = Some((iterations, mu));
+    }
+
     fn get_value(&self, p: &Point3d<i32>) -> f64 {
         use crate::core::indexer::IndexerMethod;
         self.factors[self.indexer.get(p) as usize]
     }
 
+    fn get_gradient(&self, p: &Point3d<i32>) -> &[f64] {
+        use crate::core::indexer::IndexerMethod;
+        let i = 3 * self.indexer.get(p) as usize;
+        &self.gradients[i..(i + 3)]
+    }
+
     fn calculate_all(&mut self, space_size: &Rc<SpaceSize3d>) {
+        *self.smoothed.borrow_mut() = self
+            .edge_stopping
+            .smooth(&self.gray.borrow(), &space_size.get_extents());
         let w = space_size.width as usize;
         let h = space_size.height as usize;
         let a = w * h;
@@ -91,6 +239,25 @@ impl SpeedFactorMethod<Indexer3d, Point3d<i32>, SpaceSize3d> for SpeedFactor3d {
                 }
             }
         }
+
+        self.gradients.resize(3 * a * d, 0.0);
+        for k in 2..(d.max(2) - 2) {
+            let ak = a * k;
+            for j in 2..(h.max(2) - 2) {
+                let wj = w * j + ak;
+                for i in 2..(w.max(2) - 2) {
+                    let c = wj + i;
+                    self.gradients[3 * c] = 0.5 * (self.factors[c + 1] - self.factors[c - 1]);
+                    self.gradients[3 * c + 1] = 0.5 * (self.factors[c + w] - self.factors[c - w]);
+                    self.gradients[3 * c + 2] = 0.5 * (self.factors[c + a] - self.factors[c - a]);
+                }
+            }
+        }
+        self.vector_field = self.diffuse_gradients(&space_size.get_extents());
+    }
+
+    fn get_vector_field(&self) -> Option<&VectorField> {
+        self.vector_field.as_ref()
     }
 }
 
@@ -104,6 +271,19 @@ impl SpeedFactor3d {
         let dx = self.differential.fx();
         let dy = self.differential.fy();
         let dz = self.differential.fz();
-        1.0 / (1.0 + (dx * dx + dy * dy + dz * dz).sqrt())
+        self.edge_stopping
+            .get_value((dx * dx + dy * dy + dz * dz).sqrt())
+    }
+
+    /// the gradient vector flow of grad(1 - g) = -grad(g)
+    fn diffuse_gradients(&self, extents: &[i32]) -> Option<VectorField> {
+        let (iterations, mu) = self.gvf?;
+        let edge_gradient: Vec<f64> = self.gradients.iter().map(|g| -g).collect();
+        Some(VectorField::diffuse(
+            extents,
+            &edge_gradient,
+            iterations,
+            mu,
+        ))
     }
 }
@@ -0,0 +1,195 @@
+use crate::core::observer::IterationState;
+use crate::core::stopping_condition::StoppingCondition;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// decides when the evolution stops.
+/// check is called once per speed update, so implementations may keep a history.
+pub trait StoppingCriterion<IntPoint> {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool;
+}
+
+/// the default rule: three consecutive total speeds are almost equal.
+impl<IntPoint> StoppingCriterion<IntPoint> for StoppingCondition {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool {
+        self.add_total_speed(state.total_speed);
+        self.is_satisfied()
+    }
+}
+
+pub struct MaxIterations {
+    max_iterations: usize,
+}
+
+impl MaxIterations {
+    pub fn new(max_iterations: usize) -> Self {
+        Self { max_iterations }
+    }
+}
+
+impl<IntPoint> StoppingCriterion<IntPoint> for MaxIterations {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool {
+        state.iteration >= self.max_iterations
+    }
+}
+
+/// the clock starts at the first check.
+pub struct WallClockBudget {
+    budget: Duration,
+    start: Option<Instant>,
+}
+
+impl WallClockBudget {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            start: None,
+        }
+    }
+}
+
+impl<IntPoint> StoppingCriterion<IntPoint> for WallClockBudget {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        start.elapsed() >= self.budget
+    }
+}
+
+/// the enclosed area(2D)/volume(3D), the number of points with negative phi,
+/// changes less than tolerance relative to its value window checks before.
+pub struct AreaChange {
+    tolerance: f64,
+    window: usize,
+    areas: VecDeque<f64>,
+}
+
+impl AreaChange {
+    pub fn new(tolerance: f64, window: usize) -> Self {
+        Self {
+            tolerance,
+            window,
+            areas: VecDeque::new(),
+        }
+    }
+}
+
+impl<IntPoint> StoppingCriterion<IntPoint> for AreaChange {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool {
+        let area = state.phi.iter().filter(|v| **v < 0.0).count() as f64;
+        self.areas.push_back(area);
+        if self.areas.len() > self.window + 1 {
+            self.areas.pop_front();
+        }
+        if self.areas.len() < self.window + 1 {
+            return false;
+        }
+        let first = self.areas.front().unwrap();
+        (area - first).abs() <= self.tolerance * first.max(1.0)
+    }
+}
+
+/// at least the given fraction of the front points has zero speed.
+/// a vanished front is regarded as stopped.
+pub struct ZeroSpeedFraction {
+    fraction: f64,
+}
+
+impl ZeroSpeedFraction {
+    pub fn new(fraction: f64) -> Self {
+        Self { fraction }
+    }
+}
+
+impl<IntPoint> StoppingCriterion<IntPoint> for ZeroSpeedFraction {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool {
+        if state.front.is_empty() {
+            return true;
+        }
+        state.zero_count as f64 / state.front.len() as f64 >= self.fraction
+    }
+}
+
+/// the total speed repeats itself with a period of 2..=max_period
+/// over the last window checks.
+pub struct Oscillation {
+    tolerance: f64,
+    window: usize,
+    max_period: usize,
+    history: VecDeque<f64>,
+}
+
+impl Oscillation {
+    pub fn new(tolerance: f64, window: usize, max_period: usize) -> Self {
+        Self {
+            tolerance,
+            window,
+            max_period,
+            history: VecDeque::new(),
+        }
+    }
+
+    fn is_periodic(&self, period: usize) -> bool {
+        let n = self.history.len();
+        if n < self.window + period {
+            return false;
+        }
+        ((n - self.window)..n).all(|i| {
+            let a = self.history[i];
+            let b = self.history[i - period];
+            (a - b).abs() <= self.tolerance * a.abs().max(b.abs()).max(f64::EPSILON)
+        })
+    }
+}
+
+impl<IntPoint> StoppingCriterion<IntPoint> for Oscillation {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool {
+        self.history.push_back(state.total_speed);
+        if self.history.len() > self.window + self.max_period {
+            self.history.pop_front();
+        }
+        (2..=self.max_period).any(|period| self.is_periodic(period))
+    }
+}
+
+/// satisfied when any of the criteria is satisfied.
+/// every criterion is checked so that each one keeps its history.
+pub struct AnyOf<IntPoint> {
+    criteria: Vec<Box<dyn StoppingCriterion<IntPoint>>>,
+}
+
+impl<IntPoint> AnyOf<IntPoint> {
+    pub fn new(criteria: Vec<Box<dyn StoppingCriterion<IntPoint>>>) -> Self {
+        Self { criteria }
+    }
+}
+
+impl<IntPoint> StoppingCriterion<IntPoint> for AnyOf<IntPoint> {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool {
+        let mut is_satisfied = false;
+        for criterion in self.criteria.iter_mut() {
+            is_satisfied |= criterion.check(state);
+        }
+        is_satisfied
+    }
+}
+
+/// satisfied when all of the criteria are satisfied.
+pub struct AllOf<IntPoint> {
+    criteria: Vec<Box<dyn StoppingCriterion<IntPoint>>>,
+}
+
+impl<IntPoint> AllOf<IntPoint> {
+    pub fn new(criteria: Vec<Box<dyn StoppingCriterion<IntPoint>>>) -> Self {
+        Self { criteria }
+    }
+}
+
+impl<IntPoint> StoppingCriterion<IntPoint> for AllOf<IntPoint> {
+    fn check(&mut self, state: &IterationState<IntPoint>) -> bool {
+        let mut is_satisfied = !self.criteria.is_empty();
+        for criterion in self.criteria.iter_mut() {
+            is_satisfied &= criterion.check(state);
+        }
+        is_satisfied
+    }
+}
@@ -0,0 +1,130 @@
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::point::{Point2d, Point3d};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// simple point criterion of digital topology. the inside(phi < 0) is 8-connected and
+/// the outside 4-connected in 2D, 26- and 6-connected in 3D.
+pub trait TopologyMethod<Indexer, Point> {
+    fn new(phi: Rc<RefCell<Vec<f64>>>, indexer: Rc<Indexer>) -> Self;
+    /// true if the sign of phi at p can change without altering the number of
+    /// connected components or holes. p must not be on the edge of the space.
+    fn is_simple(&self, p: &Point) -> bool;
+}
+
+/// offset of the k-th point of a 3x3(x3) neighborhood from its center
+fn offset_of(k: usize) -> [i32; 3] {
+    [
+        (k % 3) as i32 - 1,
+        ((k / 3) % 3) as i32 - 1,
+        (k / 9) as i32 - 1,
+    ]
+}
+
+fn is_adjacent(a: usize, b: usize, full: bool) -> bool {
+    let (oa, ob) = (offset_of(a), offset_of(b));
+    let d: Vec<i32> = (0..3).map(|i| (oa[i] - ob[i]).abs()).collect();
+    if full {
+        d.iter().all(|v| *v <= 1) && a != b
+    } else {
+        d.iter().sum::<i32>() == 1
+    }
+}
+
+/// number of connected components of members, counting only the components
+/// which contain a point 4/6-adjacent to the center if touching is set.
+fn count_components(members: &[bool], full: bool, touching: bool) -> usize {
+    let center = members.len() / 2;
+    let mut visited = vec![false; members.len()];
+    let mut count = 0;
+    for seed in 0..members.len() {
+        if !members[seed] || visited[seed] {
+            continue;
+        }
+        let mut touches = false;
+        let mut stack = vec![seed];
+        visited[seed] = true;
+        while let Some(k) = stack.pop() {
+            touches |= is_adjacent(k, center, false);
+            for l in 0..members.len() {
+                if members[l] && !visited[l] && is_adjacent(k, l, full) {
+                    visited[l] = true;
+                    stack.push(l);
+                }
+            }
+        }
+        if touches || !touching {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// inside flags of the 3x3 neighborhood in the order of x then y, the center is ignored.
+pub fn is_simple_2d(inside: &[bool; 9]) -> bool {
+    let mut foreground = *inside;
+    foreground[4] = false;
+    let mut background = inside.map(|v| !v);
+    background[4] = false;
+    count_components(&foreground, true, false) == 1
+        && count_components(&background, false, true) == 1
+}
+
+/// inside flags of the 3x3x3 neighborhood in the order of x, y then z, the center is ignored.
+pub fn is_simple_3d(inside: &[bool; 27]) -> bool {
+    let mut foreground = *inside;
+    foreground[13] = false;
+    // the background is examined within the 18-neighborhood
+    let mut background = [false; 27];
+    for (k, v) in background.iter_mut().enumerate() {
+        let o = offset_of(k);
+        let distance: i32 = o.iter().map(|c| c.abs()).sum();
+        *v = !inside[k] && 0 < distance && distance <= 2;
+    }
+    count_components(&foreground, true, false) == 1
+        && count_components(&background, false, true) == 1
+}
+
+pub struct Topology2d {
+    phi: Rc<RefCell<Vec<f64>>>,
+    indexer: Rc<Indexer2d>,
+}
+
+impl TopologyMethod<Indexer2d, Point2d<i32>> for Topology2d {
+    fn new(phi: Rc<RefCell<Vec<f64>>>, indexer: Rc<Indexer2d>) -> Self {
+        Self { phi, indexer }
+    }
+
+    fn is_simple(&self, p: &Point2d<i32>) -> bool {
+        let phi = self.phi.borrow();
+        let mut inside = [false; 9];
+        for (k, v) in inside.iter_mut().enumerate() {
+            let o = offset_of(k);
+            let q = Point2d::<i32>::new(p.x + o[0], p.y + o[1]);
+            *v = phi[self.indexer.get(&q) as usize] < 0.0;
+        }
+        is_simple_2d(&inside)
+    }
+}
+
+pub struct Topology3d {
+    phi: Rc<RefCell<Vec<f64>>>,
+    indexer: Rc<Indexer3d>,
+}
+
+impl TopologyMethod<Indexer3d, Point3d<i32>> for Topology3d {
+    fn new(phi: Rc<RefCell<Vec<f64>>>, indexer: Rc<Indexer3d>) -> Self {
+        Self { phi, indexer }
+    }
+
+    fn is_simple(&self, p: &Point3d<i32>) -> bool {
+        let phi = self.phi.borrow();
+        let mut inside = [false; 27];
+        for (k, v) in inside.iter_mut().enumerate() {
+            let o = offset_of(k);
+            let q = Point3d::<i32>::new(p.x + o[0], p.y + o[1], p.z + o[2]);
+            *v = phi[self.indexer.get(&q) as usize] < 0.0;
+        }
+        is_simple_3d(&inside)
+    }
+}
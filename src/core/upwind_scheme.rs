@@ -2,15 +2,51 @@ use super::upwind::{Upwind2d, Upwind3d};
 use crate::core::indexer::{Indexer2d, Indexer3d};
 use crate::core::point::{Point2d, Point3d};
 use crate::core::position::{Position2d, Position3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
 use crate::core::speed::Speed;
 use crate::core::util;
 use std::cell::RefCell;
 use std::cmp;
 use std::rc::Rc;
 
-pub trait UpwindSchemeMethod<T, P> {
-    fn new(t: Rc<T>, phi: Rc<RefCell<Vec<f64>>>) -> Self;
+pub trait UpwindSchemeMethod<S, T, P> {
+    /// narrow band width the stencil of the scheme requires
+    const MIN_BAND_WIDTH: i32 = 1;
+
+    fn new(space_size: &S, t: Rc<T>, phi: Rc<RefCell<Vec<f64>>>) -> Self;
     fn calculate(&mut self, p: &P, speed: Speed) -> f64;
+    /// V・grad(phi) with the velocity V = -gain * gradient(one value per axis).
+    /// each component is differenced on the upwind side of V.
+    fn calculate_advection(&mut self, p: &P, gradient: &[f64], gain: f64) -> f64;
+    /// Russo-Smereka estimate phi0 / |grad(phi0)| of the distance to the zero-levelset,
+    /// None unless the sign of phi0 changes between p and one of its axis neighbors.
+    fn calculate_subcell_distance(&mut self, p: &P, phi0: &[f64]) -> Option<f64>;
+}
+
+/// neighbors holds the pair of (backward, forward) values of phi0 along each axis.
+pub fn subcell_distance(me: f64, neighbors: &[(f64, f64)]) -> Option<f64> {
+    let inside = me < 0.0;
+    let mut crosses = false;
+    let mut norm = 0.0;
+    for (backward, forward) in neighbors {
+        if (*backward < 0.0) != inside || (*forward < 0.0) != inside {
+            crosses = true;
+        }
+        let d = util::max(
+            util::max((forward - backward).abs() / 2.0, (forward - me).abs()),
+            util::max((me - backward).abs(), 1.0e-12),
+        );
+        norm += d * d;
+    }
+    if crosses {
+        Some(me / norm.sqrt())
+    } else {
+        None
+    }
+}
+
+fn upwind_advection(velocity: f64, backward: f64, forward: f64) -> f64 {
+    util::max(velocity, 0.0) * backward + util::min(velocity, 0.0) * forward
 }
 
 pub struct UpwindScheme2d {
@@ -20,8 +56,8 @@ pub struct UpwindScheme2d {
     pub indexer: Rc<Indexer2d>,
 }
 
-impl UpwindSchemeMethod<Indexer2d, Point2d<i32>> for UpwindScheme2d {
-    fn new(indexer: Rc<Indexer2d>, phi: Rc<RefCell<Vec<f64>>>) -> Self {
+impl UpwindSchemeMethod<SpaceSize2d, Indexer2d, Point2d<i32>> for UpwindScheme2d {
+    fn new(_space_size: &SpaceSize2d, indexer: Rc<Indexer2d>, phi: Rc<RefCell<Vec<f64>>>) -> Self {
         Self {
             position: Position2d::new(),
             upwind: Upwind2d::new(),
@@ -42,6 +78,33 @@ impl UpwindSchemeMethod<Indexer2d, Point2d<i32>> for UpwindScheme2d {
             + self.upwind.fdyp.powf(2.0);
         t.sqrt()
     }
+
+    fn calculate_advection(&mut self, p: &Point2d<i32>, gradient: &[f64], gain: f64) -> f64 {
+        self.position.set_position(p, Rc::clone(&self.indexer));
+        let phi = self.phi.borrow();
+        let me = phi[self.position.me as usize];
+        upwind_advection(
+            -gain * gradient[0],
+            me - phi[self.position.left as usize],
+            phi[self.position.right as usize] - me,
+        ) + upwind_advection(
+            -gain * gradient[1],
+            me - phi[self.position.top as usize],
+            phi[self.position.bottom as usize] - me,
+        )
+    }
+
+    fn calculate_subcell_distance(&mut self, p: &Point2d<i32>, phi0: &[f64]) -> Option<f64> {
+        self.position.set_position(p, Rc::clone(&self.indexer));
+        let r = &self.position;
+        subcell_distance(
+            phi0[r.me as usize],
+            &[
+                (phi0[r.left as usize], phi0[r.right as usize]),
+                (phi0[r.top as usize], phi0[r.bottom as usize]),
+            ],
+        )
+    }
 }
 
 impl UpwindScheme2d {
@@ -100,8 +163,8 @@ pub struct UpwindScheme3d {
     pub indexer: Rc<Indexer3d>,
 }
 
-impl UpwindSchemeMethod<Indexer3d, Point3d<i32>> for UpwindScheme3d {
-    fn new(indexer: Rc<Indexer3d>, phi: Rc<RefCell<Vec<f64>>>) -> Self {
+impl UpwindSchemeMethod<SpaceSize3d, Indexer3d, Point3d<i32>> for UpwindScheme3d {
+    fn new(_space_size: &SpaceSize3d, indexer: Rc<Indexer3d>, phi: Rc<RefCell<Vec<f64>>>) -> Self {
         Self {
             position: Position3d::new(),
             upwind: Upwind3d::new(),
@@ -124,6 +187,38 @@ impl UpwindSchemeMethod<Indexer3d, Point3d<i32>> for UpwindScheme3d {
             + self.upwind.fdzp.powf(2.0);
         t.sqrt()
     }
+
+    fn calculate_advection(&mut self, p: &Point3d<i32>, gradient: &[f64], gain: f64) -> f64 {
+        self.position.set_position(p, Rc::clone(&self.indexer));
+        let phi = self.phi.borrow();
+        let me = phi[self.position.me as usize];
+        upwind_advection(
+            -gain * gradient[0],
+            me - phi[self.position.left as usize],
+            phi[self.position.right as usize] - me,
+        ) + upwind_advection(
+            -gain * gradient[1],
+            me - phi[self.position.top as usize],
+            phi[self.position.bottom as usize] - me,
+        ) + upwind_advection(
+            -gain * gradient[2],
+            me - phi[self.position.front as usize],
+            phi[self.position.back as usize] - me,
+        )
+    }
+
+    fn calculate_subcell_distance(&mut self, p: &Point3d<i32>, phi0: &[f64]) -> Option<f64> {
+        self.position.set_position(p, Rc::clone(&self.indexer));
+        let r = &self.position;
+        subcell_distance(
+            phi0[r.me as usize],
+            &[
+                (phi0[r.left as usize], phi0[r.right as usize]),
+                (phi0[r.top as usize], phi0[r.bottom as usize]),
+                (phi0[r.front as usize], phi0[r.back as usize]),
+            ],
+        )
+    }
 }
 
 impl UpwindScheme3d {
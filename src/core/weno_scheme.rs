@@ -0,0 +1,200 @@
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use crate::core::speed::Speed;
+use crate::core::upwind_scheme::{subcell_distance, UpwindSchemeMethod};
+use crate::core::util;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// fifth-order WENO approximation of the one-sided derivatives (minus, plus)
+/// at the center of values = [phi(i-3), ..., phi(i+3)].
+fn weno5_derivatives(values: &[f64; 7]) -> (f64, f64) {
+    let d: Vec<f64> = (0..6).map(|k| values[k + 1] - values[k]).collect();
+    // d[k] is the backward difference at i-2+k.
+    let minus = weno5(d[0], d[1], d[2], d[3], d[4]);
+    let plus = weno5(d[5], d[4], d[3], d[2], d[1]);
+    (minus, plus)
+}
+
+fn weno5(v1: f64, v2: f64, v3: f64, v4: f64, v5: f64) -> f64 {
+    let phi1 = v1 / 3.0 - 7.0 * v2 / 6.0 + 11.0 * v3 / 6.0;
+    let phi2 = -v2 / 6.0 + 5.0 * v3 / 6.0 + v4 / 3.0;
+    let phi3 = v3 / 3.0 + 5.0 * v4 / 6.0 - v5 / 6.0;
+
+    let s1 = 13.0 / 12.0 * (v1 - 2.0 * v2 + v3).powi(2) + 0.25 * (v1 - 4.0 * v2 + 3.0 * v3).powi(2);
+    let s2 = 13.0 / 12.0 * (v2 - 2.0 * v3 + v4).powi(2) + 0.25 * (v2 - v4).powi(2);
+    let s3 = 13.0 / 12.0 * (v3 - 2.0 * v4 + v5).powi(2) + 0.25 * (3.0 * v3 - 4.0 * v4 + v5).powi(2);
+
+    let max_v = [v1, v2, v3, v4, v5]
+        .iter()
+        .fold(0.0, |m: f64, v| util::max(m, v * v));
+    let epsilon = 1.0e-06 * max_v + 1.0e-99;
+
+    let a1 = 0.1 / (s1 + epsilon).powi(2);
+    let a2 = 0.6 / (s2 + epsilon).powi(2);
+    let a3 = 0.3 / (s3 + epsilon).powi(2);
+    (a1 * phi1 + a2 * phi2 + a3 * phi3) / (a1 + a2 + a3)
+}
+
+/// squared derivative of the Godunov Hamiltonian along one axis.
+fn godunov(minus: f64, plus: f64, speed: &Speed) -> f64 {
+    match speed {
+        Speed::Positive => util::max(util::max(minus, 0.0).powi(2), util::min(plus, 0.0).powi(2)),
+        Speed::Negative => util::max(util::min(minus, 0.0).powi(2), util::max(plus, 0.0).powi(2)),
+    }
+}
+
+fn upwind_advection(velocity: f64, minus: f64, plus: f64) -> f64 {
+    util::max(velocity, 0.0) * minus + util::min(velocity, 0.0) * plus
+}
+
+pub struct WenoScheme2d {
+    phi: Rc<RefCell<Vec<f64>>>,
+    indexer: Rc<Indexer2d>,
+    width: i32,
+    height: i32,
+}
+
+impl WenoScheme2d {
+    /// phi(i-3..i+3) along the axis, clamped to the space.
+    fn stencil(&self, p: &Point2d<i32>, axis: usize) -> [f64; 7] {
+        self.stencil_of(&self.phi.borrow(), p, axis)
+    }
+
+    fn stencil_of(&self, phi: &[f64], p: &Point2d<i32>, axis: usize) -> [f64; 7] {
+        let mut values = [0.0; 7];
+        for (k, v) in values.iter_mut().enumerate() {
+            let o = k as i32 - 3;
+            let q = match axis {
+                0 => Point2d::<i32>::new((p.x + o).clamp(0, self.width - 1), p.y),
+                _ => Point2d::<i32>::new(p.x, (p.y + o).clamp(0, self.height - 1)),
+            };
+            *v = phi[self.indexer.get(&q) as usize];
+        }
+        values
+    }
+
+    pub fn derivatives(&self, p: &Point2d<i32>) -> [(f64, f64); 2] {
+        [
+            weno5_derivatives(&self.stencil(p, 0)),
+            weno5_derivatives(&self.stencil(p, 1)),
+        ]
+    }
+}
+
+impl UpwindSchemeMethod<SpaceSize2d, Indexer2d, Point2d<i32>> for WenoScheme2d {
+    const MIN_BAND_WIDTH: i32 = 3;
+
+    fn new(space_size: &SpaceSize2d, indexer: Rc<Indexer2d>, phi: Rc<RefCell<Vec<f64>>>) -> Self {
+        Self {
+            phi: Rc::clone(&phi),
+            indexer: Rc::clone(&indexer),
+            width: space_size.width,
+            height: space_size.height,
+        }
+    }
+
+    fn calculate(&mut self, p: &Point2d<i32>, speed: Speed) -> f64 {
+        self.derivatives(p)
+            .iter()
+            .map(|(minus, plus)| godunov(*minus, *plus, &speed))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    fn calculate_advection(&mut self, p: &Point2d<i32>, gradient: &[f64], gain: f64) -> f64 {
+        self.derivatives(p)
+            .iter()
+            .zip(gradient)
+            .map(|((minus, plus), g)| upwind_advection(-gain * g, *minus, *plus))
+            .sum()
+    }
+
+    fn calculate_subcell_distance(&mut self, p: &Point2d<i32>, phi0: &[f64]) -> Option<f64> {
+        let neighbors: Vec<(f64, f64)> = (0..2)
+            .map(|axis| {
+                let s = self.stencil_of(phi0, p, axis);
+                (s[2], s[4])
+            })
+            .collect();
+        subcell_distance(self.stencil_of(phi0, p, 0)[3], &neighbors)
+    }
+}
+
+pub struct WenoScheme3d {
+    phi: Rc<RefCell<Vec<f64>>>,
+    indexer: Rc<Indexer3d>,
+    width: i32,
+    height: i32,
+    depth: i32,
+}
+
+impl WenoScheme3d {
+    /// phi(i-3..i+3) along the axis, clamped to the space.
+    fn stencil(&self, p: &Point3d<i32>, axis: usize) -> [f64; 7] {
+        self.stencil_of(&self.phi.borrow(), p, axis)
+    }
+
+    fn stencil_of(&self, phi: &[f64], p: &Point3d<i32>, axis: usize) -> [f64; 7] {
+        let mut values = [0.0; 7];
+        for (k, v) in values.iter_mut().enumerate() {
+            let o = k as i32 - 3;
+            let q = match axis {
+                0 => Point3d::<i32>::new((p.x + o).clamp(0, self.width - 1), p.y, p.z),
+                1 => Point3d::<i32>::new(p.x, (p.y + o).clamp(0, self.height - 1), p.z),
+                _ => Point3d::<i32>::new(p.x, p.y, (p.z + o).clamp(0, self.depth - 1)),
+            };
+            *v = phi[self.indexer.get(&q) as usize];
+        }
+        values
+    }
+
+    pub fn derivatives(&self, p: &Point3d<i32>) -> [(f64, f64); 3] {
+        [
+            weno5_derivatives(&self.stencil(p, 0)),
+            weno5_derivatives(&self.stencil(p, 1)),
+            weno5_derivatives(&self.stencil(p, 2)),
+        ]
+    }
+}
+
+impl UpwindSchemeMethod<SpaceSize3d, Indexer3d, Point3d<i32>> for WenoScheme3d {
+    const MIN_BAND_WIDTH: i32 = 3;
+
+    fn new(space_size: &SpaceSize3d, indexer: Rc<Indexer3d>, phi: Rc<RefCell<Vec<f64>>>) -> Self {
+        Self {
+            phi: Rc::clone(&phi),
+            indexer: Rc::clone(&indexer),
+            width: space_size.width,
+            height: space_size.height,
+            depth: space_size.depth,
+        }
+    }
+
+    fn calculate(&mut self, p: &Point3d<i32>, speed: Speed) -> f64 {
+        self.derivatives(p)
+            .iter()
+            .map(|(minus, plus)| godunov(*minus, *plus, &speed))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    fn calculate_advection(&mut self, p: &Point3d<i32>, gradient: &[f64], gain: f64) -> f64 {
+        self.derivatives(p)
+            .iter()
+            .zip(gradient)
+            .map(|((minus, plus), g)| upwind_advection(-gain * g, *minus, *plus))
+            .sum()
+    }
+
+    fn calculate_subcell_distance(&mut self, p: &Point3d<i32>, phi0: &[f64]) -> Option<f64> {
+        let neighbors: Vec<(f64, f64)> = (0..3)
+            .map(|axis| {
+                let s = self.stencil_of(phi0, p, axis);
+                (s[2], s[4])
+            })
+            .collect();
+        subcell_distance(self.stencil_of(phi0, p, 0)[3], &neighbors)
+    }
+}
@@ -1 +1,2 @@
 pub mod commandline_interface;
+pub mod shape_model_interface;
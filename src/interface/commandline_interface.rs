@@ -1,10 +1,46 @@
-use crate::core::parameters::Parameters;
-use crate::core::types::{InitialFront, IntPoint, SpaceSize, TwoDim};
-use clap::Parser;
+use crate::core::evolution_result::{EvolutionResult2d, EvolutionResult3d};
+use crate::core::indexer::IndexerMethod;
+use crate::core::morphological_snakes::{
+    MorphologicalSnakes2d, MorphologicalSnakes3d, MorphologicalSnakesMethod,
+};
+use crate::core::multiphase::{Multiphase2d, Multiphase3d, MultiphaseMethod, MAX_PHASES};
+use crate::core::observer::{IterationState, ObserverAction};
+use crate::core::parameters::{
+    EdgeFunction, Engine, Parameters, Reinitialization, ReinitializationTrigger, SpatialScheme,
+    SpeedMode, TimeIntegrator,
+};
+use crate::core::preprocessing::{
+    parse_pipeline, Filter, Preprocessing2d, Preprocessing3d, PreprocessingMethod,
+};
+use crate::core::shape_prior::ReferenceMap;
+use crate::core::types::{
+    Indexer, InitialFront, IntPoint, LevelSetMethod, SpaceSize, ThreeDim, TwoDim,
+};
+use crate::interface::shape_model_interface::ShapeModelArguments;
+use clap::{Parser, Subcommand};
 use image::GenericImageView;
 use image::ImageFormat;
 use opencv as cv;
 use opencv::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+/// arguments of the executable, which evolves a front unless a subcommand is given
+#[derive(Parser)]
+#[command(author, version, about, args_conflicts_with_subcommands = true)]
+pub struct Arguments {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub evolution: Option<CommandlineArguments>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// builds a PCA shape model from aligned binary masks
+    BuildShapeModel(ShapeModelArguments),
+}
+
 #[derive(Parser)]
 #[command(author, version, about)]
 pub struct CommandlineArguments {
@@ -52,6 +88,138 @@ pub struct CommandlineArguments {
 
     #[arg(long)]
     back: Option<i32>,
+
+    /// another initial front competing with the first one,
+    /// "left,top,right,bottom" in 2D and "left,top,front,right,bottom,back" in 3D
+    #[arg(long = "phase")]
+    phases: Vec<String>,
+
+    /// a preprocessing filter applied to the input in the order given, such as "gaussian:1.5",
+    /// "median:1", "perona-malik:10,0.125,20", "curvature-diffusion:5,0.0625,3",
+    /// "window:30,200" or "rescale"
+    #[arg(long = "filter")]
+    filters: Vec<String>,
+
+    /// file of the preprocessing filters, one per line, applied before the ones of --filter
+    #[arg(long)]
+    pipeline: Option<std::path::PathBuf>,
+
+    /// saves the preprocessed input, an image in 2D and a directory of slices in 3D
+    #[arg(long)]
+    preprocessed_output: Option<std::path::PathBuf>,
+
+    #[arg(long)]
+    output: std::path::PathBuf,
+
+    #[arg(long)]
+    max_iterations: Option<usize>,
+
+    #[arg(long, default_value = "edge", value_parser = ["edge", "chan-vese", "lbf"])]
+    speed_mode: String,
+
+    #[arg(long, default_value_t = 1.0)]
+    lambda_inside: f64,
+
+    #[arg(long, default_value_t = 1.0)]
+    lambda_outside: f64,
+
+    #[arg(long, default_value_t = 3.0)]
+    lbf_sigma: f64,
+
+    #[arg(long, default_value_t = 0.0)]
+    advection_gain: f64,
+
+    #[arg(long, default_value = "rational", value_parser = ["rational", "exponential", "sigmoid"])]
+    edge_function: String,
+
+    #[arg(long, default_value_t = 0.0)]
+    edge_sigma: f64,
+
+    #[arg(long, default_value_t = 1.0)]
+    edge_k: f64,
+
+    #[arg(long, default_value_t = 1.0)]
+    edge_power: f64,
+
+    #[arg(long, default_value_t = -10.0)]
+    sigmoid_alpha: f64,
+
+    #[arg(long, default_value_t = 30.0)]
+    sigmoid_beta: f64,
+
+    #[arg(long, default_value_t = 0.0)]
+    gvf_weight: f64,
+
+    #[arg(long, default_value_t = 80)]
+    gvf_iterations: usize,
+
+    #[arg(long, default_value_t = 0.2)]
+    gvf_mu: f64,
+
+    /// saves the gradient vector flow driving the front in the LSVF format,
+    /// which needs --gvf-weight and a single front evolved by a level set engine
+    #[arg(long)]
+    gvf_output: Option<std::path::PathBuf>,
+
+    #[arg(long, default_value = "first-order", value_parser = ["first-order", "weno5"])]
+    spatial_scheme: String,
+
+    #[arg(long, default_value = "euler", value_parser = ["euler", "rk2", "rk3"])]
+    time_integrator: String,
+
+    #[arg(long)]
+    adaptive_time_step: bool,
+
+    #[arg(long, default_value_t = 0.5)]
+    cfl_number: f64,
+
+    #[arg(
+        long,
+        default_value = "distance-map",
+        value_parser = ["distance-map", "fast-marching", "fast-marching-2nd", "pde"]
+    )]
+    reinitialization: String,
+
+    #[arg(long, default_value_t = 5)]
+    pde_iterations: usize,
+
+    #[arg(
+        long,
+        default_value = "reset-band",
+        value_parser = ["reset-band", "interval", "gradient-deviation"]
+    )]
+    reinitialization_trigger: String,
+
+    #[arg(long, default_value_t = 10)]
+    reinitialization_interval: usize,
+
+    #[arg(long, default_value_t = 0.2)]
+    gradient_deviation_tolerance: f64,
+
+    #[arg(long, default_value = "narrow-band", value_parser = ["narrow-band", "sparse-field", "drlse", "morphological"])]
+    engine: String,
+
+    #[arg(long, default_value_t = 0.2)]
+    distance_regularization: f64,
+
+    #[arg(long)]
+    topology_preservation: bool,
+
+    /// signed distance map of the reference shape, for a single front without --phase
+    #[arg(long)]
+    shape_prior: Option<std::path::PathBuf>,
+
+    #[arg(long, default_value_t = 0.0)]
+    shape_prior_weight: f64,
+
+    #[arg(long, default_value_t = 0)]
+    shape_prior_pose_interval: usize,
+
+    #[arg(long, default_value_t = 1)]
+    morphological_smoothing: usize,
+
+    #[arg(long, default_value_t = 0.5)]
+    morphological_threshold: f64,
 }
 
 pub fn load_input_image(input_path: &std::path::PathBuf) -> Option<(SpaceSize<TwoDim>, Vec<u8>)> {
@@ -69,6 +237,34 @@ pub fn load_input_image(input_path: &std::path::PathBuf) -> Option<(SpaceSize<Tw
     Some((space_size, image))
 }
 
+/// loads a volume from a directory of 2D slices.
+/// the slices are stacked along z in the order of their file names.
+pub fn load_input_volume(input_path: &std::path::Path) -> Option<(SpaceSize<ThreeDim>, Vec<u8>)> {
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(input_path)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+
+    let mut slice_size: Option<SpaceSize<TwoDim>> = None;
+    let mut volume = Vec::<u8>::new();
+    for path in &paths {
+        let (size, image) = load_input_image(path)?;
+        match &slice_size {
+            Some(s) if s.width != size.width || s.height != size.height => return None,
+            Some(_) => (),
+            None => slice_size = Some(size),
+        }
+        volume.extend(image);
+    }
+
+    let s = slice_size?;
+    let space_size = SpaceSize::<ThreeDim>::new(s.width, s.height, paths.len() as i32);
+    Some((space_size, volume))
+}
+
 fn make_parameters(args: &CommandlineArguments) -> Parameters {
     Parameters {
         wband: args.wband,
@@ -77,10 +273,76 @@ fn make_parameters(args: &CommandlineArguments) -> Parameters {
         gain: args.gain,
         constant_speed: args.constant_speed,
         speed_threshold: args.speed_threshold,
+        speed_mode: match args.speed_mode.as_str() {
+            "chan-vese" => SpeedMode::ChanVese,
+            "lbf" => SpeedMode::LocalBinaryFitting,
+            _ => SpeedMode::Edge,
+        },
+        lambda_inside: args.lambda_inside,
+        lambda_outside: args.lambda_outside,
+        lbf_sigma: args.lbf_sigma,
+        advection_gain: args.advection_gain,
+        edge_function: match args.edge_function.as_str() {
+            "exponential" => EdgeFunction::Exponential,
+            "sigmoid" => EdgeFunction::Sigmoid,
+            _ => EdgeFunction::Rational,
+        },
+        edge_sigma: args.edge_sigma,
+        edge_k: args.edge_k,
+        edge_power: args.edge_power,
+        sigmoid_alpha: args.sigmoid_alpha,
+        sigmoid_beta: args.sigmoid_beta,
+        gvf_weight: args.gvf_weight,
+        gvf_iterations: args.gvf_iterations,
+        gvf_mu: args.gvf_mu,
+        spatial_scheme: match args.spatial_scheme.as_str() {
+            "weno5" => SpatialScheme::Weno5,
+            _ => SpatialScheme::FirstOrder,
+        },
+        time_integrator: match args.time_integrator.as_str() {
+            "rk2" => TimeIntegrator::Rk2,
+            "rk3" => TimeIntegrator::Rk3,
+            _ => TimeIntegrator::Euler,
+        },
+        adaptive_time_step: args.adaptive_time_step,
+        cfl_number: args.cfl_number,
+        pde_iterations: args.pde_iterations,
+        reinitialization_trigger: match args.reinitialization_trigger.as_str() {
+            "interval" => ReinitializationTrigger::Interval,
+            "gradient-deviation" => ReinitializationTrigger::GradientDeviation,
+            _ => ReinitializationTrigger::ResetBand,
+        },
+        reinitialization_interval: args.reinitialization_interval,
+        gradient_deviation_tolerance: args.gradient_deviation_tolerance,
+        engine: match args.engine.as_str() {
+            "sparse-field" => Engine::SparseField,
+            "drlse" => Engine::Drlse,
+            "morphological" => Engine::Morphological,
+            _ => Engine::NarrowBand,
+        },
+        distance_regularization: args.distance_regularization,
+        topology_preservation: args.topology_preservation,
+        shape_prior_weight: args.shape_prior_weight,
+        shape_prior_pose_interval: args.shape_prior_pose_interval,
+        morphological_smoothing: args.morphological_smoothing,
+        morphological_threshold: args.morphological_threshold,
+        reinitialization: match args.reinitialization.as_str() {
+            "fast-marching" => Reinitialization::FastMarching,
+            "fast-marching-2nd" => Reinitialization::FastMarchingSecondOrder,
+            "pde" => Reinitialization::Pde,
+            _ => Reinitialization::DistanceMap,
+        },
     }
 }
 
-fn execute_level_set_method_in_2d(args: &CommandlineArguments, params: &Parameters) {
+fn execute_level_set_method_in_2d(
+    args: &CommandlineArguments,
+    params: &Parameters,
+) -> Result<(), String> {
+    if params.engine != Engine::Morphological {
+        LevelSetMethod::<TwoDim>::validate_parameters(params)?;
+    }
+
     // set an initial front
     let left = args.left;
     let top = args.top;
@@ -92,12 +354,384 @@ fn execute_level_set_method_in_2d(args: &CommandlineArguments, params: &Paramete
     let inital_front = InitialFront::<TwoDim> { vertices: [lt, rb] };
 
     // load an input image
-    let (space_size, image) = load_input_image(&args.input_path).unwrap();
+    let (space_size, image) = load_input_image(&args.input_path)
+        .ok_or(format!("failed to load {:?}", args.input_path))?;
+
+    // the front needs one pixel margin for the finite differences
+    if left < 1 || right > space_size.width - 2 || left >= right {
+        return Err(format!("invalid left/right: {}/{}", left, right));
+    }
+    if top < 1 || bottom > space_size.height - 2 || top >= bottom {
+        return Err(format!("invalid top/bottom: {}/{}", top, bottom));
+    }
+
+    let mut initial_fronts = vec![inital_front];
+    for text in &args.phases {
+        let v = parse_phase(text, 4)?;
+        if v[0] < 1 || v[2] > space_size.width - 2 || v[0] >= v[2] {
+            return Err(format!("invalid left/right of phase: {}", text));
+        }
+        if v[1] < 1 || v[3] > space_size.height - 2 || v[1] >= v[3] {
+            return Err(format!("invalid top/bottom of phase: {}", text));
+        }
+        let lt = IntPoint::<TwoDim>::new(v[0], v[1]);
+        let rb = IntPoint::<TwoDim>::new(v[2], v[3]);
+        initial_fronts.push(InitialFront::<TwoDim> { vertices: [lt, rb] });
+    }
+
+    // preprocess the input image
+    let size = Rc::new(space_size);
+    let image = Preprocessing2d::new(Rc::clone(&size)).apply(&make_filters(args)?, &image);
+    if let Some(path) = &args.preprocessed_output {
+        save_slice(path, &image, size.width, size.height)?;
+    }
+
+    // evolve the front
+    let gray = Rc::new(RefCell::new(image));
+    if initial_fronts.len() > 1 {
+        let mut multiphase = Multiphase2d::new(params.clone(), Rc::clone(&size), gray);
+        multiphase.initialize(&initial_fronts)?;
+        let result = multiphase.run(args.max_iterations.unwrap_or(usize::MAX));
+        if args.verbose {
+            println!("iterations: {}", result.iterations);
+            println!("phases: {}", result.phases.len());
+        }
+        std::fs::create_dir_all(&args.output).map_err(|e| format!("{:?}: {}", args.output, e))?;
+        return save_slice(
+            &args.output.join("labels.png"),
+            &result.labels,
+            size.width,
+            size.height,
+        );
+    }
+    let inital_front = &initial_fronts[0];
+    if params.engine == Engine::Morphological {
+        let mut snakes = MorphologicalSnakes2d::new(params.clone(), Rc::clone(&size), gray)?;
+        snakes.initialize(inital_front);
+        let result = snakes.run(args.max_iterations.unwrap_or(usize::MAX));
+        if args.verbose {
+            println!("iterations: {}", result.iterations);
+            println!("front size: {}", result.front.len());
+        }
+        return save_result_2d(&args.output, &size, &result);
+    }
+    let mut lsm = LevelSetMethod::<TwoDim>::new(params.clone(), Rc::clone(&size), gray)?;
+    if let Some(path) = &args.shape_prior {
+        lsm.set_shape_prior(load_reference_map(path, 2)?)?;
+    }
+    lsm.initialize(inital_front);
+    if args.verbose {
+        lsm.add_observer(Box::new(print_iteration::<IntPoint<TwoDim>>));
+    }
+    let result = lsm.run(args.max_iterations.unwrap_or(usize::MAX));
+    if args.verbose {
+        println!("iterations: {}", result.iterations);
+        println!("front size: {}", result.front.len());
+        println!("resets: {}", result.resets);
+        println!("vetoes: {}", result.vetoes.iter().sum::<usize>());
+    }
 
-    // Viewer2d
+    if let Some(path) = &args.gvf_output {
+        let field = lsm.get_vector_field().ok_or("no gradient vector flow")?;
+        field.save(path)?;
+    }
+    save_result_2d(&args.output, &size, &result)
+}
+
+fn execute_level_set_method_in_3d(
+    args: &CommandlineArguments,
+    params: &Parameters,
+) -> Result<(), String> {
+    if params.engine != Engine::Morphological {
+        LevelSetMethod::<ThreeDim>::validate_parameters(params)?;
+    }
+
+    // set an initial front
+    let left = args.left;
+    let top = args.top;
+    let front = args.front.ok_or("--front is required when --dim is 3")?;
+    let right = args.right;
+    let bottom = args.bottom;
+    let back = args.back.ok_or("--back is required when --dim is 3")?;
+
+    let ltf = IntPoint::<ThreeDim>::new(left, top, front);
+    let rbb = IntPoint::<ThreeDim>::new(right, bottom, back);
+    let inital_front = InitialFront::<ThreeDim> {
+        vertices: [ltf, rbb],
+    };
+
+    // load an input volume
+    let (space_size, volume) = load_input_volume(&args.input_path)
+        .ok_or(format!("failed to load {:?}", args.input_path))?;
+
+    // the front needs one voxel margin for the finite differences
+    if left < 1 || right > space_size.width - 2 || left >= right {
+        return Err(format!("invalid left/right: {}/{}", left, right));
+    }
+    if top < 1 || bottom > space_size.height - 2 || top >= bottom {
+        return Err(format!("invalid top/bottom: {}/{}", top, bottom));
+    }
+    if front < 1 || back > space_size.depth - 2 || front >= back {
+        return Err(format!("invalid front/back: {}/{}", front, back));
+    }
+
+    let mut initial_fronts = vec![inital_front];
+    for text in &args.phases {
+        let v = parse_phase(text, 6)?;
+        if v[0] < 1 || v[3] > space_size.width - 2 || v[0] >= v[3] {
+            return Err(format!("invalid left/right of phase: {}", text));
+        }
+        if v[1] < 1 || v[4] > space_size.height - 2 || v[1] >= v[4] {
+            return Err(format!("invalid top/bottom of phase: {}", text));
+        }
+        if v[2] < 1 || v[5] > space_size.depth - 2 || v[2] >= v[5] {
+            return Err(format!("invalid front/back of phase: {}", text));
+        }
+        let ltf = IntPoint::<ThreeDim>::new(v[0], v[1], v[2]);
+        let rbb = IntPoint::<ThreeDim>::new(v[3], v[4], v[5]);
+        initial_fronts.push(InitialFront::<ThreeDim> {
+            vertices: [ltf, rbb],
+        });
+    }
+
+    // preprocess the input volume
+    let size = Rc::new(space_size);
+    let volume = Preprocessing3d::new(Rc::clone(&size)).apply(&make_filters(args)?, &volume);
+    if let Some(path) = &args.preprocessed_output {
+        std::fs::create_dir_all(path).map_err(|e| format!("{:?}: {}", path, e))?;
+        let area = (size.width * size.height) as usize;
+        for k in 0..(size.depth as usize) {
+            let range = (area * k)..(area * (k + 1));
+            let slice = path.join(format!("slice_{:04}.png", k));
+            save_slice(&slice, &volume[range], size.width, size.height)?;
+        }
+    }
+
+    // evolve the front
+    let gray = Rc::new(RefCell::new(volume));
+    if initial_fronts.len() > 1 {
+        let mut multiphase = Multiphase3d::new(params.clone(), Rc::clone(&size), gray);
+        multiphase.initialize(&initial_fronts)?;
+        let result = multiphase.run(args.max_iterations.unwrap_or(usize::MAX));
+        if args.verbose {
+            println!("iterations: {}", result.iterations);
+            println!("phases: {}", result.phases.len());
+        }
+        std::fs::create_dir_all(&args.output).map_err(|e| format!("{:?}: {}", args.output, e))?;
+        let area = (size.width * size.height) as usize;
+        for k in 0..(size.depth as usize) {
+            let path = args.output.join(format!("labels_{:04}.png", k));
+            let range = (area * k)..(area * (k + 1));
+            save_slice(&path, &result.labels[range], size.width, size.height)?;
+        }
+        return Ok(());
+    }
+    let inital_front = &initial_fronts[0];
+    if params.engine == Engine::Morphological {
+        let mut snakes = MorphologicalSnakes3d::new(params.clone(), Rc::clone(&size), gray)?;
+        snakes.initialize(inital_front);
+        let result = snakes.run(args.max_iterations.unwrap_or(usize::MAX));
+        if args.verbose {
+            println!("iterations: {}", result.iterations);
+            println!("front size: {}", result.front.len());
+        }
+        return save_result_3d(&args.output, &size, &result);
+    }
+    let mut lsm = LevelSetMethod::<ThreeDim>::new(params.clone(), Rc::clone(&size), gray)?;
+    if let Some(path) = &args.shape_prior {
+        lsm.set_shape_prior(load_reference_map(path, 3)?)?;
+    }
+    lsm.initialize(inital_front);
+    if args.verbose {
+        lsm.add_observer(Box::new(print_iteration::<IntPoint<ThreeDim>>));
+    }
+    let result = lsm.run(args.max_iterations.unwrap_or(usize::MAX));
+    if args.verbose {
+        println!("iterations: {}", result.iterations);
+        println!("front size: {}", result.front.len());
+        println!("resets: {}", result.resets);
+        println!("vetoes: {}", result.vetoes.iter().sum::<usize>());
+    }
+
+    if let Some(path) = &args.gvf_output {
+        let field = lsm.get_vector_field().ok_or("no gradient vector flow")?;
+        field.save(path)?;
+    }
+    save_result_3d(&args.output, &size, &result)
 }
 
-fn execute_level_set_method_in_3d(args: &CommandlineArguments, params: &Parameters) {}
+/// loads the signed distance map given by --shape-prior.
+fn load_reference_map(path: &std::path::Path, dimension: usize) -> Result<ReferenceMap, String> {
+    let reference = ReferenceMap::load(path)?;
+    if reference.extents.len() != dimension {
+        return Err(format!(
+            "{:?}: the shape prior must be {}D",
+            path, dimension
+        ));
+    }
+    Ok(reference)
+}
+
+/// the morphological engine evolves a single front without the shape prior.
+fn check_morphological(args: &CommandlineArguments, params: &Parameters) -> Result<(), String> {
+    if params.engine == Engine::Morphological && !args.phases.is_empty() {
+        Err("--phase is not supported with the morphological engine".to_string())
+    } else if params.engine == Engine::Morphological && args.shape_prior.is_some() {
+        Err("--shape-prior is not supported with the morphological engine".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// the shape prior is set only on the LevelSetMethod of a single front.
+fn check_shape_prior(args: &CommandlineArguments) -> Result<(), String> {
+    if args.shape_prior.is_some() && !args.phases.is_empty() {
+        Err("--shape-prior is not supported with --phase".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// the gradient vector flow exists only in the LevelSetMethod of a single front with gvf_weight.
+fn check_gvf_output(args: &CommandlineArguments, params: &Parameters) -> Result<(), String> {
+    if args.gvf_output.is_none() {
+        Ok(())
+    } else if params.gvf_weight == 0.0 {
+        Err("--gvf-output needs --gvf-weight".to_string())
+    } else if !args.phases.is_empty() || params.engine == Engine::Morphological {
+        Err("--gvf-output is not supported with --phase or the morphological engine".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// the filters of --pipeline followed by the ones of --filter.
+pub fn make_filters(args: &CommandlineArguments) -> Result<Vec<Filter>, String> {
+    let mut filters = match &args.pipeline {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("{:?}: {}", path, e))?;
+            parse_pipeline(&text, args.dim as usize)?
+        }
+        None => Vec::new(),
+    };
+    for text in &args.filters {
+        filters.push(Filter::parse(text, args.dim as usize)?);
+    }
+    Ok(filters)
+}
+
+/// parses the comma separated coordinates of an initial front given by --phase.
+fn parse_phase(text: &str, count: usize) -> Result<Vec<i32>, String> {
+    let values: Vec<i32> = text
+        .split(',')
+        .map(|v| v.trim().parse::<i32>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("invalid phase {}: {}", text, e))?;
+    if values.len() != count {
+        return Err(format!("phase {} needs {} coordinates", text, count));
+    }
+    Ok(values)
+}
+
+/// writes mask.png(inside of the front) and contour.png(the front) into output.
+fn save_result_2d(
+    output: &std::path::Path,
+    size: &SpaceSize<TwoDim>,
+    result: &EvolutionResult2d,
+) -> Result<(), String> {
+    std::fs::create_dir_all(output).map_err(|e| format!("{:?}: {}", output, e))?;
+
+    let mask = make_mask(&result.phi);
+
+    let indexer = Indexer::<TwoDim>::new(size);
+    let mut contour = vec![0u8; size.total as usize];
+    for p in result.front.iter() {
+        contour[indexer.get(p) as usize] = 255u8;
+    }
+
+    save_slice(&output.join("mask.png"), &mask, size.width, size.height)?;
+    save_slice(
+        &output.join("contour.png"),
+        &contour,
+        size.width,
+        size.height,
+    )?;
+    save_values(&output.join("time_steps.txt"), &result.time_steps)?;
+    if !result.vetoes.is_empty() {
+        save_values(&output.join("vetoes.txt"), &result.vetoes)?;
+    }
+    Ok(())
+}
+
+/// writes mask_NNNN.png and contour_NNNN.png for every slice into output.
+fn save_result_3d(
+    output: &std::path::Path,
+    size: &SpaceSize<ThreeDim>,
+    result: &EvolutionResult3d,
+) -> Result<(), String> {
+    std::fs::create_dir_all(output).map_err(|e| format!("{:?}: {}", output, e))?;
+
+    let mask = make_mask(&result.phi);
+
+    let indexer = Indexer::<ThreeDim>::new(size);
+    let mut contour = vec![0u8; size.total as usize];
+    for p in result.front.iter() {
+        contour[indexer.get(p) as usize] = 255u8;
+    }
+
+    let area = (size.width * size.height) as usize;
+    for k in 0..(size.depth as usize) {
+        let range = (area * k)..(area * (k + 1));
+        let path = output.join(format!("mask_{:04}.png", k));
+        save_slice(&path, &mask[range.clone()], size.width, size.height)?;
+        let path = output.join(format!("contour_{:04}.png", k));
+        save_slice(&path, &contour[range], size.width, size.height)?;
+    }
+    save_values(&output.join("time_steps.txt"), &result.time_steps)?;
+    if !result.vetoes.is_empty() {
+        save_values(&output.join("vetoes.txt"), &result.vetoes)?;
+    }
+    Ok(())
+}
+
+/// writes the value of each iteration, one per line.
+fn save_values<T: std::fmt::Display>(path: &std::path::Path, values: &[T]) -> Result<(), String> {
+    let text: String = values.iter().map(|v| format!("{}\n", v)).collect();
+    std::fs::write(path, text).map_err(|e| format!("{:?}: {}", path, e))
+}
+
+fn print_iteration<P>(state: &IterationState<P>) -> ObserverAction {
+    println!(
+        "iteration: {}, total_speed: {}, zero_count: {}, front size: {}",
+        state.iteration,
+        state.total_speed,
+        state.zero_count,
+        state.front.len()
+    );
+    ObserverAction::Continue
+}
+
+fn make_mask(phi: &[f64]) -> Vec<u8> {
+    phi.iter()
+        .map(|v| if *v < 0.0 { 255u8 } else { 0u8 })
+        .collect()
+}
+
+fn save_slice(
+    path: &std::path::Path,
+    buffer: &[u8],
+    width: i32,
+    height: i32,
+) -> Result<(), String> {
+    image::save_buffer(
+        path,
+        buffer,
+        width as u32,
+        height as u32,
+        image::ColorType::L8,
+    )
+    .map_err(|e| format!("{:?}: {}", path, e))
+}
 
 fn print_args(args: &CommandlineArguments) {
     println!("dim: {}", args.dim);
@@ -121,14 +755,77 @@ fn print_args(args: &CommandlineArguments) {
         Some(v) => println!("back: {}", v),
         None => println!("back: no used"),
     }
+    println!("filters: {:?}", args.filters);
+    println!("pipeline: {:?}", args.pipeline);
+    println!("preprocessed_output: {:?}", args.preprocessed_output);
+    println!("output: {:?}", args.output);
+    println!("speed_mode: {}", args.speed_mode);
+    println!("lambda_inside: {}", args.lambda_inside);
+    println!("lambda_outside: {}", args.lambda_outside);
+    println!("lbf_sigma: {}", args.lbf_sigma);
+    println!("advection_gain: {}", args.advection_gain);
+    println!("edge_function: {}", args.edge_function);
+    println!("edge_sigma: {}", args.edge_sigma);
+    println!("edge_k: {}", args.edge_k);
+    println!("edge_power: {}", args.edge_power);
+    println!("sigmoid_alpha: {}", args.sigmoid_alpha);
+    println!("sigmoid_beta: {}", args.sigmoid_beta);
+    println!("gvf_weight: {}", args.gvf_weight);
+    println!("gvf_iterations: {}", args.gvf_iterations);
+    println!("gvf_mu: {}", args.gvf_mu);
+    println!("gvf_output: {:?}", args.gvf_output);
+    println!("spatial_scheme: {}", args.spatial_scheme);
+    println!("time_integrator: {}", args.time_integrator);
+    println!("adaptive_time_step: {:?}", args.adaptive_time_step);
+    println!("cfl_number: {}", args.cfl_number);
+    println!("reinitialization: {}", args.reinitialization);
+    println!("pde_iterations: {}", args.pde_iterations);
+    println!(
+        "reinitialization_trigger: {}",
+        args.reinitialization_trigger
+    );
+    println!(
+        "reinitialization_interval: {}",
+        args.reinitialization_interval
+    );
+    println!(
+        "gradient_deviation_tolerance: {}",
+        args.gradient_deviation_tolerance
+    );
+    println!("engine: {}", args.engine);
+    println!("distance_regularization: {}", args.distance_regularization);
+    println!("phases: {:?}", args.phases);
+    println!("topology_preservation: {:?}", args.topology_preservation);
+    println!("shape_prior: {:?}", args.shape_prior);
+    println!("shape_prior_weight: {}", args.shape_prior_weight);
+    println!(
+        "shape_prior_pose_interval: {}",
+        args.shape_prior_pose_interval
+    );
+    println!("morphological_smoothing: {}", args.morphological_smoothing);
+    println!("morphological_threshold: {}", args.morphological_threshold);
+    match args.max_iterations {
+        Some(v) => println!("max_iterations: {}", v),
+        None => println!("max_iterations: no used"),
+    }
 }
 
-pub fn execute_level_set_method(args: &CommandlineArguments) {
+pub fn execute_level_set_method(args: &CommandlineArguments) -> Result<(), String> {
     print_args(&args);
     let params = make_parameters(&args);
+    check_morphological(args, &params)?;
+    check_shape_prior(args)?;
+    check_gvf_output(args, &params)?;
+    if args.phases.len() + 1 > MAX_PHASES {
+        return Err(format!(
+            "too many phases: {}, at most {} with the initial front",
+            args.phases.len() + 1,
+            MAX_PHASES
+        ));
+    }
     match args.dim {
         2 => execute_level_set_method_in_2d(&args, &params),
         3 => execute_level_set_method_in_3d(&args, &params),
-        _ => println!("unsupported dimension!"),
+        _ => Err(format!("unsupported dimension: {}", args.dim)),
     }
 }
@@ -0,0 +1,113 @@
+use crate::core::shape_model::{ShapeModel2d, ShapeModel3d, ShapeModelMethod};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use crate::interface::commandline_interface::{load_input_image, load_input_volume};
+use clap::Parser;
+
+/// arguments of the build-shape-model subcommand
+#[derive(Parser)]
+#[command(about = "builds a PCA shape model from aligned binary masks")]
+pub struct ShapeModelArguments {
+    #[arg(long)]
+    dim: i32,
+
+    /// a directory of mask images in 2D, or of directories of mask slices in 3D
+    #[arg(long)]
+    input_path: std::path::PathBuf,
+
+    /// the maximum number of modes
+    #[arg(long, default_value_t = 5)]
+    modes: usize,
+
+    /// the model file
+    #[arg(long)]
+    output: std::path::PathBuf,
+
+    /// writes the mean shape also as a reference map for --shape-prior
+    #[arg(long)]
+    mean_output: Option<std::path::PathBuf>,
+}
+
+/// entries of a directory in the order of their names
+fn list_entries(
+    path: &std::path::Path,
+    directories: bool,
+) -> Result<Vec<std::path::PathBuf>, String> {
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(path)
+        .map_err(|e| format!("{:?}: {}", path, e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            if directories {
+                path.is_dir()
+            } else {
+                path.is_file()
+            }
+        })
+        .collect();
+    paths.sort();
+    if paths.is_empty() {
+        return Err(format!("{:?}: no masks", path));
+    }
+    Ok(paths)
+}
+
+fn load_masks_2d(path: &std::path::Path) -> Result<(SpaceSize2d, Vec<Vec<u8>>), String> {
+    let mut size: Option<SpaceSize2d> = None;
+    let mut masks = Vec::new();
+    for p in list_entries(path, false)? {
+        let (s, mask) = load_input_image(&p).ok_or(format!("{:?}: cannot load", p))?;
+        if let Some(size) = &size {
+            if size.width != s.width || size.height != s.height {
+                return Err(format!("{:?}: the size differs", p));
+            }
+        }
+        size = Some(s);
+        masks.push(mask);
+    }
+    Ok((size.unwrap(), masks))
+}
+
+fn load_masks_3d(path: &std::path::Path) -> Result<(SpaceSize3d, Vec<Vec<u8>>), String> {
+    let mut size: Option<SpaceSize3d> = None;
+    let mut masks = Vec::new();
+    for p in list_entries(path, true)? {
+        let (s, mask) = load_input_volume(&p).ok_or(format!("{:?}: cannot load", p))?;
+        if let Some(size) = &size {
+            if size.width != s.width || size.height != s.height || size.depth != s.depth {
+                return Err(format!("{:?}: the size differs", p));
+            }
+        }
+        size = Some(s);
+        masks.push(mask);
+    }
+    Ok((size.unwrap(), masks))
+}
+
+fn save_model<T, P>(
+    args: &ShapeModelArguments,
+    model: &impl ShapeModelMethod<T, P>,
+) -> Result<(), String> {
+    model.save(&args.output)?;
+    if let Some(path) = &args.mean_output {
+        model.to_reference_map().save(path)?;
+    }
+    println!("modes: {}", model.get_modes().len());
+    for (k, v) in model.get_eigenvalues().iter().enumerate() {
+        println!("eigenvalue {}: {}", k, v);
+    }
+    Ok(())
+}
+
+pub fn execute_shape_model_builder(args: &ShapeModelArguments) -> Result<(), String> {
+    match args.dim {
+        2 => {
+            let (size, masks) = load_masks_2d(&args.input_path)?;
+            save_model(args, &ShapeModel2d::build(&size, &masks, args.modes)?)
+        }
+        3 => {
+            let (size, masks) = load_masks_3d(&args.input_path)?;
+            save_model(args, &ShapeModel3d::build(&size, &masks, args.modes)?)
+        }
+        _ => Err(format!("unsupported dimension: {}", args.dim)),
+    }
+}
@@ -1,19 +1,35 @@
 pub mod test_commandline_interface;
 pub mod test_differential;
 pub mod test_distance_map_generator;
+pub mod test_distance_regularization;
+pub mod test_fast_marching;
 pub mod test_front;
+pub mod test_gaussian;
+pub mod test_gradient_vector_flow;
 pub mod test_grid;
+pub mod test_grid_file;
 pub mod test_indexer;
 pub mod test_initial_front;
 pub mod test_level_set_method;
+pub mod test_morphological_snakes;
+pub mod test_multiphase;
 pub mod test_neighboring_points;
+pub mod test_observer;
 pub mod test_parameters;
 pub mod test_point;
 pub mod test_position;
+pub mod test_preprocessing;
+pub mod test_region_speed;
+pub mod test_shape_model;
+pub mod test_shape_prior;
 pub mod test_space_size;
+pub mod test_sparse_field;
 pub mod test_speed_factor;
+pub mod test_stopping_criterion;
+pub mod test_topology;
 pub mod test_types;
 pub mod test_upwind;
 pub mod test_upwind_scheme;
 pub mod test_util;
+pub mod test_weno_scheme;
 pub mod test_zero_level_set_detector;
@@ -1,4 +1,6 @@
+use crate::core::preprocessing::Filter;
 use crate::interface::commandline_interface as cm;
+use clap::Parser;
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -15,4 +17,211 @@ mod tests {
             None => (),
         }
     }
+
+    fn make_args(dim: &str, input_path: &str) -> Vec<String> {
+        let args = [
+            "levelset_by_rust",
+            "--dim",
+            dim,
+            "--input-path",
+            input_path,
+            "--wband",
+            "5",
+            "--wreset",
+            "2",
+            "--time-step",
+            "1",
+            "--gain",
+            "0.3",
+            "--constant-speed",
+            "1",
+            "--speed-threshold",
+            "0.05",
+            "--left",
+            "1",
+            "--top",
+            "1",
+            "--right",
+            "3",
+            "--bottom",
+            "3",
+            "--output",
+            "/tmp/levelset_by_rust",
+        ];
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_subcommand() {
+        let args = make_args("2", "./hoge/foo.png");
+        let parsed = cm::Arguments::try_parse_from(&args).unwrap();
+        assert!(parsed.command.is_none());
+        assert!(parsed.evolution.is_some());
+
+        let args = [
+            "levelset_by_rust",
+            "build-shape-model",
+            "--dim",
+            "2",
+            "--input-path",
+            "./hoge",
+            "--output",
+            "./hoge/model.bin",
+        ];
+        let parsed = cm::Arguments::try_parse_from(args).unwrap();
+        assert!(matches!(
+            parsed.command,
+            Some(cm::Command::BuildShapeModel(_))
+        ));
+        assert!(parsed.evolution.is_none());
+
+        // the options of the evolution do not mix with the subcommand
+        let mut args = make_args("2", "./hoge/foo.png");
+        args.push("build-shape-model".to_string());
+        assert!(cm::Arguments::try_parse_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_parse_output() {
+        let args = make_args("2", "./hoge/foo.png");
+        assert!(cm::CommandlineArguments::try_parse_from(&args).is_ok());
+
+        let args: Vec<String> = args.into_iter().take(26).collect();
+        assert!(cm::CommandlineArguments::try_parse_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_parse_phases() {
+        let mut args = make_args("2", "./hoge/foo.png");
+        args.extend(["--phase", "5,1,7,3", "--phase", "1,5,3,7"].map(String::from));
+        assert!(cm::CommandlineArguments::try_parse_from(&args).is_ok());
+
+        // a phase without enough coordinates fails
+        let mut args = make_args("2", "./hoge/not_found.png");
+        args.extend(["--phase", "5,1,7"].map(String::from));
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        assert!(cm::execute_level_set_method(&parsed).is_err());
+    }
+
+    #[test]
+    fn test_execute_level_set_method_failure() {
+        let args = make_args("2", "./hoge/not_found.png");
+        let args = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        assert!(cm::execute_level_set_method(&args).is_err());
+
+        let args = make_args("4", "./hoge/not_found.png");
+        let args = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        assert!(cm::execute_level_set_method(&args).is_err());
+    }
+
+    #[test]
+    fn test_execute_level_set_method_in_3d_without_front_and_back() {
+        let args = make_args("3", "./hoge");
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        let r = cm::execute_level_set_method(&parsed);
+        assert!(r.unwrap_err().contains("--front"));
+
+        let mut args = args;
+        args.push(String::from("--front"));
+        args.push(String::from("1"));
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        let r = cm::execute_level_set_method(&parsed);
+        assert!(r.unwrap_err().contains("--back"));
+    }
+
+    #[test]
+    fn test_load_input_volume() {
+        let path = std::path::PathBuf::from("./hoge/not_found");
+        assert!(cm::load_input_volume(&path).is_none());
+    }
+
+    #[test]
+    fn test_make_filters() {
+        let path = std::env::temp_dir().join("levelset_by_rust_pipeline.txt");
+        std::fs::write(&path, "# denoise first\nmedian:1\n\nrescale\n").unwrap();
+        let mut args = make_args("2", "./hoge/foo.png");
+        args.extend(["--pipeline", path.to_str().unwrap()].map(String::from));
+        args.extend(["--filter", "gaussian:1.5"].map(String::from));
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        let filters = cm::make_filters(&parsed).unwrap();
+        assert_eq!(
+            filters,
+            vec![
+                Filter::Median { radius: 1 },
+                Filter::Rescale,
+                Filter::Gaussian { sigma: 1.5 }
+            ]
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let mut args = make_args("2", "./hoge/foo.png");
+        args.extend(["--filter", "sharpen:2"].map(String::from));
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        assert!(cm::make_filters(&parsed).is_err());
+    }
+
+    #[test]
+    fn test_gvf_output_without_gvf_weight() {
+        let mut args = make_args("2", "./hoge/foo.png");
+        args.extend(["--gvf-output", "./hoge/gvf.lsvf"].map(String::from));
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        let r = cm::execute_level_set_method(&parsed);
+        assert_eq!(r, Err("--gvf-output needs --gvf-weight".to_string()));
+    }
+
+    #[test]
+    fn test_too_many_phases() {
+        let mut args = make_args("2", "./hoge/foo.png");
+        for _ in 0..255 {
+            args.extend(["--phase", "1,1,2,2"].map(String::from));
+        }
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        let r = cm::execute_level_set_method(&parsed);
+        assert!(r.unwrap_err().starts_with("too many phases: 256"));
+    }
+
+    #[test]
+    fn test_morphological_engine_with_phase_or_shape_prior() {
+        let mut args = make_args("2", "./hoge/foo.png");
+        args.extend(["--engine", "morphological"].map(String::from));
+        let mut with_phase = args.clone();
+        with_phase.extend(["--phase", "1,1,2,2"].map(String::from));
+        let parsed = cm::CommandlineArguments::try_parse_from(&with_phase).unwrap();
+        let r = cm::execute_level_set_method(&parsed);
+        assert_eq!(
+            r,
+            Err("--phase is not supported with the morphological engine".to_string())
+        );
+
+        args.extend(["--shape-prior", "./hoge/prior.lsdm"].map(String::from));
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        let r = cm::execute_level_set_method(&parsed);
+        assert_eq!(
+            r,
+            Err("--shape-prior is not supported with the morphological engine".to_string())
+        );
+    }
+
+    #[test]
+    fn test_shape_prior_with_phase() {
+        let mut args = make_args("2", "./hoge/foo.png");
+        args.extend(["--phase", "1,1,2,2", "--shape-prior", "./hoge/prior.lsdm"].map(String::from));
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        let r = cm::execute_level_set_method(&parsed);
+        assert_eq!(
+            r,
+            Err("--shape-prior is not supported with --phase".to_string())
+        );
+    }
+
+    #[test]
+    fn test_narrow_band_too_thin_for_weno5() {
+        let mut args = make_args("2", "./hoge/foo.png");
+        let k = args.iter().position(|a| a == "--wband").unwrap();
+        args[k + 1] = "2".to_string();
+        args.extend(["--spatial-scheme", "weno5"].map(String::from));
+        let parsed = cm::CommandlineArguments::try_parse_from(&args).unwrap();
+        let r = cm::execute_level_set_method(&parsed);
+        assert_eq!(r, Err("wband must be at least 3 for Weno5".to_string()));
+    }
 }
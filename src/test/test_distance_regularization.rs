@@ -0,0 +1,130 @@
+use crate::core::distance_regularization::{
+    diffusion_rate, DistanceRegularization2d, DistanceRegularization3d,
+    DistanceRegularizationMethod,
+};
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::parameters::{Engine, Parameters};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffusion_rate_values() {
+        assert_eq!(diffusion_rate(0.0), 1.0);
+        assert!((diffusion_rate(0.25) - 2.0 / std::f64::consts::PI).abs() < 1.0e-12);
+        assert!(diffusion_rate(0.5).abs() < 1.0e-12);
+        assert!(diffusion_rate(1.0).abs() < 1.0e-12);
+        assert_eq!(diffusion_rate(2.0), 0.5);
+        // phi is pushed back toward |grad(phi)| = 1 from both sides
+        assert!(diffusion_rate(0.8) < 0.0);
+        assert!(diffusion_rate(1.2) > 0.0);
+    }
+
+    #[test]
+    fn calculate_2d() {
+        let size = SpaceSize2d::new(5, 5);
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let phi = Rc::new(RefCell::new(vec![0.0; 25]));
+        let mut regularization =
+            DistanceRegularization2d::new(Rc::clone(&indexer), Rc::clone(&phi));
+        let p = Point2d::<i32>::new(2, 2);
+
+        // a signed distance or a linear function has no divergence
+        for (i, v) in phi.borrow_mut().iter_mut().enumerate() {
+            *v = (i % 5) as f64 - 2.0;
+        }
+        assert!(regularization.calculate(&p).abs() < 1.0e-12);
+        for (i, v) in phi.borrow_mut().iter_mut().enumerate() {
+            *v = 3.0 * (i / 5) as f64;
+        }
+        assert!(regularization.calculate(&p).abs() < 1.0e-12);
+
+        // the slope 0.25 on the left and 2 on the right of p
+        for (i, v) in phi.borrow_mut().iter_mut().enumerate() {
+            let x = (i % 5) as f64 - 2.0;
+            *v = if x < 0.0 { 0.25 * x } else { 2.0 * x };
+        }
+        let expected = 0.5 * 2.0 - 2.0 / std::f64::consts::PI * 0.25;
+        assert!((regularization.calculate(&p) - expected).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn calculate_3d() {
+        let size = SpaceSize3d::new(3, 3, 3);
+        let indexer = Rc::new(Indexer3d::new(&size));
+        let phi = Rc::new(RefCell::new(vec![0.0; 27]));
+        let mut regularization =
+            DistanceRegularization3d::new(Rc::clone(&indexer), Rc::clone(&phi));
+        let p = Point3d::<i32>::new(1, 1, 1);
+        assert_eq!(regularization.calculate(&p), 0.0);
+
+        // a bump of 0.1 at the center flows out through the six faces at the rate 1
+        phi.borrow_mut()[13] = 0.1;
+        let d = diffusion_rate(0.1);
+        assert!((regularization.calculate(&p) + 6.0 * d * 0.1).abs() < 1.0e-12);
+    }
+
+    /// mean of ||grad(phi)| - 1| by the central differences where |phi| < 2.
+    fn calculate_deviation_near_front(phi: &[f64], width: usize) -> f64 {
+        let mut sum = 0.0;
+        let mut count = 0;
+        for y in 1..(phi.len() / width - 1) {
+            for x in 1..(width - 1) {
+                let i = x + width * y;
+                if phi[i].abs() < 2.0 {
+                    let fx = (phi[i + 1] - phi[i - 1]) / 2.0;
+                    let fy = (phi[i + width] - phi[i - width]) / 2.0;
+                    sum += ((fx * fx + fy * fy).sqrt() - 1.0).abs();
+                    count += 1;
+                }
+            }
+        }
+        sum / count as f64
+    }
+
+    fn run_expanding_square(engine: Engine, distance_regularization: f64) -> (usize, usize, f64) {
+        let mut params = Parameters::new();
+        params.wband = 4;
+        params.wreset = 1;
+        params.constant_speed = 1.0;
+        params.gain = 0.05;
+        params.time_step = 0.4;
+        params.engine = engine;
+        params.distance_regularization = distance_regularization;
+        let size = Rc::new(SpaceSize2d::new(80, 80));
+        let gray = Rc::new(RefCell::new(vec![0u8; 6400]));
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(34, 34);
+        initial_front.vertices[1] = Point2d::<i32>::new(45, 45);
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        lsm.run(60);
+        let phi = lsm.get_phi();
+        let area = phi.borrow().iter().filter(|v| **v < 0.0).count();
+        let deviation = calculate_deviation_near_front(&phi.borrow(), 80);
+        (area, lsm.get_reset_count(), deviation)
+    }
+
+    #[test]
+    fn drlse_2d() {
+        let (area, resets, _) = run_expanding_square(Engine::NarrowBand, 0.0);
+        assert!(resets > 0);
+
+        // the front moves as far without any reset
+        let (drlse_area, drlse_resets, deviation) = run_expanding_square(Engine::Drlse, 0.2);
+        assert_eq!(drlse_resets, 0);
+        assert!((drlse_area as f64 - area as f64).abs() < 0.05 * area as f64);
+
+        // and phi stays closer to a signed distance than without the regularization
+        let (_, _, unregularized_deviation) = run_expanding_square(Engine::Drlse, 0.0);
+        assert!(deviation < 0.25);
+        assert!(deviation < 0.8 * unregularized_deviation);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::core::fast_marching::{FastMarching2d, FastMarching3d, FastMarchingMethod};
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::parameters::{Parameters, Reinitialization};
+use crate::core::point::Point2d;
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS: f64 = 7.3;
+
+    fn make_circle_2d(size: i32) -> (Vec<f64>, Vec<f64>) {
+        let c = size as f64 / 2.0;
+        let mut phi0 = Vec::new();
+        let mut exact = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let r = ((x as f64 - c).powi(2) + (y as f64 - c).powi(2)).sqrt();
+                // the zero-levelset of a non-distance function
+                phi0.push(r * r - RADIUS * RADIUS);
+                exact.push(r - RADIUS);
+            }
+        }
+        (phi0, exact)
+    }
+
+    fn band_of(exact: &[f64], width: f64) -> Vec<usize> {
+        (0..exact.len())
+            .filter(|i| exact[*i].abs() < width)
+            .collect()
+    }
+
+    fn max_error(phi: &[f64], exact: &[f64], band: &[usize]) -> f64 {
+        band.iter()
+            .fold(0.0, |m: f64, i| m.max((phi[*i] - exact[*i]).abs()))
+    }
+
+    #[test]
+    fn planar_front_2d() {
+        let size = SpaceSize2d::new(10, 6);
+        let phi0: Vec<f64> = (0..60).map(|i| 2.0 * ((i % 10) as f64 - 4.3)).collect();
+        let band: Vec<usize> = (0..60).collect();
+        let mut phi = vec![0.0; 60];
+        let mut fast_marching = FastMarching2d::new(&size);
+        fast_marching.reinitialize(&phi0, &mut phi, &band, false);
+        for (i, v) in phi.iter().enumerate() {
+            assert!((v - ((i % 10) as f64 - 4.3)).abs() < 1.0e-12);
+        }
+    }
+
+    fn mean_error(phi: &[f64], exact: &[f64], band: &[usize]) -> f64 {
+        band.iter()
+            .map(|i| (phi[*i] - exact[*i]).abs())
+            .sum::<f64>()
+            / band.len() as f64
+    }
+
+    #[test]
+    fn circle_2d() {
+        let size = SpaceSize2d::new(24, 24);
+        let (phi0, exact) = make_circle_2d(24);
+        let band = band_of(&exact, 4.0);
+        let mut fast_marching = FastMarching2d::new(&size);
+
+        let mut first = phi0.clone();
+        fast_marching.reinitialize(&phi0, &mut first, &band, false);
+        let mut second = phi0.clone();
+        fast_marching.reinitialize(&phi0, &mut second, &band, true);
+
+        assert!(max_error(&first, &exact, &band) < 0.2);
+        assert!(max_error(&second, &exact, &band) < 0.15);
+        assert!(mean_error(&second, &exact, &band) < mean_error(&first, &exact, &band));
+
+        // points outside the band are left as they are
+        for i in 0..phi0.len() {
+            if !band.contains(&i) {
+                assert_eq!(second[i], phi0[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn sphere_3d() {
+        let n = 20;
+        let size = SpaceSize3d::new(n, n, n);
+        let c = n as f64 / 2.0;
+        let mut phi0 = Vec::new();
+        let mut exact = Vec::new();
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    let r =
+                        ((x as f64 - c).powi(2) + (y as f64 - c).powi(2) + (z as f64 - c).powi(2))
+                            .sqrt();
+                    phi0.push(3.0 * (r - 5.6));
+                    exact.push(r - 5.6);
+                }
+            }
+        }
+        let band = band_of(&exact, 3.0);
+        let mut fast_marching = FastMarching3d::new(&size);
+
+        let mut first = phi0.clone();
+        fast_marching.reinitialize(&phi0, &mut first, &band, false);
+        let mut second = phi0.clone();
+        fast_marching.reinitialize(&phi0, &mut second, &band, true);
+
+        assert!(max_error(&first, &exact, &band) < 0.3);
+        assert!(max_error(&second, &exact, &band) < 0.2);
+    }
+
+    #[test]
+    fn level_set_method_2d() {
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let size = Rc::new(SpaceSize2d::new(11, 11));
+        let gray = Rc::new(RefCell::new(vec![0u8; 121]));
+        let area = |phi: &Vec<f64>| phi.iter().filter(|v| **v < 0.0).count();
+
+        let mut areas = Vec::new();
+        for reinitialization in [
+            Reinitialization::DistanceMap,
+            Reinitialization::FastMarching,
+            Reinitialization::FastMarchingSecondOrder,
+        ] {
+            let mut params = Parameters::new();
+            params.wband = 3;
+            params.wreset = 1;
+            params.constant_speed = -1.0;
+            params.gain = 0.1;
+            params.time_step = 0.5;
+            params.reinitialization = reinitialization;
+            let mut lsm =
+                LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+            lsm.initialize(&initial_front);
+
+            // the rectangle is 2 cells away from (5, 5) and the front stays on the zero-levelset
+            let phi = lsm.get_phi();
+            assert!((phi.borrow()[5 + 11 * 5] + 2.0).abs() < 0.1);
+            for p in lsm.get_front().borrow().iter() {
+                assert_eq!(phi.borrow()[(p.x + 11 * p.y) as usize], 0.0);
+            }
+
+            let result = lsm.run(10);
+            assert!(result.phi.iter().all(|v| v.is_finite()));
+            areas.push(area(&result.phi));
+        }
+        assert!(areas[1].abs_diff(areas[0]) <= 4);
+        assert!(areas[2].abs_diff(areas[0]) <= 4);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::core::gaussian::{convolve, convolve_within, dilate, make_kernel, smooth};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel() {
+        let kernel = make_kernel(1.0);
+        assert_eq!(kernel.len(), 7);
+        assert!((kernel.iter().sum::<f64>() - 1.0).abs() < 1.0e-12);
+        for k in 0..3 {
+            assert_eq!(kernel[k], kernel[6 - k]);
+            assert!(kernel[k] < kernel[k + 1]);
+        }
+        assert!((kernel[4] / kernel[3] - (-0.5f64).exp()).abs() < 1.0e-12);
+        assert_eq!(make_kernel(0.4).len(), 5);
+    }
+
+    #[test]
+    fn convolve_2d() {
+        let kernel = make_kernel(0.5);
+        let extents = [7, 6];
+
+        // a constant is kept away from the edges and darkened near them
+        let result = convolve(&[2.0; 42], &extents, &kernel);
+        assert!((result[3 + 7 * 3] - 2.0).abs() < 1.0e-12);
+        assert!(result[0] < 2.0);
+
+        // an impulse spreads as the outer product of the kernel
+        let mut impulse = vec![0.0; 42];
+        impulse[3 + 7 * 2] = 1.0;
+        let result = convolve(&impulse, &extents, &kernel);
+        for y in 0..6 {
+            for x in 0..7 {
+                let (dx, dy) = (x as i32 - 3, y as i32 - 2);
+                let expected = if dx.abs() <= 2 && dy.abs() <= 2 {
+                    kernel[(dx + 2) as usize] * kernel[(dy + 2) as usize]
+                } else {
+                    0.0
+                };
+                assert!((result[x + 7 * y] - expected).abs() < 1.0e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn convolve_3d() {
+        let kernel = make_kernel(0.3);
+        let extents = [3, 4, 5];
+        let mut impulse = vec![0.0; 60];
+        impulse[1 + 3 * 2 + 12 * 3] = 1.0;
+        let result = convolve(&impulse, &extents, &kernel);
+        assert!((result.iter().sum::<f64>() - 1.0).abs() < 1.0e-12);
+        let along_z = result[1 + 3 * 2 + 12 * 4] / result[1 + 3 * 2 + 12 * 3];
+        assert!((along_z - kernel[0] / kernel[1]).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn dilate_2d() {
+        let extents = [7, 6];
+        let mut is_marked = vec![false; 42];
+        let mut result = dilate(&[1 + 7 * 3, 2 + 7 * 3], &extents, 2, &mut is_marked);
+        result.sort();
+        let expected: Vec<usize> = (0..42)
+            .filter(|i| i % 7 <= 4 && (1..=5).contains(&(i / 7)))
+            .collect();
+        assert_eq!(result, expected);
+        assert!(is_marked.iter().all(|m| !m));
+    }
+
+    #[test]
+    fn convolve_within_region() {
+        let kernel = make_kernel(0.5);
+        let extents = [7, 6, 5];
+        let values: Vec<f64> = (0..210).map(|i| ((i * 37) % 11) as f64).collect();
+        let expected = convolve(&values, &extents, &kernel);
+
+        // exact where the kernel box lies in the region, and untouched outside it
+        let targets = [3 + 7 * 2 + 42 * 2, 0];
+        let region = dilate(&targets, &extents, 2, &mut vec![false; 210]);
+        let mut result = vec![-1.0; 210];
+        let mut scratch = vec![0.0; 210];
+        convolve_within(
+            &values,
+            &extents,
+            &kernel,
+            &region,
+            &mut result,
+            &mut scratch,
+        );
+        for i in 0..210 {
+            if targets.contains(&i) {
+                assert!((result[i] - expected[i]).abs() < 1.0e-12);
+            } else if !region.contains(&i) {
+                assert_eq!(result[i], -1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_near_edges() {
+        // a constant stays constant up to the edges, and a ramp stays a ramp inside
+        let extents = [8, 5];
+        let result = smooth(&[3.0; 40], &extents, 1.0);
+        assert!(result.iter().all(|v| (v - 3.0).abs() < 1.0e-12));
+        let ramp: Vec<f64> = (0..40).map(|i| (i % 8) as f64).collect();
+        let result = smooth(&ramp, &extents, 0.5);
+        assert!((result[4 + 8 * 2] - 4.0).abs() < 1.0e-9);
+        assert!(result[0] > 0.0);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::core::gradient_vector_flow::VectorField;
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::initial_front::{InitialFront2d, InitialFront3d};
+use crate::core::level_set_method::{LevelSetMethod2d, LevelSetMethod3d};
+use crate::core::parameters::Parameters;
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use crate::core::speed_factor::{SpeedFactor2d, SpeedFactorMethod};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffuse_2d() {
+        // the edge map has a ridge along x = 10
+        let extents = [21, 5];
+        let mut edge_gradient = vec![0.0; 2 * 21 * 5];
+        for j in 0..5 {
+            edge_gradient[2 * (9 + 21 * j)] = 0.5;
+            edge_gradient[2 * (11 + 21 * j)] = -0.5;
+        }
+        let field = VectorField::diffuse(&extents, &edge_gradient, 80, 0.2);
+        assert_eq!(field.vectors.len(), edge_gradient.len());
+
+        // v points to the ridge from both sides, far from it too
+        for j in 0..5 {
+            for i in 0..21 {
+                let v = field.get_vector(i + 21 * j);
+                assert_eq!(v[1], 0.0);
+                assert_eq!(v[0] > 0.0, i < 10);
+                assert_eq!(v[0] < 0.0, i > 10);
+            }
+        }
+        // and decreases away from it
+        for i in 0..9 {
+            assert!(field.get_vector(i)[0] < field.get_vector(i + 1)[0]);
+        }
+    }
+
+    #[test]
+    fn diffuse_3d() {
+        // the edge map has a peak at the center
+        let extents = [7, 7, 7];
+        let mut edge_gradient = vec![0.0; 3 * 343];
+        let center = 3 + 7 * 3 + 49 * 3;
+        for (axis, stride) in [1, 7, 49].iter().enumerate() {
+            edge_gradient[3 * (center - stride) + axis] = 0.5;
+            edge_gradient[3 * (center + stride) + axis] = -0.5;
+        }
+        let field = VectorField::diffuse(&extents, &edge_gradient, 30, 0.2);
+
+        // v points to the center from the corners
+        let v = field.get_vector(0);
+        assert!(v.iter().all(|c| *c > 0.0));
+        let v = field.get_vector(342);
+        assert!(v.iter().all(|c| *c < 0.0));
+        assert!(field.get_vector(center).iter().all(|c| c.abs() < 1.0e-12));
+    }
+
+    #[test]
+    fn diffuse_without_edges() {
+        let field = VectorField::diffuse(&[4, 3], &[0.0; 24], 10, 0.2);
+        assert_eq!(field.vectors, vec![0.0; 24]);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let field = VectorField {
+            extents: vec![2, 1, 2],
+            vectors: (0..12).map(|v| v as f64 * 0.25 - 1.0).collect(),
+        };
+        let path = std::env::temp_dir().join("levelset_by_rust_vector_field.lsvf");
+        field.save(&path).unwrap();
+        let loaded = VectorField::load(&path).unwrap();
+        assert_eq!(loaded.extents, field.extents);
+        assert_eq!(loaded.vectors, field.vectors);
+
+        std::fs::write(&path, b"LSVF").unwrap();
+        assert!(VectorField::load(&path).is_err());
+        // a zero extent, an extent above i32::MAX and extents whose product overflows
+        for extents in [[0u32, 5], [0x8000_0000, 1], [0x1_0000, 0x1_0000]] {
+            let mut bytes = b"LSVF\x02\x00\x00\x00".to_vec();
+            for e in extents {
+                bytes.extend_from_slice(&e.to_le_bytes());
+            }
+            std::fs::write(&path, bytes).unwrap();
+            assert!(VectorField::load(&path).is_err());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn speed_factor_2d() {
+        // a vertical edge at x = 6
+        let size = Rc::new(SpaceSize2d::new(12, 6));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let gray: Vec<u8> = (0..72)
+            .map(|i| if i % 12 < 6 { 0u8 } else { 100u8 })
+            .collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut factor = SpeedFactor2d::new(Rc::clone(&indexer), Rc::clone(&gray));
+        factor.calculate_all(&size);
+        assert!(factor.get_vector_field().is_none());
+
+        factor.set_gradient_vector_flow(20, 0.2);
+        factor.calculate_all(&size);
+        let field = factor.get_vector_field().unwrap();
+        assert_eq!(field.extents, vec![12, 6]);
+        let v = field.get_vector(indexer.get(&Point2d::<i32>::new(1, 3)) as usize);
+        assert!(v[0] > 0.0);
+        let v = field.get_vector(indexer.get(&Point2d::<i32>::new(10, 3)) as usize);
+        assert!(v[0] < 0.0);
+    }
+
+    fn make_params(gvf_weight: f64) -> Parameters {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.time_step = 0.5;
+        params.gain = 0.1;
+        params.gvf_weight = gvf_weight;
+        params.gvf_iterations = 200;
+        params
+    }
+
+    fn is_in_cup(i: i32, j: i32) -> bool {
+        // a cup open to the top, with the concavity 8 pixels wide and 12 deep
+        let is_in_box = (10..30).contains(&i) && (12..32).contains(&j);
+        let is_in_concavity = (16..24).contains(&i) && j < 24;
+        is_in_box && !is_in_concavity
+    }
+
+    fn count_wrong_points(gvf_weight: f64) -> usize {
+        let size = Rc::new(SpaceSize2d::new(40, 40));
+        let indexer = Indexer2d::new(&size);
+        let mut gray = vec![20u8; size.total as usize];
+        for j in 0..size.height {
+            for i in 0..size.width {
+                if is_in_cup(i, j) {
+                    gray[indexer.get(&Point2d::<i32>::new(i, j)) as usize] = 200u8;
+                }
+            }
+        }
+        let gray = Rc::new(RefCell::new(gray));
+
+        // far from the cup
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(3, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(36, 36);
+
+        let mut lsm =
+            LevelSetMethod2d::new(make_params(gvf_weight), Rc::clone(&size), gray).unwrap();
+        lsm.initialize(&initial_front);
+        let result = lsm.run(300);
+        assert_eq!(lsm.get_vector_field().is_some(), gvf_weight != 0.0);
+
+        let mut wrong = 0;
+        for j in 0..size.height {
+            for i in 0..size.width {
+                let index = indexer.get(&Point2d::<i32>::new(i, j)) as usize;
+                if is_in_cup(i, j) != (result.phi[index] < 0.0) {
+                    wrong += 1;
+                }
+            }
+        }
+        wrong
+    }
+
+    #[test]
+    fn gradient_vector_flow_2d() {
+        let without_flow = count_wrong_points(0.0);
+        let with_flow = count_wrong_points(1.0);
+        // the corners rounded by the curvature term are missed
+        assert!(with_flow < 30);
+        assert!(without_flow > 10 * with_flow);
+    }
+
+    #[test]
+    fn gradient_vector_flow_3d() {
+        let size = Rc::new(SpaceSize3d::new(24, 24, 24));
+        let indexer = Indexer3d::new(&size);
+        let distance = |i: i32, j: i32, k: i32| {
+            (((i - 12) * (i - 12) + (j - 12) * (j - 12) + (k - 12) * (k - 12)) as f64).sqrt()
+        };
+        let mut gray = vec![20u8; size.total as usize];
+        for k in 0..24 {
+            for j in 0..24 {
+                for i in 0..24 {
+                    if distance(i, j, k) <= 6.0 {
+                        gray[indexer.get(&Point3d::<i32>::new(i, j, k)) as usize] = 200u8;
+                    }
+                }
+            }
+        }
+        let gray = Rc::new(RefCell::new(gray));
+        let mut initial_front = InitialFront3d::new();
+        initial_front.vertices[0] = Point3d::<i32>::new(3, 3, 3);
+        initial_front.vertices[1] = Point3d::<i32>::new(20, 20, 20);
+
+        let mut lsm = LevelSetMethod3d::new(make_params(1.0), Rc::clone(&size), gray).unwrap();
+        lsm.initialize(&initial_front);
+        let result = lsm.run(300);
+        assert!(result.iterations < 300);
+
+        // the surface settles between the voxels on both sides of the edge of the ball
+        for k in 0..24 {
+            for j in 0..24 {
+                for i in 0..24 {
+                    let index = indexer.get(&Point3d::<i32>::new(i, j, k)) as usize;
+                    let d = distance(i, j, k);
+                    if d < 5.0 {
+                        assert!(result.phi[index] < 0.0);
+                    } else if d > 6.0 {
+                        assert!(result.phi[index] > 0.0);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::core::grid_file::{self, GridHeader};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_header(magic: &[u8; 4], extents: &[u32]) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.extend_from_slice(&(extents.len() as u32).to_le_bytes());
+        for e in extents {
+            bytes.extend_from_slice(&e.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn read_header() {
+        let mut bytes = make_header(b"TEST", &[3, 4, 5]);
+        bytes.extend_from_slice(&1.5f64.to_le_bytes());
+        let header = GridHeader::read(&bytes, b"TEST").unwrap();
+        assert_eq!(header.extents, vec![3, 4, 5]);
+        assert_eq!(header.total, 60);
+        assert_eq!(header.offset, 20);
+        assert_eq!(grid_file::read_values(&bytes[header.offset..]), vec![1.5]);
+
+        // the header is read whatever follows it
+        let bytes = make_header(b"TEST", &[3, 4]);
+        assert_eq!(GridHeader::read(&bytes, b"TEST").unwrap().offset, 16);
+    }
+
+    #[test]
+    fn read_invalid_header() {
+        assert!(GridHeader::read(&make_header(b"TEST", &[3, 4]), b"LSDM").is_none());
+        assert!(GridHeader::read(&make_header(b"TEST", &[3]), b"TEST").is_none());
+        assert!(GridHeader::read(&make_header(b"TEST", &[3, 0]), b"TEST").is_none());
+        assert!(GridHeader::read(&make_header(b"TEST", &[3, u32::MAX]), b"TEST").is_none());
+        // the product of the extents overflows
+        let large = 1 << 16;
+        assert!(GridHeader::read(&make_header(b"TEST", &[large, large]), b"TEST").is_none());
+        // the extents are cut off
+        let bytes = make_header(b"TEST", &[3, 4, 5]);
+        assert!(GridHeader::read(&bytes[..18], b"TEST").is_none());
+        assert!(GridHeader::read(b"TE", b"TEST").is_none());
+    }
+}
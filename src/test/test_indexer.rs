@@ -1,4 +1,4 @@
-use crate::core::indexer::IndexerMethod;
+use crate::core::indexer::{FlatIndexer, IndexerMethod};
 use crate::core::types::{Indexer, IntPoint, SpaceSize, ThreeDim, TwoDim};
 #[cfg(test)]
 mod tests {
@@ -22,4 +22,29 @@ mod tests {
         let q = indexer.get(&p);
         assert_eq!(q, 1 + 1 * 2 + 3 * 2);
     }
+
+    #[test]
+    fn flat_indexer() {
+        let indexer = FlatIndexer::new(&[4, 3, 2]);
+        assert_eq!(indexer.get_strides(), &[1, 4, 12]);
+        assert_eq!(indexer.get_total(), 24);
+        // (1, 2, 1)
+        let index = 1 + 4 * 2 + 12;
+        let coordinates: Vec<i32> = (0..3).map(|axis| indexer.coordinate(index, axis)).collect();
+        assert_eq!(coordinates, vec![1, 2, 1]);
+        assert_eq!(indexer.shift(index, 0, -1), Some(index - 1));
+        assert_eq!(indexer.shift(index, 1, 1), None);
+        assert_eq!(indexer.shift(index, 2, -1), Some(index - 12));
+        assert_eq!(
+            indexer.neighbors(index),
+            [Some(20), Some(22), Some(17), None, Some(9), None]
+        );
+
+        // the axes beyond the space have no neighbors
+        let indexer = FlatIndexer::new(&[3, 3]);
+        assert_eq!(
+            indexer.neighbors(0),
+            [None, Some(1), None, Some(3), None, None]
+        );
+    }
 }
@@ -3,7 +3,9 @@ use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
 use crate::core::initial_front::{InitialFront2d, InitialFront3d};
 use crate::core::inside_estimator::{InsideEstimator2d, InsideEstimator3d, InsideEstimatorMethod};
 use crate::core::level_set_method::{LevelSetMethod2d, LevelSetMethod3d};
-use crate::core::parameters::Parameters;
+use crate::core::parameters::{
+    Parameters, Reinitialization, ReinitializationTrigger, TimeIntegrator,
+};
 use crate::core::point::{Point2d, Point3d};
 use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
 use crate::core::status::Status;
@@ -29,7 +31,7 @@ mod tests {
         initial_front.vertices[1] = Point2d::<i32>::new(2, 2);
         let size = Rc::new(SpaceSize2d::new(3, 3));
         let gray = Rc::new(RefCell::new(vec![0u8]));
-        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         let front = lsm.get_front();
         assert_eq!(front.borrow().len(), 8);
@@ -46,7 +48,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize3d::new(3, 3, 3));
         let gray = Rc::new(RefCell::new(vec![0u8]));
-        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
 
         let front = lsm.get_front();
@@ -63,7 +65,7 @@ mod tests {
         initial_front.vertices[1] = Point2d::<i32>::new(82, 74);
         let size = Rc::new(SpaceSize2d::new(101, 143));
         let gray = Rc::new(RefCell::new(vec![0u8]));
-        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
 
         let phi = lsm.get_phi();
@@ -115,7 +117,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize3d::new(101, 143, 131));
         let gray = Rc::new(RefCell::new(vec![0u8]));
-        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
 
         let phi = lsm.get_phi();
@@ -180,7 +182,8 @@ mod tests {
 
         let size = Rc::new(SpaceSize3d::new(101, 143, 131));
         let gray = Rc::new(RefCell::new(vec![0u8]));
-        let mut lsm = LevelSetMethod3d::new(params.clone(), Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm =
+            LevelSetMethod3d::new(params.clone(), Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
 
@@ -220,7 +223,8 @@ mod tests {
 
         let size = Rc::new(SpaceSize2d::new(101, 143));
         let gray = Rc::new(RefCell::new(vec![0u8]));
-        let mut lsm = LevelSetMethod2d::new(params.clone(), Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm =
+            LevelSetMethod2d::new(params.clone(), Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
 
@@ -343,7 +347,7 @@ mod tests {
         let size = Rc::new(SpaceSize2d::new(11, 11));
         let gray = make_input_gray_2d(&size, &initial_front);
 
-        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
 
@@ -396,7 +400,7 @@ mod tests {
         let size = Rc::new(SpaceSize3d::new(11, 11, 11));
         let gray = make_input_gray(&size, &initial_front);
 
-        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
 
@@ -487,7 +491,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize2d::new(11, 11));
         let gray = make_input_gray_2d(&size, &initial_front);
-        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
 
@@ -533,7 +537,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize3d::new(11, 11, 11));
         let gray = make_input_gray(&size, &initial_front);
-        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
 
@@ -642,7 +646,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize2d::new(11, 11));
         let gray = make_input_gray_2d(&size, &initial_front);
-        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
 
@@ -698,7 +702,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize3d::new(11, 11, 11));
         let gray = make_input_gray(&size, &initial_front);
-        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
 
@@ -758,7 +762,7 @@ mod tests {
         initial_front.vertices[1] = Point2d::<i32>::new(right, bottom);
         let size = Rc::new(SpaceSize2d::new(11, 11));
         let gray = make_input_gray_2d(&size, &initial_front);
-        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_distance_map();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
@@ -852,7 +856,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize3d::new(11, 11, 11));
         let gray = make_input_gray(&size, &initial_front);
-        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
         lsm.initialize_distance_map();
         lsm.initialize_along_front(&initial_front);
         lsm.initialize_over_all(&initial_front);
@@ -1209,7 +1213,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize2d::new(11, 11));
         let gray = make_input_gray_2d(&size, &initial_front);
-        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
 
         let mut statuses = lsm.get_statuses();
 
@@ -1248,7 +1252,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize3d::new(11, 11, 11));
         let gray = make_input_gray(&size, &initial_front);
-        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
 
         let mut statuses = lsm.get_statuses();
 
@@ -1266,6 +1270,33 @@ mod tests {
         assert_eq!(narrow_band[2], Point3d::<i32>::new(2, 0, 0));
     }
 
+    #[test]
+    fn update_narrow_band_2d() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.constant_speed = -1.0;
+        params.gain = 0.1;
+        params.wreset = 1;
+        params.time_step = 0.5;
+        params.reinitialization_trigger = ReinitializationTrigger::Interval;
+        params.reinitialization_interval = 2;
+
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let mut lsm = make_lsm_2d(params, &initial_front);
+
+        // the band tracked over the resets agrees with a scan over the whole space
+        for _ in 0..10 {
+            let narrow_band = lsm.get_narrow_bands().clone();
+            lsm.get_narrow_bands().clear();
+            lsm.register_to_narrow_band_();
+            assert_eq!(*lsm.get_narrow_bands(), narrow_band);
+            lsm.step();
+        }
+        assert!(lsm.get_reset_count() >= 5);
+    }
+
     #[test]
     fn propagate_front_2d() {
         let mut params = Parameters::new();
@@ -1277,7 +1308,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize2d::new(3, 3));
         let gray = Rc::new(RefCell::new(vec![0u8]));
-        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
 
         let phi = lsm.get_phi();
         let speed = lsm.get_speed();
@@ -1314,7 +1345,7 @@ mod tests {
 
         let size = Rc::new(SpaceSize3d::new(3, 3, 3));
         let gray = Rc::new(RefCell::new(vec![0u8]));
-        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
 
         let phi = lsm.get_phi();
         let speed = lsm.get_speed();
@@ -1342,4 +1373,471 @@ mod tests {
         lsm.propagate_front();
         assert_eq!(phi.borrow()[13], 2.0 + 3.0 * 91.0_f64.sqrt());
     }
+
+    fn make_lsm_2d(params: Parameters, initial_front: &InitialFront2d) -> LevelSetMethod2d {
+        let size = Rc::new(SpaceSize2d::new(11, 11));
+        let gray = make_input_gray_2d(&size, initial_front);
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(initial_front);
+        lsm
+    }
+
+    #[test]
+    fn create_labels_2d() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.constant_speed = 1.0;
+        params.gain = 2.0;
+        params.wreset = 1;
+
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let mut lsm = make_lsm_2d(params, &initial_front);
+
+        let front_size = lsm.get_front().borrow().len();
+        let resets = lsm.create_labels();
+        assert!(!resets);
+        assert_eq!(lsm.get_front().borrow().len(), front_size);
+
+        // a front point which has come from the reset band requests a reset.
+        let statuses = lsm.get_statuses();
+        let indexer = lsm.get_indexer();
+        let p = Point2d::<i32>::new(5, 3);
+        statuses.borrow_mut()[indexer.get(&p) as usize] = Status::ResetBand;
+        let resets = lsm.create_labels();
+        assert!(resets);
+        assert_eq!(statuses.borrow()[indexer.get(&p) as usize], Status::Front);
+
+        // a point without zero crossing leaves the front.
+        let phi = lsm.get_phi();
+        for v in phi.borrow_mut().iter_mut() {
+            *v = 2.0;
+        }
+        let q = Point2d::<i32>::new(5, 5);
+        phi.borrow_mut()[indexer.get(&q) as usize] = -1.0;
+        let resets = lsm.create_labels();
+        assert!(!resets);
+        let front = lsm.get_front();
+        assert_eq!(front.borrow().len(), 1);
+        assert_eq!(front.borrow()[0], q);
+        assert_eq!(statuses.borrow()[indexer.get(&p) as usize], Status::Band);
+    }
+
+    #[test]
+    fn run_2d() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.constant_speed = -1.0;
+        params.gain = 0.1;
+        params.wreset = 1;
+        params.time_step = 0.5;
+        params.speed_threshold = 0.05;
+
+        // the front shrinks from around a bright square onto its edges
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 2);
+        initial_front.vertices[1] = Point2d::<i32>::new(18, 18);
+        let size = Rc::new(SpaceSize2d::new(21, 21));
+        let gray: Vec<u8> = (0..441)
+            .map(|i| {
+                let is_object = (7..14).contains(&(i % 21)) && (7..14).contains(&(i / 21));
+                if is_object {
+                    255u8
+                } else {
+                    0u8
+                }
+            })
+            .collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        let initial_area = count_inside(&lsm.get_phi().borrow());
+
+        let max_iterations = 100;
+        let result = lsm.run(max_iterations);
+        // the stopping condition holds before the limit
+        assert!(0 < result.iterations);
+        assert!(result.iterations < max_iterations);
+        assert_eq!(result.iterations, lsm.get_iteration());
+        assert_eq!(result.phi.len(), 441);
+        // the negative speed shrinks the inside onto the square
+        assert!(count_inside(&result.phi) < initial_area);
+        assert!(count_inside(&result.phi) >= 49);
+        assert!(!result.front.is_empty());
+
+        let statuses = lsm.get_statuses();
+        let indexer = lsm.get_indexer();
+        for p in result.front.iter() {
+            assert_eq!(statuses.borrow()[indexer.get(p) as usize], Status::Front);
+            // the front lies on the sign change of phi
+            let is_inside = result.phi[indexer.get(p) as usize] < 0.0;
+            assert!([(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| {
+                let q = Point2d::<i32>::new(p.x + dx, p.y + dy);
+                (result.phi[indexer.get(&q) as usize] < 0.0) != is_inside
+            }));
+        }
+    }
+
+    #[test]
+    fn run_3d() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.constant_speed = -1.0;
+        params.gain = 0.1;
+        params.wreset = 1;
+        params.time_step = 0.5;
+        params.speed_threshold = 0.05;
+
+        // the front shrinks from around a bright cube onto its faces
+        let mut initial_front = InitialFront3d::new();
+        initial_front.vertices[0] = Point3d::<i32>::new(2, 2, 2);
+        initial_front.vertices[1] = Point3d::<i32>::new(14, 14, 14);
+        let size = Rc::new(SpaceSize3d::new(17, 17, 17));
+        let gray: Vec<u8> = (0..4913)
+            .map(|i| {
+                let is_object = [i % 17, (i / 17) % 17, i / 289]
+                    .iter()
+                    .all(|c| (5..12).contains(c));
+                if is_object {
+                    255u8
+                } else {
+                    0u8
+                }
+            })
+            .collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        let initial_volume = count_inside(&lsm.get_phi().borrow());
+
+        let max_iterations = 100;
+        let result = lsm.run(max_iterations);
+        // the stopping condition holds before the limit
+        assert!(0 < result.iterations);
+        assert!(result.iterations < max_iterations);
+        assert_eq!(result.phi.len(), 4913);
+        // the negative speed shrinks the inside onto the cube
+        assert!(count_inside(&result.phi) < initial_volume);
+        assert!(count_inside(&result.phi) >= 343);
+        assert!(!result.front.is_empty());
+
+        let statuses = lsm.get_statuses();
+        let indexer = lsm.get_indexer();
+        let offsets = [
+            (-1, 0, 0),
+            (1, 0, 0),
+            (0, -1, 0),
+            (0, 1, 0),
+            (0, 0, -1),
+            (0, 0, 1),
+        ];
+        for p in result.front.iter() {
+            assert_eq!(statuses.borrow()[indexer.get(p) as usize], Status::Front);
+            // the front lies on the sign change of phi
+            let is_inside = result.phi[indexer.get(p) as usize] < 0.0;
+            assert!(offsets.iter().any(|(dx, dy, dz)| {
+                let q = Point3d::<i32>::new(p.x + dx, p.y + dy, p.z + dz);
+                (result.phi[indexer.get(&q) as usize] < 0.0) != is_inside
+            }));
+        }
+    }
+
+    fn count_inside(phi: &[f64]) -> usize {
+        phi.iter().filter(|v| **v < 0.0).count()
+    }
+
+    #[test]
+    fn propagate_front_with_advection_2d() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.time_step = 1.0;
+        params.advection_gain = 2.0;
+
+        // a vertical edge at x = 4
+        let size = Rc::new(SpaceSize2d::new(7, 7));
+        let gray: Vec<u8> = (0..49)
+            .map(|i| if i % 7 < 4 { 0u8 } else { 100u8 })
+            .collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.calculate_speed_factors();
+
+        let phi = lsm.get_phi();
+        for (i, v) in phi.borrow_mut().iter_mut().enumerate() {
+            *v = (i % 7) as f64 - 2.0;
+        }
+        let p = Point2d::<i32>::new(2, 3);
+        lsm.get_narrow_bands().push(p);
+        lsm.propagate_front();
+
+        // g decreases toward the edge, so the velocity -gain * grad(g) points to +x
+        // and phi decreases by the velocity times the backward difference 1.
+        let after = phi.borrow()[(2 + 7 * 3) as usize];
+        assert!(after < 0.0);
+    }
+
+    fn make_time_integration_parameters(time_integrator: TimeIntegrator) -> Parameters {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.constant_speed = -1.0;
+        params.gain = 0.1;
+        params.wreset = 1;
+        params.time_step = 0.5;
+        params.time_integrator = time_integrator;
+        params
+    }
+
+    #[test]
+    fn propagate_front_by_euler_2d() {
+        let params = make_time_integration_parameters(TimeIntegrator::Euler);
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let mut lsm = make_lsm_2d(params, &initial_front);
+
+        let before = lsm.get_phi().borrow().clone();
+        lsm.propagate_front();
+        let phi = lsm.get_phi();
+        let dphi = lsm.get_dphi();
+        for (i, v) in phi.borrow().iter().enumerate() {
+            assert_eq!(*v, before[i] - dphi.borrow()[i]);
+        }
+    }
+
+    #[test]
+    fn propagate_front_by_runge_kutta_2d() {
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+
+        let area = |phi: &Vec<f64>| phi.iter().filter(|v| **v < 0.0).count();
+        let mut euler = make_lsm_2d(
+            make_time_integration_parameters(TimeIntegrator::Euler),
+            &initial_front,
+        );
+        let initial_area = area(&euler.get_phi().borrow());
+        let euler_area = area(&euler.run(10).phi);
+
+        for time_integrator in [TimeIntegrator::Rk2, TimeIntegrator::Rk3] {
+            let mut lsm = make_lsm_2d(
+                make_time_integration_parameters(time_integrator),
+                &initial_front,
+            );
+            let before = lsm.get_phi().borrow().clone();
+            lsm.propagate_front();
+            // the intermediate stages move phi only within the narrow band
+            let phi = lsm.get_phi();
+            let statuses = lsm.get_statuses();
+            for (i, v) in phi.borrow().iter().enumerate() {
+                assert!(v.is_finite());
+                if statuses.borrow()[i] == Status::Farway {
+                    assert_eq!(*v, before[i]);
+                }
+            }
+
+            let mut lsm = make_lsm_2d(
+                make_time_integration_parameters(time_integrator),
+                &initial_front,
+            );
+            let result = lsm.run(10);
+            // a negative speed shrinks the inside like the Euler update does
+            assert!(area(&result.phi) < initial_area);
+            assert!(area(&result.phi).abs_diff(euler_area) <= 4);
+        }
+    }
+
+    #[test]
+    fn fixed_time_step_2d() {
+        let params = make_time_integration_parameters(TimeIntegrator::Euler);
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let mut lsm = make_lsm_2d(params, &initial_front);
+
+        let result = lsm.run(5);
+        assert_eq!(result.time_steps.len(), result.iterations);
+        assert!(result.time_steps.iter().all(|dt| *dt == 0.5));
+    }
+
+    #[test]
+    fn adaptive_time_step_2d() {
+        let mut params = make_time_integration_parameters(TimeIntegrator::Euler);
+        params.time_step = 10.0;
+        params.adaptive_time_step = true;
+        params.cfl_number = 0.5;
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let mut lsm = make_lsm_2d(params, &initial_front);
+
+        // the speed on the narrow band is at most |constant_speed| + gain * |kappa|
+        let speed = lsm.get_speed();
+        let max_speed = speed.borrow().iter().fold(0.0, |m: f64, v| m.max(v.abs()));
+        let dt = lsm.calculate_time_step();
+        assert!(0.0 < dt);
+        assert!((dt - 0.5 / (max_speed * 2.0f64.sqrt() + 2.0 * 2.0 * 0.1)).abs() < 1.0e-12);
+
+        let result = lsm.run(10);
+        assert_eq!(result.time_steps.len(), result.iterations);
+        assert_eq!(lsm.get_time_steps(), &result.time_steps[..]);
+        assert_eq!(result.time_steps[0], dt);
+        // the curvature term alone bounds dt by cfl_number / (2 * dimension * gain)
+        assert!(result
+            .time_steps
+            .iter()
+            .all(|dt| *dt <= 0.5 / (2.0 * 2.0 * 0.1)));
+        assert!(result.phi.iter().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn cfl_time_step() {
+        let mut params = make_time_integration_parameters(TimeIntegrator::Euler);
+        params.time_step = 10.0;
+        params.adaptive_time_step = true;
+        params.cfl_number = 0.5;
+
+        // |speed| * sqrt(dimension) + 2 * dimension * gain bounds the rate of change
+        let size = Rc::new(SpaceSize2d::new(5, 5));
+        let gray = Rc::new(RefCell::new(vec![0u8; 25]));
+        let mut lsm = LevelSetMethod2d::new(params.clone(), Rc::clone(&size), gray).unwrap();
+        lsm.get_narrow_bands().push(Point2d::<i32>::new(2, 2));
+        lsm.get_speed().borrow_mut()[12] = -2.0;
+        let dt = lsm.calculate_time_step();
+        assert!((dt - 0.5 / (2.0 * 2.0f64.sqrt() + 0.4)).abs() < 1.0e-12);
+
+        let size = Rc::new(SpaceSize3d::new(5, 5, 5));
+        let gray = Rc::new(RefCell::new(vec![0u8; 125]));
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), gray).unwrap();
+        lsm.get_narrow_bands().push(Point3d::<i32>::new(2, 2, 2));
+        lsm.get_speed().borrow_mut()[62] = 2.0;
+        let dt = lsm.calculate_time_step();
+        assert!((dt - 0.5 / (2.0 * 3.0f64.sqrt() + 0.6)).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn reinitialize_pde_2d() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        let n = 31;
+        let size = Rc::new(SpaceSize2d::new(n, n));
+        let gray = Rc::new(RefCell::new(vec![0u8; (n * n) as usize]));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+
+        // a circle whose phi is far from a distance function
+        let radius = 8.4;
+        let c = 15.0;
+        let phi = lsm.get_phi();
+        let mut exact = vec![0.0; (n * n) as usize];
+        for y in 0..n {
+            for x in 0..n {
+                let r = ((x as f64 - c).powi(2) + (y as f64 - c).powi(2)).sqrt();
+                let i = (x + n * y) as usize;
+                exact[i] = r - radius;
+                phi.borrow_mut()[i] = 3.0 * (r - radius);
+                if exact[i].abs() < 4.0 {
+                    lsm.get_narrow_bands().push(Point2d::<i32>::new(x, y));
+                }
+            }
+        }
+        let before = phi.borrow().clone();
+
+        lsm.reinitialize_pde(20);
+
+        let mut max_error = 0.0f64;
+        let mut max_drift = 0.0f64;
+        for (i, e) in exact.iter().enumerate() {
+            let v = phi.borrow()[i];
+            if e.abs() < 3.0 {
+                max_error = max_error.max((v - e).abs());
+            }
+            // the sign, and so the zero-levelset, is kept
+            assert_eq!(v < 0.0, before[i] < 0.0);
+            if e.abs() < 1.0 {
+                max_drift = max_drift.max((v - e).abs());
+            }
+        }
+        assert!(max_error < 0.2);
+        // the subcell fix keeps the zero-levelset in place
+        assert!(max_drift < 0.05);
+    }
+
+    #[test]
+    fn run_with_pde_reinitialization_2d() {
+        let mut params = make_time_integration_parameters(TimeIntegrator::Euler);
+        params.reinitialization = Reinitialization::Pde;
+        params.pde_iterations = 3;
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let mut lsm = make_lsm_2d(params, &initial_front);
+        let area = |phi: &Vec<f64>| phi.iter().filter(|v| **v < 0.0).count();
+        let initial_area = area(&lsm.get_phi().borrow());
+
+        let result = lsm.run(10);
+        assert!(result.phi.iter().all(|v| v.is_finite()));
+        assert!(area(&result.phi) < initial_area);
+    }
+
+    fn run_with_trigger_2d(
+        trigger: ReinitializationTrigger,
+        tolerance: f64,
+    ) -> (LevelSetMethod2d, usize) {
+        let mut params = make_time_integration_parameters(TimeIntegrator::Euler);
+        params.reinitialization_trigger = trigger;
+        params.reinitialization_interval = 3;
+        params.gradient_deviation_tolerance = tolerance;
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let mut lsm = make_lsm_2d(params, &initial_front);
+        let mut reset_band_hits = 0;
+        for _ in 0..9 {
+            lsm.step();
+            if lsm.get_resets() && !lsm.is_reinitialization_triggered() {
+                reset_band_hits += 1;
+            }
+        }
+        (lsm, reset_band_hits)
+    }
+
+    #[test]
+    fn reinitialization_trigger_2d() {
+        let (lsm, hits) = run_with_trigger_2d(ReinitializationTrigger::ResetBand, 0.0);
+        assert_eq!(lsm.get_reset_count(), hits);
+        assert_eq!(lsm.make_result().resets, hits);
+
+        // every third iteration at least
+        let (mut lsm, _) = run_with_trigger_2d(ReinitializationTrigger::Interval, 0.0);
+        assert!(lsm.get_reset_count() >= 3);
+        assert!(lsm.is_reinitialization_triggered());
+
+        // a tolerance never exceeded leaves only the ResetBand
+        let (lsm, hits) = run_with_trigger_2d(ReinitializationTrigger::GradientDeviation, 1.0e+10);
+        assert_eq!(lsm.get_reset_count(), hits);
+
+        // a tolerance always exceeded rebuilds phi at every iteration
+        let (lsm, _) = run_with_trigger_2d(ReinitializationTrigger::GradientDeviation, -1.0);
+        assert_eq!(lsm.get_reset_count(), 9);
+        assert_eq!(lsm.make_result().resets, 9);
+    }
+
+    #[test]
+    fn calculate_gradient_deviation_2d() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        let size = Rc::new(SpaceSize2d::new(7, 7));
+        let gray = Rc::new(RefCell::new(vec![0u8; 49]));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        let phi = lsm.get_phi();
+        for (i, v) in phi.borrow_mut().iter_mut().enumerate() {
+            *v = 3.0 * ((i % 7) as f64 - 3.5);
+        }
+        assert_eq!(lsm.calculate_gradient_deviation(), 0.0);
+        lsm.get_narrow_bands().push(Point2d::<i32>::new(3, 3));
+        lsm.get_narrow_bands().push(Point2d::<i32>::new(0, 3));
+        assert_eq!(lsm.calculate_gradient_deviation(), 2.0);
+    }
 }
@@ -0,0 +1,231 @@
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::initial_front::{InitialFront2d, InitialFront3d};
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::morphological_snakes::{
+    MorphologicalSnakes2d, MorphologicalSnakes3d, MorphologicalSnakesMethod,
+};
+use crate::core::parameters::{Engine, Parameters, SpeedMode};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_params(speed_mode: SpeedMode) -> Parameters {
+        let mut params = Parameters::new();
+        params.engine = Engine::Morphological;
+        params.speed_mode = speed_mode;
+        params
+    }
+
+    #[test]
+    fn rejected_by_level_set_method() {
+        let params = make_params(SpeedMode::Edge);
+        assert_eq!(
+            LevelSetMethod2d::validate_parameters(&params),
+            Err("the morphological engine runs in MorphologicalSnakes2d/3d".to_string())
+        );
+        let size = Rc::new(SpaceSize2d::new(8, 8));
+        let gray = Rc::new(RefCell::new(vec![0u8; 64]));
+        assert!(LevelSetMethod2d::new(params, size, gray).is_err());
+    }
+
+    #[test]
+    fn local_binary_fitting_rejected() {
+        // the local binary fitting has no morphological counterpart
+        let size = Rc::new(SpaceSize2d::new(8, 8));
+        let gray = Rc::new(RefCell::new(vec![0u8; 64]));
+        let params = make_params(SpeedMode::LocalBinaryFitting);
+        assert_eq!(
+            MorphologicalSnakes2d::new(params.clone(), size, gray).err(),
+            Some(
+                "the morphological engine supports the edge and chan-vese speed modes".to_string()
+            )
+        );
+        let size = Rc::new(SpaceSize3d::new(4, 4, 4));
+        let gray = Rc::new(RefCell::new(vec![0u8; 64]));
+        assert!(MorphologicalSnakes3d::new(params, size, gray).is_err());
+    }
+
+    fn make_square(size: &SpaceSize2d) -> Vec<u8> {
+        // a bright square without sharp edges around it
+        let indexer = Indexer2d::new(size);
+        let mut gray = vec![20u8; size.total as usize];
+        for j in 10..20 {
+            for i in 10..20 {
+                gray[indexer.get(&Point2d::<i32>::new(i, j)) as usize] = 200u8;
+            }
+        }
+        gray
+    }
+
+    fn make_disk(size: &SpaceSize2d) -> Vec<u8> {
+        let indexer = Indexer2d::new(size);
+        let mut gray = vec![20u8; size.total as usize];
+        for j in 0..size.height {
+            for i in 0..size.width {
+                if (i - 20) * (i - 20) + (j - 20) * (j - 20) <= 100 {
+                    gray[indexer.get(&Point2d::<i32>::new(i, j)) as usize] = 200u8;
+                }
+            }
+        }
+        gray
+    }
+
+    fn count_wrong_points(phi: &[f64], gray: &[u8]) -> usize {
+        phi.iter()
+            .zip(gray)
+            .filter(|(v, g)| (**g == 200u8) != (**v < 0.0))
+            .count()
+    }
+
+    #[test]
+    fn initialize() {
+        let size = Rc::new(SpaceSize2d::new(6, 5));
+        let gray = Rc::new(RefCell::new(vec![0u8; 30]));
+        let mut snakes =
+            MorphologicalSnakes2d::new(make_params(SpeedMode::ChanVese), Rc::clone(&size), gray)
+                .unwrap();
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(1, 1);
+        initial_front.vertices[1] = Point2d::<i32>::new(4, 3);
+        snakes.initialize(&initial_front);
+
+        // only the points strictly inside the initial front, as in LevelSetMethod
+        let inside: Vec<usize> = (0..30).filter(|i| snakes.get_levelset()[*i] == 1).collect();
+        assert_eq!(inside, vec![14, 15]);
+    }
+
+    #[test]
+    fn chan_vese_2d() {
+        let size = Rc::new(SpaceSize2d::new(30, 30));
+        let gray = Rc::new(RefCell::new(make_square(&size)));
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(3, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(26, 26);
+
+        let mut snakes = MorphologicalSnakes2d::new(
+            make_params(SpeedMode::ChanVese),
+            Rc::clone(&size),
+            Rc::clone(&gray),
+        )
+        .unwrap();
+        snakes.initialize(&initial_front);
+        let result = snakes.run(100);
+
+        // the corners of the square are rounded by the curvature operators
+        assert!(count_wrong_points(&result.phi, &gray.borrow()) <= 4);
+        assert!(result.iterations < 100);
+        assert_eq!(result.time_steps.len(), result.iterations);
+        assert_eq!(result.resets, 0);
+        assert!(result.vetoes.is_empty());
+
+        // the front is the inside points next to the outside
+        let indexer = Indexer2d::new(&size);
+        assert!(result.front.len() >= 32);
+        for p in &result.front {
+            assert!(result.phi[indexer.get(p) as usize] < 0.0);
+            let has_outside = [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| {
+                let q = Point2d::<i32>::new(p.x + dx, p.y + dy);
+                result.phi[indexer.get(&q) as usize] > 0.0
+            });
+            assert!(has_outside);
+        }
+    }
+
+    #[test]
+    fn faster_than_level_set_method() {
+        let size = Rc::new(SpaceSize2d::new(30, 30));
+        let gray = Rc::new(RefCell::new(make_square(&size)));
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(3, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(26, 26);
+
+        let mut snakes = MorphologicalSnakes2d::new(
+            make_params(SpeedMode::ChanVese),
+            Rc::clone(&size),
+            Rc::clone(&gray),
+        )
+        .unwrap();
+        snakes.initialize(&initial_front);
+        let result = snakes.run(100);
+        let wrong = count_wrong_points(&result.phi, &gray.borrow());
+        assert!(wrong <= 4);
+
+        // the pde of the same region term on the same image, which moves the front
+        // by a fraction of a point per iteration, is still far from it after ten times as many
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.gain = 0.1;
+        params.time_step = 0.5;
+        params.speed_mode = SpeedMode::ChanVese;
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        let pde = lsm.run(10 * result.iterations);
+        assert_eq!(pde.iterations, 10 * result.iterations);
+        assert!(count_wrong_points(&pde.phi, &gray.borrow()) > 10 * wrong.max(1));
+    }
+
+    #[test]
+    fn geodesic_active_contour_2d() {
+        let size = Rc::new(SpaceSize2d::new(40, 40));
+        let gray = Rc::new(RefCell::new(make_disk(&size)));
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(3, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(36, 36);
+
+        // the balloon shrinks the front over the flat background until the edges stop it
+        let mut params = make_params(SpeedMode::Edge);
+        params.constant_speed = -1.0;
+        let mut snakes =
+            MorphologicalSnakes2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        snakes.initialize(&initial_front);
+        let result = snakes.run(100);
+
+        // only the four single pixel tips of the digital disk are smoothed out
+        assert!(count_wrong_points(&result.phi, &gray.borrow()) <= 4);
+        assert!(result.iterations < 100);
+    }
+
+    #[test]
+    fn chan_vese_3d() {
+        let size = Rc::new(SpaceSize3d::new(30, 30, 30));
+        let indexer = Indexer3d::new(&size);
+        let mut gray = vec![20u8; size.total as usize];
+        for k in 0..30 {
+            for j in 0..30 {
+                for i in 0..30 {
+                    if (i - 15) * (i - 15) + (j - 15) * (j - 15) + (k - 15) * (k - 15) <= 81 {
+                        gray[indexer.get(&Point3d::<i32>::new(i, j, k)) as usize] = 200u8;
+                    }
+                }
+            }
+        }
+        let gray = Rc::new(RefCell::new(gray));
+        let mut initial_front = InitialFront3d::new();
+        initial_front.vertices[0] = Point3d::<i32>::new(3, 3, 3);
+        initial_front.vertices[1] = Point3d::<i32>::new(26, 26, 26);
+
+        let mut snakes = MorphologicalSnakes3d::new(
+            make_params(SpeedMode::ChanVese),
+            Rc::clone(&size),
+            Rc::clone(&gray),
+        )
+        .unwrap();
+        snakes.initialize(&initial_front);
+        let result = snakes.run(100);
+
+        // the curvature operators shave a part of the surface of the ball,
+        // and the region term settles them into a cycle which stops the evolution
+        let object = gray.borrow().iter().filter(|v| **v == 200u8).count();
+        assert!(20 * count_wrong_points(&result.phi, &gray.borrow()) < object);
+        assert!(result.iterations < 100);
+        for p in &result.front {
+            assert!(result.phi[indexer.get(p) as usize] < 0.0);
+        }
+    }
+}
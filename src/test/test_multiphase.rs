@@ -0,0 +1,134 @@
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::multiphase::{make_exclusion, make_labels, Multiphase2d, MultiphaseMethod};
+use crate::core::parameters::Parameters;
+use crate::core::point::Point2d;
+use crate::core::space_size::SpaceSize2d;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        let a = Rc::new(RefCell::new(vec![-1.0, -2.0, 1.0, 0.0]));
+        let b = Rc::new(RefCell::new(vec![-2.0, -1.0, 2.0, -0.5]));
+        let labels = make_labels(&[a, b], 4);
+        assert_eq!(labels, vec![2, 1, 0, 2]);
+    }
+
+    #[test]
+    fn exclusion() {
+        // 4x3, the phase 2 owns (1, 1)
+        let mut labels = vec![0u8; 12];
+        labels[5] = 2;
+        labels[0] = 1;
+        let mut exclusion = vec![true; 12];
+        make_exclusion(&labels, 1, &[4, 3], &mut exclusion);
+        let expected: Vec<usize> = vec![1, 4, 5, 6, 9];
+        for (i, e) in exclusion.iter().enumerate() {
+            assert_eq!(*e, expected.contains(&i));
+        }
+
+        make_exclusion(&labels, 2, &[4, 3], &mut exclusion);
+        let expected: Vec<usize> = vec![0, 1, 4];
+        for (i, e) in exclusion.iter().enumerate() {
+            assert_eq!(*e, expected.contains(&i));
+        }
+    }
+
+    fn make_initial_front(left: i32, right: i32) -> InitialFront2d {
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(left, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(right, 10);
+        initial_front
+    }
+
+    fn make_parameters() -> Parameters {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.constant_speed = 1.0;
+        params.gain = 0.1;
+        params.time_step = 0.5;
+        params
+    }
+
+    fn count_overlap(a: &[f64], b: &[f64]) -> usize {
+        a.iter()
+            .zip(b.iter())
+            .filter(|(u, v)| **u < 0.0 && **v < 0.0)
+            .count()
+    }
+
+    #[test]
+    fn competing_fronts_2d() {
+        let size = Rc::new(SpaceSize2d::new(24, 14));
+        let gray = Rc::new(RefCell::new(vec![0u8; 24 * 14]));
+        let initial_fronts = [make_initial_front(2, 9), make_initial_front(14, 21)];
+
+        // each front alone expands over the other
+        let mut phis = Vec::new();
+        for initial_front in initial_fronts.iter() {
+            let mut lsm =
+                LevelSetMethod2d::new(make_parameters(), Rc::clone(&size), Rc::clone(&gray))
+                    .unwrap();
+            lsm.initialize(initial_front);
+            phis.push(lsm.run(12).phi);
+        }
+        assert!(count_overlap(&phis[0], &phis[1]) > 0);
+
+        let mut multiphase =
+            Multiphase2d::new(make_parameters(), Rc::clone(&size), Rc::clone(&gray));
+        multiphase.initialize(&initial_fronts).unwrap();
+        assert_eq!(multiphase.get_phase_count(), 2);
+        let initial_labels = multiphase.get_labels();
+        let result = multiphase.run(12);
+        assert_eq!(result.iterations, 12);
+        assert_eq!(result.phases.len(), 2);
+        assert_eq!(
+            count_overlap(&result.phases[0].phi, &result.phases[1].phi),
+            0
+        );
+
+        // both regions have grown toward each other and stopped
+        let count = |labels: &[u8], label: u8| labels.iter().filter(|l| **l == label).count();
+        for label in [1, 2] {
+            assert!(count(&result.labels, label) > count(&initial_labels, label));
+        }
+        for y in 3..=10 {
+            let row = &result.labels[(24 * y)..(24 * (y + 1))];
+            let last = row.iter().rposition(|l| *l == 1).unwrap();
+            let first = row.iter().position(|l| *l == 2).unwrap();
+            assert!(last < first);
+        }
+    }
+
+    #[test]
+    fn share_image() {
+        let size = Rc::new(SpaceSize2d::new(24, 14));
+        let gray: Vec<u8> = (0..24 * 14)
+            .map(|i| if i % 24 < 12 { 40u8 } else { 160u8 })
+            .collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut params = make_parameters();
+        params.gvf_weight = 1.0;
+        params.gvf_iterations = 10;
+        let mut first =
+            LevelSetMethod2d::new(params.clone(), Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        first.initialize(&make_initial_front(2, 9));
+
+        // the second phase reuses the speed factors and the vector field of the first
+        let mut second = LevelSetMethod2d::new_sharing_image(&first);
+        second.initialize(&make_initial_front(14, 21));
+        assert!(std::ptr::eq(
+            first.get_vector_field().unwrap(),
+            second.get_vector_field().unwrap()
+        ));
+        let mut alone = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        alone.initialize(&make_initial_front(14, 21));
+        assert_eq!(second.run(8).phi, alone.run(8).phi);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::observer::{IterationState, ObserverAction};
+use crate::core::parameters::Parameters;
+use crate::core::point::Point2d;
+use crate::core::space_size::SpaceSize2d;
+use crate::core::status::Status;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_lsm_2d() -> LevelSetMethod2d {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.constant_speed = -1.0;
+        params.gain = 0.1;
+        params.time_step = 0.5;
+
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(5, 5);
+        initial_front.vertices[1] = Point2d::<i32>::new(15, 15);
+        let size = Rc::new(SpaceSize2d::new(21, 21));
+        let gray = Rc::new(RefCell::new(vec![1u8; size.total as usize]));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        lsm
+    }
+
+    #[test]
+    fn observer_stops_evolution() {
+        let mut lsm = make_lsm_2d();
+        lsm.add_observer(Box::new(|state: &IterationState<Point2d<i32>>| {
+            if state.iteration == 3 {
+                ObserverAction::Stop
+            } else {
+                ObserverAction::Continue
+            }
+        }));
+        let result = lsm.run(100);
+        assert_eq!(result.iterations, 3);
+    }
+
+    #[test]
+    fn observer_receives_state() {
+        let mut lsm = make_lsm_2d();
+        let iterations = Rc::new(RefCell::new(Vec::<usize>::new()));
+        let front_sizes = Rc::new(RefCell::new(Vec::<usize>::new()));
+        let iterations_ = Rc::clone(&iterations);
+        let front_sizes_ = Rc::clone(&front_sizes);
+        lsm.add_observer(Box::new(move |state: &IterationState<Point2d<i32>>| {
+            assert_eq!(state.phi.len(), 21 * 21);
+            assert_eq!(state.statuses.len(), 21 * 21);
+            assert!(state.total_speed >= 0.0);
+            assert!(state.zero_count >= 0);
+            for p in state.front {
+                let index = (p.x + 21 * p.y) as usize;
+                assert_eq!(state.statuses[index], Status::Front);
+            }
+            iterations_.borrow_mut().push(state.iteration);
+            front_sizes_.borrow_mut().push(state.front.len());
+            ObserverAction::Continue
+        }));
+        let result = lsm.run(5);
+        assert_eq!(result.iterations, 5);
+        assert_eq!(*iterations.borrow(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(*front_sizes.borrow().last().unwrap(), result.front.len());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::core::parameters::Parameters;
+use crate::core::parameters::{
+    EdgeFunction, Engine, Parameters, Reinitialization, ReinitializationTrigger, SpatialScheme,
+    SpeedMode, TimeIntegrator,
+};
 
 #[cfg(test)]
 mod tests {
@@ -12,6 +15,36 @@ mod tests {
             gain: 4.0,
             constant_speed: 5.0,
             speed_threshold: 6.0,
+            speed_mode: SpeedMode::ChanVese,
+            lambda_inside: 7.0,
+            lambda_outside: 8.0,
+            lbf_sigma: 1.5,
+            advection_gain: 9.0,
+            edge_function: EdgeFunction::Sigmoid,
+            edge_sigma: 1.2,
+            edge_k: 2.5,
+            edge_power: 2.0,
+            sigmoid_alpha: -4.0,
+            sigmoid_beta: 20.0,
+            gvf_weight: 1.5,
+            gvf_iterations: 40,
+            gvf_mu: 0.1,
+            spatial_scheme: SpatialScheme::Weno5,
+            time_integrator: TimeIntegrator::Rk3,
+            adaptive_time_step: true,
+            cfl_number: 0.25,
+            reinitialization: Reinitialization::FastMarching,
+            pde_iterations: 7,
+            reinitialization_trigger: ReinitializationTrigger::Interval,
+            reinitialization_interval: 11,
+            gradient_deviation_tolerance: 0.3,
+            engine: Engine::SparseField,
+            distance_regularization: 0.1,
+            topology_preservation: true,
+            shape_prior_weight: 0.4,
+            shape_prior_pose_interval: 5,
+            morphological_smoothing: 3,
+            morphological_threshold: 0.7,
         };
         assert_eq!(1, params.wband);
         assert_eq!(2, params.wreset);
@@ -19,5 +52,69 @@ mod tests {
         assert_eq!(4.0, params.gain);
         assert_eq!(5.0, params.constant_speed);
         assert_eq!(6.0, params.speed_threshold);
+        assert_eq!(SpeedMode::ChanVese, params.speed_mode);
+        assert_eq!(7.0, params.lambda_inside);
+        assert_eq!(8.0, params.lambda_outside);
+        assert_eq!(1.5, params.lbf_sigma);
+        assert_eq!(9.0, params.advection_gain);
+        assert_eq!(EdgeFunction::Sigmoid, params.edge_function);
+        assert_eq!(1.2, params.edge_sigma);
+        assert_eq!(2.5, params.edge_k);
+        assert_eq!(2.0, params.edge_power);
+        assert_eq!(-4.0, params.sigmoid_alpha);
+        assert_eq!(20.0, params.sigmoid_beta);
+        assert_eq!(1.5, params.gvf_weight);
+        assert_eq!(40, params.gvf_iterations);
+        assert_eq!(0.1, params.gvf_mu);
+        assert_eq!(SpatialScheme::Weno5, params.spatial_scheme);
+        assert_eq!(TimeIntegrator::Rk3, params.time_integrator);
+        assert!(params.adaptive_time_step);
+        assert_eq!(0.25, params.cfl_number);
+        assert_eq!(Reinitialization::FastMarching, params.reinitialization);
+        assert_eq!(7, params.pde_iterations);
+        assert_eq!(
+            ReinitializationTrigger::Interval,
+            params.reinitialization_trigger
+        );
+        assert_eq!(11, params.reinitialization_interval);
+        assert_eq!(0.3, params.gradient_deviation_tolerance);
+        assert_eq!(Engine::SparseField, params.engine);
+        assert_eq!(0.1, params.distance_regularization);
+        assert!(params.topology_preservation);
+        assert_eq!(0.4, params.shape_prior_weight);
+        assert_eq!(5, params.shape_prior_pose_interval);
+        assert_eq!(3, params.morphological_smoothing);
+        assert_eq!(0.7, params.morphological_threshold);
+    }
+
+    #[test]
+    fn default_parameters() {
+        let params = Parameters::new();
+        assert_eq!(SpatialScheme::FirstOrder, params.spatial_scheme);
+        assert_eq!(TimeIntegrator::Euler, params.time_integrator);
+        assert!(!params.adaptive_time_step);
+        assert_eq!(0.5, params.cfl_number);
+        assert_eq!(Reinitialization::DistanceMap, params.reinitialization);
+        assert_eq!(
+            ReinitializationTrigger::ResetBand,
+            params.reinitialization_trigger
+        );
+        assert_eq!(Engine::NarrowBand, params.engine);
+        assert_eq!(0.2, params.distance_regularization);
+        assert_eq!(3.0, params.lbf_sigma);
+        assert_eq!(EdgeFunction::Rational, params.edge_function);
+        assert_eq!(0.0, params.edge_sigma);
+        assert_eq!(1.0, params.edge_k);
+        assert_eq!(1.0, params.edge_power);
+        assert_eq!(-10.0, params.sigmoid_alpha);
+        assert_eq!(30.0, params.sigmoid_beta);
+        assert_eq!(0.0, params.gvf_weight);
+        assert_eq!(80, params.gvf_iterations);
+        assert_eq!(0.2, params.gvf_mu);
+        assert!(!params.topology_preservation);
+        assert_eq!(0.0, params.shape_prior_weight);
+        assert_eq!(0, params.shape_prior_pose_interval);
+        assert_eq!(1, params.morphological_smoothing);
+        assert_eq!(0.5, params.morphological_threshold);
     }
 }
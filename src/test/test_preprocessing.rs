@@ -0,0 +1,257 @@
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::preprocessing::{
+    parse_pipeline, Filter, Preprocessing2d, Preprocessing3d, PreprocessingMethod,
+};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Filter::parse("gaussian:1.5", 2),
+            Ok(Filter::Gaussian { sigma: 1.5 })
+        );
+        assert_eq!(
+            Filter::parse("median:1", 2),
+            Ok(Filter::Median { radius: 1 })
+        );
+        assert_eq!(
+            Filter::parse("perona-malik:10, 0.125, 20", 2),
+            Ok(Filter::PeronaMalik {
+                iterations: 10,
+                time_step: 0.125,
+                conductance: 20.0
+            })
+        );
+        assert_eq!(
+            Filter::parse("curvature-diffusion:5,0.0625,3", 2),
+            Ok(Filter::CurvatureDiffusion {
+                iterations: 5,
+                time_step: 0.0625,
+                conductance: 3.0
+            })
+        );
+        assert_eq!(
+            Filter::parse("window:30,200", 2),
+            Ok(Filter::Window {
+                lower: 30.0,
+                upper: 200.0
+            })
+        );
+        assert_eq!(Filter::parse("rescale", 2), Ok(Filter::Rescale));
+
+        assert!(Filter::parse("sharpen:1", 2).is_err());
+        assert!(Filter::parse("gaussian", 2).is_err());
+        assert!(Filter::parse("gaussian:-1", 2).is_err());
+        assert!(Filter::parse("gaussian:a", 2).is_err());
+        assert!(Filter::parse("median:1.5", 2).is_err());
+        assert!(Filter::parse("median:15", 3).is_ok());
+        assert_eq!(
+            Filter::parse("median:16", 2),
+            Err("the radius of filter median:16 must be at most 15".to_string())
+        );
+        assert!(Filter::parse("median:1e12", 2).is_err());
+        // the radius must be an integer before it is bounded
+        assert_eq!(
+            Filter::parse("median:16.5", 2),
+            Err("invalid values of filter median:16.5".to_string())
+        );
+        assert!(Filter::parse("perona-malik:10,0.125", 2).is_err());
+        assert!(Filter::parse("window:200,30", 2).is_err());
+        assert!(Filter::parse("rescale:1", 2).is_err());
+
+        // the explicit diffusions are stable for time_step <= 1 / (2 * dimension)
+        assert!(Filter::parse("perona-malik:10,0.25,20", 2).is_ok());
+        assert!(Filter::parse("perona-malik:10,0.25,20", 3).is_err());
+        assert!(Filter::parse("perona-malik:10,1.25,20", 2).is_err());
+        assert!(Filter::parse("curvature-diffusion:5,0.125,3", 3).is_ok());
+        assert!(Filter::parse("curvature-diffusion:5,0.2,3", 3).is_err());
+    }
+
+    #[test]
+    fn pipeline() {
+        let text = "# denoise\nmedian:1\n\n  gaussian:2 # then smooth\nrescale\n";
+        assert_eq!(
+            parse_pipeline(text, 2),
+            Ok(vec![
+                Filter::Median { radius: 1 },
+                Filter::Gaussian { sigma: 2.0 },
+                Filter::Rescale
+            ])
+        );
+        assert!(parse_pipeline("median:1\nunknown\n", 2).is_err());
+        assert_eq!(parse_pipeline("# nothing\n", 2), Ok(vec![]));
+    }
+
+    fn make_step(size: &SpaceSize2d) -> Vec<u8> {
+        // dark on the left, bright on the right
+        let indexer = Indexer2d::new(size);
+        let mut gray = vec![0u8; size.total as usize];
+        for j in 0..size.height {
+            for i in 0..size.width {
+                let v = if i < size.width / 2 { 50u8 } else { 150u8 };
+                gray[indexer.get(&Point2d::<i32>::new(i, j)) as usize] = v;
+            }
+        }
+        gray
+    }
+
+    #[test]
+    fn constant_image() {
+        let size = Rc::new(SpaceSize2d::new(12, 10));
+        let preprocessing = Preprocessing2d::new(Rc::clone(&size));
+        let gray = vec![77u8; size.total as usize];
+        let filters = parse_pipeline(
+            "gaussian:2\nmedian:2\nperona-malik:5,0.2,10\ncurvature-diffusion:5,0.2,10",
+            2,
+        )
+        .unwrap();
+        assert_eq!(preprocessing.apply(&filters, &gray), gray);
+        assert_eq!(preprocessing.apply(&[], &gray), gray);
+    }
+
+    #[test]
+    fn median_2d() {
+        let size = Rc::new(SpaceSize2d::new(10, 10));
+        let indexer = Indexer2d::new(&size);
+        let mut gray = make_step(&size);
+        let outlier = indexer.get(&Point2d::<i32>::new(2, 5)) as usize;
+        gray[outlier] = 255u8;
+        let preprocessing = Preprocessing2d::new(Rc::clone(&size));
+        let filtered = preprocessing.apply(&[Filter::Median { radius: 1 }], &gray);
+        // the outlier is removed while the step stays in place
+        assert_eq!(filtered, make_step(&size));
+    }
+
+    #[test]
+    fn median_3d() {
+        let size = Rc::new(SpaceSize3d::new(6, 6, 6));
+        let indexer = Indexer3d::new(&size);
+        let mut gray = vec![40u8; size.total as usize];
+        gray[indexer.get(&Point3d::<i32>::new(3, 2, 4)) as usize] = 0u8;
+        gray[indexer.get(&Point3d::<i32>::new(0, 0, 0)) as usize] = 255u8;
+        let preprocessing = Preprocessing3d::new(Rc::clone(&size));
+        let filtered = preprocessing.apply(&[Filter::Median { radius: 1 }], &gray);
+        assert_eq!(filtered, vec![40u8; size.total as usize]);
+    }
+
+    #[test]
+    fn diffusion_3d() {
+        // a step along z stays sharp, while the single bright voxel spreads out
+        let size = Rc::new(SpaceSize3d::new(6, 5, 8));
+        let indexer = Indexer3d::new(&size);
+        let mut gray = vec![0u8; size.total as usize];
+        for k in 0..8 {
+            for j in 0..5 {
+                for i in 0..6 {
+                    let v = if k < 4 { 50u8 } else { 150u8 };
+                    gray[indexer.get(&Point3d::<i32>::new(i, j, k)) as usize] = v;
+                }
+            }
+        }
+        let spike = indexer.get(&Point3d::<i32>::new(2, 2, 1)) as usize;
+        gray[spike] = 90u8;
+        let preprocessing = Preprocessing3d::new(Rc::clone(&size));
+        let filters =
+            parse_pipeline("perona-malik:10,0.15,30\ncurvature-diffusion:10,0.15,30", 3).unwrap();
+        let filtered = preprocessing.apply(&filters, &gray);
+        assert!(filtered[spike] < 60u8);
+        let column: Vec<i32> = (0..8)
+            .map(|k| filtered[indexer.get(&Point3d::<i32>::new(4, 1, k)) as usize] as i32)
+            .collect();
+        assert!(column[4] - column[3] > 90);
+        assert_eq!(column[0], 50);
+        assert_eq!(column[7], 150);
+    }
+
+    fn make_noisy_step(size: &SpaceSize2d) -> Vec<u8> {
+        let mut gray = make_step(size);
+        for (i, v) in gray.iter_mut().enumerate() {
+            *v = (*v as usize + (i * 7919) % 11 - 5) as u8;
+        }
+        gray
+    }
+
+    fn deviation(gray: &[u8], clean: &[u8]) -> f64 {
+        let sum: f64 = gray
+            .iter()
+            .zip(clean)
+            .map(|(a, b)| (*a as f64 - *b as f64).powi(2))
+            .sum();
+        (sum / gray.len() as f64).sqrt()
+    }
+
+    fn check_edge_preserving(filter: Filter) {
+        let size = Rc::new(SpaceSize2d::new(20, 20));
+        let indexer = Indexer2d::new(&size);
+        let clean = make_step(&size);
+        let noisy = make_noisy_step(&size);
+        let preprocessing = Preprocessing2d::new(Rc::clone(&size));
+        let filtered = preprocessing.apply(&[filter], &noisy);
+
+        // the noise is reduced
+        assert!(deviation(&filtered, &clean) < 0.5 * deviation(&noisy, &clean));
+        // while the step stays sharp
+        for j in 0..size.height {
+            let left = filtered[indexer.get(&Point2d::<i32>::new(9, j)) as usize];
+            let right = filtered[indexer.get(&Point2d::<i32>::new(10, j)) as usize];
+            assert!((right as i32) - (left as i32) > 80);
+        }
+    }
+
+    #[test]
+    fn perona_malik() {
+        check_edge_preserving(Filter::PeronaMalik {
+            iterations: 20,
+            time_step: 0.2,
+            conductance: 10.0,
+        });
+    }
+
+    #[test]
+    fn curvature_diffusion() {
+        check_edge_preserving(Filter::CurvatureDiffusion {
+            iterations: 20,
+            time_step: 0.1,
+            conductance: 10.0,
+        });
+    }
+
+    #[test]
+    fn gaussian_blurs_step() {
+        let size = Rc::new(SpaceSize2d::new(20, 20));
+        let indexer = Indexer2d::new(&size);
+        let preprocessing = Preprocessing2d::new(Rc::clone(&size));
+        let filtered = preprocessing.apply(&[Filter::Gaussian { sigma: 2.0 }], &make_step(&size));
+        let left = filtered[indexer.get(&Point2d::<i32>::new(9, 10)) as usize];
+        let right = filtered[indexer.get(&Point2d::<i32>::new(10, 10)) as usize];
+        assert!((right as i32) - (left as i32) < 40);
+    }
+
+    #[test]
+    fn window_and_rescale() {
+        let size = Rc::new(SpaceSize2d::new(5, 1));
+        let preprocessing = Preprocessing2d::new(Rc::clone(&size));
+        let gray = vec![10u8, 30, 115, 200, 250];
+        let window = Filter::Window {
+            lower: 30.0,
+            upper: 200.0,
+        };
+        assert_eq!(
+            preprocessing.apply(&[window], &gray),
+            vec![0u8, 0, 128, 255, 255]
+        );
+        assert_eq!(
+            preprocessing.apply(&[Filter::Rescale], &gray),
+            vec![0u8, 21, 112, 202, 255]
+        );
+        // a constant image is kept by the rescale
+        let gray = vec![9u8; 5];
+        assert_eq!(preprocessing.apply(&[Filter::Rescale], &gray), gray);
+    }
+}
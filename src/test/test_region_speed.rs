@@ -0,0 +1,302 @@
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::initial_front::{InitialFront2d, InitialFront3d};
+use crate::core::level_set_method::{LevelSetMethod2d, LevelSetMethod3d};
+use crate::core::parameters::{Engine, Parameters, SpeedMode};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::region_speed::{ChanVeseSpeed, LocalBinaryFittingSpeed, LocalFittingImage};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chan_vese_speed() {
+        let gray = Rc::new(RefCell::new(vec![10u8, 20, 30, 100, 200]));
+        let phi = vec![-1.0, -1.0, -1.0, 1.0, 0.0];
+        let mut speed = ChanVeseSpeed::new(Rc::clone(&gray), 1.0, 1.0);
+        speed.update(&phi);
+        assert_eq!(speed.get_inside_mean(), 20.0);
+        assert_eq!(speed.get_outside_mean(), 150.0);
+
+        let answer = (130.0 * 130.0 - 0.0) / (255.0 * 255.0);
+        assert_eq!(speed.get_value(1), answer);
+        let answer = (50.0 * 50.0 - 180.0 * 180.0) / (255.0 * 255.0);
+        assert_eq!(speed.get_value(4), answer);
+    }
+
+    #[test]
+    fn chan_vese_speed_at_sign_changes() {
+        let gray = Rc::new(RefCell::new(vec![10u8, 20, 30, 100, 200]));
+        let mut speed = ChanVeseSpeed::new(Rc::clone(&gray), 1.0, 1.0);
+        // the first update counts the whole image
+        speed.update_at(&[-1.0, -1.0, -1.0, 1.0, 0.0], &[]);
+        assert_eq!(speed.get_inside_mean(), 20.0);
+        assert_eq!(speed.get_outside_mean(), 150.0);
+
+        // the points which keep their signs are left as they are
+        let phi = vec![-1.0, -0.5, 1.0, -1.0, 0.0];
+        speed.update_at(&phi, &[1, 2, 3]);
+        let mut whole = ChanVeseSpeed::new(gray, 1.0, 1.0);
+        whole.update(&phi);
+        assert_eq!(speed.get_inside_mean(), whole.get_inside_mean());
+        assert_eq!(speed.get_outside_mean(), whole.get_outside_mean());
+    }
+
+    fn make_params() -> Parameters {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.gain = 0.1;
+        params.time_step = 0.5;
+        params.speed_mode = SpeedMode::ChanVese;
+        params
+    }
+
+    #[test]
+    fn chan_vese_2d() {
+        // a bright square without sharp edges around it
+        let size = Rc::new(SpaceSize2d::new(30, 30));
+        let indexer = Indexer2d::new(&size);
+        let mut gray = vec![20u8; size.total as usize];
+        for j in 10..20 {
+            for i in 10..20 {
+                gray[indexer.get(&Point2d::<i32>::new(i, j)) as usize] = 200u8;
+            }
+        }
+        let gray = Rc::new(RefCell::new(gray));
+
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(3, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(26, 26);
+
+        // the sparse field updates the means from the sign changes only
+        for engine in [Engine::NarrowBand, Engine::SparseField] {
+            let mut params = make_params();
+            params.engine = engine;
+            let mut lsm =
+                LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+            lsm.initialize(&initial_front);
+            let result = lsm.run(300);
+
+            let mut wrong = 0;
+            for j in 0..size.height {
+                for i in 0..size.width {
+                    let index = indexer.get(&Point2d::<i32>::new(i, j)) as usize;
+                    let is_object = gray.borrow()[index] == 200u8;
+                    if is_object != (result.phi[index] < 0.0) {
+                        wrong += 1;
+                    }
+                }
+            }
+            assert_eq!(wrong, 0);
+        }
+    }
+
+    #[test]
+    fn chan_vese_3d() {
+        let size = Rc::new(SpaceSize3d::new(20, 20, 20));
+        let indexer = Indexer3d::new(&size);
+        let mut gray = vec![20u8; size.total as usize];
+        for k in 7..13 {
+            for j in 7..13 {
+                for i in 7..13 {
+                    gray[indexer.get(&Point3d::<i32>::new(i, j, k)) as usize] = 200u8;
+                }
+            }
+        }
+        let gray = Rc::new(RefCell::new(gray));
+
+        let mut initial_front = InitialFront3d::new();
+        initial_front.vertices[0] = Point3d::<i32>::new(4, 4, 4);
+        initial_front.vertices[1] = Point3d::<i32>::new(15, 15, 15);
+
+        let mut lsm =
+            LevelSetMethod3d::new(make_params(), Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        let result = lsm.run(100);
+
+        let mut wrong = 0;
+        for (index, v) in result.phi.iter().enumerate() {
+            let is_object = gray.borrow()[index] == 200u8;
+            if is_object != (*v < 0.0) {
+                wrong += 1;
+            }
+        }
+        assert_eq!(wrong, 0);
+    }
+
+    #[test]
+    fn local_binary_fitting_speed() {
+        // the inside on the left, and the outside brighter by 100 over a ramp
+        let gray: Vec<u8> = (0..8)
+            .map(|x| 10 * x + if x < 4 { 0 } else { 100 })
+            .collect();
+        let phi: Vec<f64> = (0..8).map(|x| x as f64 - 3.5).collect();
+        let image = Rc::new(LocalFittingImage::new(&gray, vec![8, 1], 0.5));
+        let mut speed = LocalBinaryFittingSpeed::new(image, 1.0, 1.0);
+        speed.update(&phi, &(0..8).collect::<Vec<_>>());
+
+        // the means follow the ramp within the window
+        assert!((speed.get_inside_mean(1) - 10.0).abs() < 0.1);
+        assert!((speed.get_outside_mean(5) - 150.0).abs() < 0.1);
+        assert!(speed.get_inside_mean(3) > speed.get_inside_mean(1) + 15.0);
+        assert!(speed.get_outside_mean(7) > speed.get_outside_mean(5) + 15.0);
+        // where the window contains both regions
+        for x in 1..8 {
+            assert_eq!(speed.get_value(x) > 0.0, x < 4);
+        }
+    }
+
+    #[test]
+    fn local_binary_fitting_speed_on_band() {
+        let size = SpaceSize2d::new(40, 40);
+        let gray = make_inhomogeneous_square(&size);
+        let phi: Vec<f64> = (0..1600)
+            .map(|i| ((i % 40 - 20) as f64).hypot((i / 40 - 20) as f64) - 10.0)
+            .collect();
+        let band: Vec<usize> = (0..1600).filter(|i| phi[*i].abs() < 1.0).collect();
+        let image = Rc::new(LocalFittingImage::new(&gray, vec![40, 40], 2.0));
+        let mut whole = LocalBinaryFittingSpeed::new(Rc::clone(&image), 1.0, 1.0);
+        whole.update(&phi, &(0..1600).collect::<Vec<_>>());
+        let mut speed = LocalBinaryFittingSpeed::new(image, 1.0, 1.0);
+        speed.update(&phi, &band);
+
+        // the values on the band are those calculated over the whole image
+        for i in band {
+            assert!((speed.get_value(i) - whole.get_value(i)).abs() < 1.0e-9);
+            assert!((speed.get_inside_mean(i) - whole.get_inside_mean(i)).abs() < 1.0e-9);
+        }
+        // and the points far from the band are left out
+        assert_eq!(speed.get_value(0), 0.0);
+
+        // the buffers written by the previous update do not leak into the next one
+        let moved: Vec<f64> = phi.iter().map(|v| v - 3.0).collect();
+        let band: Vec<usize> = (0..1600).filter(|i| moved[*i].abs() < 1.0).collect();
+        whole.update(&moved, &(0..1600).collect::<Vec<_>>());
+        speed.update(&moved, &band);
+        for i in band {
+            assert!((speed.get_value(i) - whole.get_value(i)).abs() < 1.0e-9);
+        }
+    }
+
+    #[test]
+    fn validate_lbf_sigma() {
+        let mut params = make_params();
+        params.speed_mode = SpeedMode::LocalBinaryFitting;
+        assert!(LevelSetMethod2d::validate_parameters(&params).is_ok());
+        for sigma in [0.0, -1.0] {
+            params.lbf_sigma = sigma;
+            assert!(LevelSetMethod2d::validate_parameters(&params).is_err());
+        }
+        // the sigma matters only to the local binary fitting
+        params.speed_mode = SpeedMode::ChanVese;
+        assert!(LevelSetMethod2d::validate_parameters(&params).is_ok());
+    }
+
+    fn make_inhomogeneous_square(size: &SpaceSize2d) -> Vec<u8> {
+        // the object is brighter than its surroundings by 60,
+        // while the illumination rises by 150 from left to right
+        let indexer = Indexer2d::new(size);
+        let mut gray = vec![0u8; size.total as usize];
+        for j in 0..size.height {
+            for i in 0..size.width {
+                let background = 20.0 + 150.0 * i as f64 / (size.width - 1) as f64;
+                let is_object = (8..32).contains(&i) && (12..28).contains(&j);
+                let v = background + if is_object { 60.0 } else { 0.0 };
+                gray[indexer.get(&Point2d::<i32>::new(i, j)) as usize] = v as u8;
+            }
+        }
+        gray
+    }
+
+    fn count_wrong_points(speed_mode: SpeedMode) -> usize {
+        let size = Rc::new(SpaceSize2d::new(40, 40));
+        let indexer = Indexer2d::new(&size);
+        let gray = Rc::new(RefCell::new(make_inhomogeneous_square(&size)));
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(5, 9);
+        initial_front.vertices[1] = Point2d::<i32>::new(34, 30);
+
+        let mut params = make_params();
+        params.speed_mode = speed_mode;
+        params.lbf_sigma = 3.0;
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        let result = lsm.run(300);
+
+        let mut wrong = 0;
+        for j in 0..size.height {
+            for i in 0..size.width {
+                let index = indexer.get(&Point2d::<i32>::new(i, j)) as usize;
+                let is_object = (8..32).contains(&i) && (12..28).contains(&j);
+                if is_object != (result.phi[index] < 0.0) {
+                    wrong += 1;
+                }
+            }
+        }
+        wrong
+    }
+
+    #[test]
+    fn local_binary_fitting_2d() {
+        let chan_vese = count_wrong_points(SpeedMode::ChanVese);
+        let local_binary_fitting = count_wrong_points(SpeedMode::LocalBinaryFitting);
+        // only the corners rounded by the curvature term are missed
+        assert!(local_binary_fitting < 20);
+        assert!(chan_vese > 10 * local_binary_fitting);
+    }
+
+    fn count_wrong_voxels(speed_mode: SpeedMode) -> usize {
+        // a cube brighter than its surroundings by 80 under the illumination rising along z
+        let size = Rc::new(SpaceSize3d::new(20, 20, 20));
+        let indexer = Indexer3d::new(&size);
+        let is_object = |i: i32, j: i32, k: i32| {
+            (6..14).contains(&i) && (6..14).contains(&j) && (6..14).contains(&k)
+        };
+        let mut gray = vec![0u8; size.total as usize];
+        for k in 0..20 {
+            for j in 0..20 {
+                for i in 0..20 {
+                    let v = 10 + 8 * k + if is_object(i, j, k) { 80 } else { 0 };
+                    gray[indexer.get(&Point3d::<i32>::new(i, j, k)) as usize] = v as u8;
+                }
+            }
+        }
+        let gray = Rc::new(RefCell::new(gray));
+        let mut initial_front = InitialFront3d::new();
+        initial_front.vertices[0] = Point3d::<i32>::new(3, 3, 3);
+        initial_front.vertices[1] = Point3d::<i32>::new(16, 16, 16);
+
+        let mut params = make_params();
+        params.speed_mode = speed_mode;
+        params.lbf_sigma = 2.0;
+        let mut lsm = LevelSetMethod3d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        let result = lsm.run(300);
+
+        let mut wrong = 0;
+        for k in 0..20 {
+            for j in 0..20 {
+                for i in 0..20 {
+                    let index = indexer.get(&Point3d::<i32>::new(i, j, k)) as usize;
+                    if is_object(i, j, k) != (result.phi[index] < 0.0) {
+                        wrong += 1;
+                    }
+                }
+            }
+        }
+        wrong
+    }
+
+    #[test]
+    fn local_binary_fitting_3d() {
+        let chan_vese = count_wrong_voxels(SpeedMode::ChanVese);
+        let local_binary_fitting = count_wrong_voxels(SpeedMode::LocalBinaryFitting);
+        // only the edges and the corners rounded by the curvature term are missed
+        assert!(local_binary_fitting < 100);
+        assert!(chan_vese > 5 * local_binary_fitting);
+    }
+}
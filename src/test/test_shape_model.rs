@@ -0,0 +1,151 @@
+use crate::core::point::{Point2d, Point3d};
+use crate::core::shape_model::{signed_distance_2d, ShapeModel2d, ShapeModel3d, ShapeModelMethod};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_disk(n: i32, center: (f64, f64), radius: f64) -> Vec<u8> {
+        let mut mask = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let r = ((x as f64 - center.0).powi(2) + (y as f64 - center.1).powi(2)).sqrt();
+                mask.push(if r < radius { 255 } else { 0 });
+            }
+        }
+        mask
+    }
+
+    #[test]
+    fn signed_distance() {
+        let size = SpaceSize2d::new(9, 9);
+        let mut mask = vec![0u8; 81];
+        for y in 2..7 {
+            for x in 2..7 {
+                mask[x + 9 * y] = 1;
+            }
+        }
+        let phi = signed_distance_2d(&size, &mask).unwrap();
+        assert!(phi[4 + 9 * 4] < -2.0);
+        assert!(phi[0] > 1.0);
+        for (m, v) in mask.iter().zip(&phi) {
+            assert_eq!(*m != 0, *v < 0.0);
+        }
+        assert!(signed_distance_2d(&size, &[0u8; 81]).is_err());
+    }
+
+    #[test]
+    fn identical_masks_2d() {
+        let size = SpaceSize2d::new(16, 16);
+        let mask = make_disk(16, (7.5, 7.5), 5.0);
+        let model = ShapeModel2d::build(&size, &vec![mask.clone(); 3], 4).unwrap();
+        assert!(model.get_modes().is_empty());
+        assert!(model.get_eigenvalues().is_empty());
+        let phi = signed_distance_2d(&size, &mask).unwrap();
+        for (m, v) in model.get_mean().iter().zip(&phi) {
+            assert!((m - v).abs() < 1.0e-12);
+        }
+        assert!(ShapeModel2d::build(&size, &[], 4).is_err());
+        assert!(ShapeModel2d::build(&size, &[vec![0u8; 10]], 4).is_err());
+    }
+
+    #[test]
+    fn circles_2d() {
+        let size = SpaceSize2d::new(32, 32);
+        let masks: Vec<Vec<u8>> = [5.0, 7.0, 9.0, 11.0]
+            .iter()
+            .map(|r| make_disk(32, (15.5, 15.5), *r))
+            .collect();
+        let model = ShapeModel2d::build(&size, &masks, 2).unwrap();
+
+        // varying the radius shifts the distance almost uniformly, which one mode explains
+        let eigenvalues = model.get_eigenvalues();
+        assert_eq!(eigenvalues.len(), 2);
+        assert_eq!(model.get_modes().len(), 2);
+        assert!(eigenvalues[0] > 50.0 * eigenvalues[1]);
+        let length: f64 = model.get_modes()[0].iter().map(|m| m * m).sum();
+        assert!((length - 1.0).abs() < 1.0e-9);
+
+        // the mean is about the disk of radius 8
+        let center = Point2d::<i32>::new(15, 15);
+        assert!(model.get_value(&center, &[]) < -6.0);
+        assert!(model.get_value(&Point2d::<i32>::new(25, 15), &[]) > 0.0);
+
+        // a weight on the first mode grows or shrinks the disk
+        let shift = model.get_modes()[0][15 + 32 * 15] * 20.0;
+        let v = model.get_value(&center, &[20.0]);
+        assert!((v - model.get_value(&center, &[]) - shift).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn model_file() {
+        let size = SpaceSize2d::new(12, 10);
+        let masks: Vec<Vec<u8>> = [(4.0, 3.0), (6.0, 5.0), (5.0, 4.0)]
+            .iter()
+            .map(|(x, r)| {
+                let mut mask = make_disk(12, (*x, 5.0), *r);
+                mask.truncate(120);
+                mask
+            })
+            .collect();
+        let model = ShapeModel2d::build(&size, &masks, 3).unwrap();
+        let path = std::env::temp_dir().join("levelset_by_rust_shape_model.bin");
+        model.save(&path).unwrap();
+        let loaded = ShapeModel2d::load(&path).unwrap();
+        assert_eq!(loaded.get_space_size().width, 12);
+        assert_eq!(loaded.get_space_size().height, 10);
+        assert_eq!(loaded.get_mean(), model.get_mean());
+        assert_eq!(loaded.get_eigenvalues(), model.get_eigenvalues());
+        assert_eq!(loaded.get_modes(), model.get_modes());
+        assert!(ShapeModel3d::load(&path).is_err());
+
+        let reference = loaded.to_reference_map();
+        assert_eq!(reference.extents, vec![12, 10]);
+        assert_eq!(reference.phi, model.get_mean());
+
+        std::fs::write(&path, b"LSPM\x02\x00\x00\x00").unwrap();
+        assert!(ShapeModel2d::load(&path).is_err());
+        // a zero extent, extents whose product overflows and a count overflowing the size
+        for header in [[0u32, 5, 0], [0x1_0000, 0x1_0000, 0], [1, 1, u32::MAX]] {
+            let mut bytes = b"LSPM\x02\x00\x00\x00".to_vec();
+            for v in header {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+            std::fs::write(&path, bytes).unwrap();
+            assert!(ShapeModel2d::load(&path).is_err());
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert!(ShapeModel2d::load(&path).is_err());
+    }
+
+    #[test]
+    fn spheres_3d() {
+        let n = 16;
+        let size = SpaceSize3d::new(n, n, n);
+        let masks: Vec<Vec<u8>> = [3.0, 4.0, 5.0]
+            .iter()
+            .map(|radius| {
+                let mut mask = Vec::new();
+                for z in 0..n {
+                    for y in 0..n {
+                        for x in 0..n {
+                            let r = ((x as f64 - 7.5).powi(2)
+                                + (y as f64 - 7.5).powi(2)
+                                + (z as f64 - 7.5).powi(2))
+                            .sqrt();
+                            mask.push(if r < *radius { 1 } else { 0 });
+                        }
+                    }
+                }
+                mask
+            })
+            .collect();
+        let model = ShapeModel3d::build(&size, &masks, 5).unwrap();
+        // at most one fewer than the number of samples
+        assert!(model.get_modes().len() <= 2);
+        assert!(model.get_eigenvalues()[0] > 10.0 * model.get_eigenvalues()[1]);
+        assert!(model.get_value(&Point3d::<i32>::new(7, 7, 7), &[]) < -2.0);
+        assert!(model.get_value(&Point3d::<i32>::new(0, 0, 0), &[]) > 0.0);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::parameters::Parameters;
+use crate::core::point::Point2d;
+use crate::core::point::Point3d;
+use crate::core::shape_prior::{Pose, ReferenceMap, ShapePrior2d, ShapePrior3d, ShapePriorMethod};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// signed distance to the ellipse with semi-axes a and b, approximated by scaling.
+    fn make_ellipse(
+        width: i32,
+        height: i32,
+        center: (f64, f64),
+        axes: (f64, f64),
+        angle: f64,
+    ) -> Vec<f64> {
+        let (s, c) = angle.sin_cos();
+        let mut phi = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (x as f64 - center.0, y as f64 - center.1);
+                let (u, v) = (c * dx + s * dy, -s * dx + c * dy);
+                let r = ((u / axes.0).powi(2) + (v / axes.1).powi(2)).sqrt();
+                phi.push((r - 1.0) * axes.0.min(axes.1));
+            }
+        }
+        phi
+    }
+
+    #[test]
+    fn reference_map_file() {
+        let reference = ReferenceMap {
+            extents: vec![3, 2],
+            phi: vec![-1.0, 0.5, 2.0, 3.25, -4.0, 0.0],
+        };
+        let path = std::env::temp_dir().join("levelset_by_rust_reference_map.bin");
+        reference.save(&path).unwrap();
+        let loaded = ReferenceMap::load(&path).unwrap();
+        assert_eq!(loaded.extents, reference.extents);
+        assert_eq!(loaded.phi, reference.phi);
+
+        std::fs::write(&path, b"LSDM\x02\x00\x00\x00").unwrap();
+        assert!(ReferenceMap::load(&path).is_err());
+        // a zero extent, an extent above i32::MAX and extents whose product overflows
+        for extents in [[0u32, 5], [0x8000_0000, 1], [0x1_0000, 0x1_0000]] {
+            let mut bytes = b"LSDM\x02\x00\x00\x00".to_vec();
+            for e in extents {
+                bytes.extend_from_slice(&e.to_le_bytes());
+            }
+            std::fs::write(&path, bytes).unwrap();
+            assert!(ReferenceMap::load(&path).is_err());
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert!(ReferenceMap::load(&path).is_err());
+    }
+
+    #[test]
+    fn pose() {
+        let pose = Pose::identity(2);
+        assert_eq!(pose.to_reference(&[2.0, 3.0]), vec![2.0, 3.0]);
+
+        // a quarter turn, doubled and shifted
+        let pose = Pose {
+            translation: vec![1.0, 2.0],
+            rotation: vec![0.0, -1.0, 1.0, 0.0],
+            scale: 2.0,
+        };
+        let u = pose.to_reference(&[1.0, 4.0]);
+        assert!((u[0] - 1.0).abs() < 1.0e-12);
+        assert!(u[1].abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn get_value_2d() {
+        let size = SpaceSize2d::new(8, 6);
+        let mut shape_prior = ShapePrior2d::new(&size);
+        assert!(!shape_prior.has_reference());
+        shape_prior
+            .set_reference(ReferenceMap {
+                extents: vec![4, 3],
+                phi: (0..12)
+                    .map(|i| (i % 4) as f64 + 2.0 * (i / 4) as f64)
+                    .collect(),
+            })
+            .unwrap();
+        assert!(shape_prior.has_reference());
+        assert_eq!(shape_prior.get_value(&Point2d::<i32>::new(1, 2)), 5.0);
+        // clamped outside the reference
+        assert_eq!(shape_prior.get_value(&Point2d::<i32>::new(7, 5)), 7.0);
+
+        // interpolated between the grid points
+        let mut pose = Pose::identity(2);
+        pose.translation = vec![0.5, 0.5];
+        shape_prior.set_pose(pose);
+        assert!((shape_prior.get_value(&Point2d::<i32>::new(2, 1)) - 2.5).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn invalid_reference() {
+        let mut shape_prior = ShapePrior2d::new(&SpaceSize2d::new(8, 6));
+        let reference_3d = ReferenceMap {
+            extents: vec![2, 2, 2],
+            phi: vec![-1.0; 8],
+        };
+        assert_eq!(
+            shape_prior.set_reference(reference_3d),
+            Err("the reference must be 2D".to_string())
+        );
+        let too_short = ReferenceMap {
+            extents: vec![4, 3],
+            phi: vec![-1.0; 11],
+        };
+        assert!(shape_prior.set_reference(too_short).is_err());
+        assert!(!shape_prior.has_reference());
+    }
+
+    #[test]
+    fn estimate_pose_2d() {
+        let size = SpaceSize2d::new(40, 40);
+        let mut shape_prior = ShapePrior2d::new(&size);
+        shape_prior
+            .set_reference(ReferenceMap {
+                extents: vec![24, 24],
+                phi: make_ellipse(24, 24, (12.0, 12.0), (8.0, 4.0), 0.0),
+            })
+            .unwrap();
+        let phi = make_ellipse(40, 40, (21.0, 18.0), (12.0, 6.0), 0.5);
+        shape_prior.estimate_pose(&phi);
+
+        let pose = shape_prior.get_pose();
+        assert!((pose.scale - 1.5).abs() < 0.05);
+        let mut agreements = 0;
+        let mut count = 0;
+        for y in 0..40 {
+            for x in 0..40 {
+                let v = phi[(x + 40 * y) as usize];
+                if v.abs() > 1.0 {
+                    count += 1;
+                    let prior = shape_prior.get_value(&Point2d::<i32>::new(x, y));
+                    if (prior < 0.0) == (v < 0.0) {
+                        agreements += 1;
+                    }
+                }
+            }
+        }
+        assert!(agreements as f64 > 0.98 * count as f64);
+    }
+
+    fn make_ellipsoid(n: i32, center: (f64, f64, f64), axes: (f64, f64, f64)) -> Vec<f64> {
+        let mut phi = Vec::new();
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    let r = (((x as f64 - center.0) / axes.0).powi(2)
+                        + ((y as f64 - center.1) / axes.1).powi(2)
+                        + ((z as f64 - center.2) / axes.2).powi(2))
+                    .sqrt();
+                    phi.push((r - 1.0) * axes.2);
+                }
+            }
+        }
+        phi
+    }
+
+    #[test]
+    fn estimate_pose_3d() {
+        let size = SpaceSize3d::new(24, 24, 24);
+        let mut shape_prior = ShapePrior3d::new(&size);
+        shape_prior
+            .set_reference(ReferenceMap {
+                extents: vec![16, 16, 16],
+                phi: make_ellipsoid(16, (8.0, 8.0, 8.0), (6.0, 4.0, 3.0)),
+            })
+            .unwrap();
+        let phi = make_ellipsoid(24, (13.0, 11.0, 12.0), (9.0, 6.0, 4.5));
+        shape_prior.estimate_pose(&phi);
+
+        let pose = shape_prior.get_pose();
+        assert!((pose.scale - 1.5).abs() < 0.05);
+        let center = Point3d::<i32>::new(13, 11, 12);
+        assert!(shape_prior.get_value(&center) < -3.0);
+        assert!(shape_prior.get_value(&Point3d::<i32>::new(13, 11, 18)) > 0.0);
+        assert!(shape_prior.get_value(&Point3d::<i32>::new(21, 11, 12)) < 0.0);
+    }
+
+    #[test]
+    fn level_set_method_2d() {
+        let size = Rc::new(SpaceSize2d::new(32, 32));
+        let gray = Rc::new(RefCell::new(vec![0u8; 32 * 32]));
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(13, 13);
+        initial_front.vertices[1] = Point2d::<i32>::new(18, 18);
+        let reference = ReferenceMap {
+            extents: vec![32, 32],
+            phi: make_ellipse(32, 32, (15.5, 15.5), (7.0, 7.0), 0.0),
+        };
+
+        let mut areas = Vec::new();
+        for weight in [0.0, 0.5, 2.0] {
+            let mut params = Parameters::new();
+            params.wband = 3;
+            params.wreset = 1;
+            params.constant_speed = 1.0;
+            params.gain = 0.1;
+            params.time_step = 0.5;
+            params.shape_prior_weight = weight;
+            let mut lsm =
+                LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+            lsm.set_shape_prior(reference.clone()).unwrap();
+            lsm.initialize(&initial_front);
+            let result = lsm.run(40);
+            areas.push(result.phi.iter().filter(|v| **v < 0.0).count());
+        }
+        // the front leaks without the prior, and with it stops where the distance to the disk
+        // of radius 7 equals constant_speed / shape_prior_weight
+        assert!(areas[0] > 3 * areas[1]);
+        let disk = |r: f64| std::f64::consts::PI * r * r;
+        assert!((areas[1] as f64 - disk(9.0)).abs() < 20.0);
+        assert!((areas[2] as f64 - disk(7.5)).abs() < 20.0);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::core::space_size::SpaceSizeMethod;
 use crate::core::types;
 #[cfg(test)]
 mod tests {
@@ -9,6 +10,8 @@ mod tests {
         assert_eq!(a.width, 1);
         assert_eq!(a.height, 2);
         assert_eq!(a.total, 2);
+        assert_eq!(a.get_dimension(), 2);
+        assert_eq!(a.get_extents(), vec![1, 2]);
     }
 
     #[test]
@@ -18,5 +21,7 @@ mod tests {
         assert_eq!(a.height, 2);
         assert_eq!(a.depth, 3);
         assert_eq!(a.total, 6);
+        assert_eq!(a.get_dimension(), 3);
+        assert_eq!(a.get_extents(), vec![1, 2, 3]);
     }
 }
@@ -0,0 +1,182 @@
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::parameters::{Engine, Parameters, SpatialScheme, TimeIntegrator};
+use crate::core::point::Point2d;
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use crate::core::sparse_field::{SparseField2d, SparseField3d, SparseFieldMethod};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_layers<T, P>(sparse_field: &T, phi: &[f64])
+    where
+        T: SparseFieldMethod<SpaceSize2d, P>,
+    {
+        let mut count = vec![0; phi.len()];
+        for k in -2..=2 {
+            for i in sparse_field.get_layer(k) {
+                assert!((phi[*i] - k as f64).abs() <= 0.5 + 1.0e-9);
+                count[*i] += 1;
+            }
+        }
+        // the layers are disjoint
+        assert!(count.iter().all(|c| *c <= 1));
+    }
+
+    #[test]
+    fn circle_2d() {
+        let n = 24;
+        let size = SpaceSize2d::new(n, n);
+        let c = n as f64 / 2.0;
+        let mut phi = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let r = ((x as f64 - c).powi(2) + (y as f64 - c).powi(2)).sqrt();
+                phi.push(r - 6.3);
+            }
+        }
+        let band: Vec<usize> = (0..phi.len()).filter(|i| phi[*i].abs() < 3.0).collect();
+        let mut sparse_field = SparseField2d::new(&size);
+        sparse_field.initialize(&mut phi, &band);
+        check_layers(&sparse_field, &phi);
+
+        let active_layer = sparse_field.get_active_layer();
+        assert_eq!(active_layer.len(), sparse_field.get_layer(0).len());
+        for p in active_layer.iter() {
+            assert!(phi[(p.x + n * p.y) as usize].abs() <= 0.5);
+        }
+        for k in [-2, -1, 1, 2] {
+            assert!(!sparse_field.get_layer(k).is_empty());
+        }
+    }
+
+    #[test]
+    fn update_2d() {
+        let size = SpaceSize2d::new(12, 5);
+        let mut phi: Vec<f64> = (0..60).map(|i| (i % 12) as f64 - 3.3).collect();
+        let band: Vec<usize> = (0..60).filter(|i| phi[*i].abs() < 3.0).collect();
+        let mut sparse_field = SparseField2d::new(&size);
+        sparse_field.initialize(&mut phi, &band);
+        assert!(sparse_field.get_active_layer().iter().all(|p| p.x == 3));
+
+        // the planar front moves by 0.4 at every update
+        for _ in 0..5 {
+            let changes = vec![0.4; sparse_field.get_layer(0).len()];
+            sparse_field.update(&mut phi, &changes);
+            check_layers(&sparse_field, &phi);
+        }
+        let active_layer = sparse_field.get_active_layer();
+        assert_eq!(active_layer.len(), 5);
+        for p in active_layer.iter() {
+            assert_eq!(p.x, 5);
+            assert!((phi[(p.x + 12 * p.y) as usize] + 0.3).abs() < 1.0e-9);
+        }
+        for i in sparse_field.get_layer(1) {
+            assert!((phi[*i] - 0.7).abs() < 1.0e-9);
+        }
+        for i in sparse_field.get_layer(-2) {
+            assert!((phi[*i] + 2.3).abs() < 1.0e-9);
+        }
+    }
+
+    #[test]
+    fn sphere_3d() {
+        let n = 16;
+        let size = SpaceSize3d::new(n, n, n);
+        let c = n as f64 / 2.0;
+        let mut phi = Vec::new();
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    let r =
+                        ((x as f64 - c).powi(2) + (y as f64 - c).powi(2) + (z as f64 - c).powi(2))
+                            .sqrt();
+                    phi.push(r - 4.6);
+                }
+            }
+        }
+        let band: Vec<usize> = (0..phi.len()).filter(|i| phi[*i].abs() < 3.0).collect();
+        let mut sparse_field = SparseField3d::new(&size);
+        sparse_field.initialize(&mut phi, &band);
+        let active_layer = sparse_field.get_active_layer();
+        assert!(!active_layer.is_empty());
+        for p in active_layer.iter() {
+            assert!(phi[(p.x + n * p.y + n * n * p.z) as usize].abs() <= 0.5);
+        }
+
+        let changes = vec![-0.3; active_layer.len()];
+        sparse_field.update(&mut phi, &changes);
+        for k in -2..=2 {
+            for i in sparse_field.get_layer(k) {
+                assert!((phi[*i] - k as f64).abs() <= 0.5 + 1.0e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn level_set_method_2d() {
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 3);
+        initial_front.vertices[1] = Point2d::<i32>::new(8, 7);
+        let size = Rc::new(SpaceSize2d::new(11, 11));
+        let gray = Rc::new(RefCell::new(vec![0u8; 121]));
+        let area = |phi: &Vec<f64>| phi.iter().filter(|v| **v < 0.0).count();
+
+        let mut areas = Vec::new();
+        for engine in [Engine::NarrowBand, Engine::SparseField] {
+            let mut params = Parameters::new();
+            params.wband = 3;
+            params.wreset = 1;
+            params.constant_speed = -1.0;
+            params.gain = 0.1;
+            params.time_step = 0.5;
+            params.engine = engine;
+            let mut lsm =
+                LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+            lsm.initialize(&initial_front);
+            let initial_area = area(&lsm.get_phi().borrow());
+
+            let result = lsm.run(6);
+            assert!(result.phi.iter().all(|v| v.is_finite()));
+            assert!(area(&result.phi) < initial_area);
+            assert_eq!(result.time_steps.len(), result.iterations);
+            areas.push(area(&result.phi));
+
+            if engine == Engine::SparseField {
+                // the front is the active layer
+                let front = lsm.get_front();
+                assert_eq!(
+                    front.borrow().len(),
+                    lsm.get_sparse_field().unwrap().get_layer(0).len()
+                );
+                assert!(lsm.get_narrow_bands().is_empty());
+            } else {
+                // the layers are not built for the other engines
+                assert!(lsm.get_sparse_field().is_none());
+            }
+        }
+        assert!(areas[1].abs_diff(areas[0]) <= 6);
+    }
+
+    #[test]
+    fn validate_parameters() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.engine = Engine::SparseField;
+        assert!(LevelSetMethod2d::validate_parameters(&params).is_ok());
+
+        // the settings which the sparse field engine cannot honour are rejected
+        let mut weno = params.clone();
+        weno.spatial_scheme = SpatialScheme::Weno5;
+        assert!(LevelSetMethod2d::validate_parameters(&weno).is_err());
+        let mut rk3 = params.clone();
+        rk3.time_integrator = TimeIntegrator::Rk3;
+        assert!(LevelSetMethod2d::validate_parameters(&rk3).is_err());
+        let mut adaptive = params.clone();
+        adaptive.adaptive_time_step = true;
+        assert!(LevelSetMethod2d::validate_parameters(&adaptive).is_err());
+    }
+}
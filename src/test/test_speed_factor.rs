@@ -1,7 +1,8 @@
 use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::parameters::{EdgeFunction, Parameters};
 use crate::core::point::{Point2d, Point3d, PointMethod};
 use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
-use crate::core::speed_factor::{SpeedFactor2d, SpeedFactor3d, SpeedFactorMethod};
+use crate::core::speed_factor::{EdgeStopping, SpeedFactor2d, SpeedFactor3d, SpeedFactorMethod};
 use std::cell::RefCell;
 use std::rc::Rc;
 #[cfg(test)]
@@ -38,4 +39,123 @@ mod tests {
         let r = factor.get_value(&p) as f32;
         assert_eq!(answer, r);
     }
+
+    #[test]
+    fn gradient_2d() {
+        let size = Rc::new(SpaceSize2d::new(5, 5));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let gray: Vec<u8> = (0..25).map(|i| ((i * i) % 251) as u8).collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut factor = SpeedFactor2d::new(Rc::clone(&indexer), Rc::clone(&gray));
+        factor.calculate_all(&size);
+
+        let f = |x: i32, y: i32| factor.get_value(&Point2d::<i32>::new(x, y));
+        let g = factor.get_gradient(&Point2d::<i32>::new(2, 2));
+        assert_eq!(g[0], 0.5 * (f(3, 2) - f(1, 2)));
+        assert_eq!(g[1], 0.5 * (f(2, 3) - f(2, 1)));
+
+        // next to the edge
+        let g = factor.get_gradient(&Point2d::<i32>::new(1, 2));
+        assert_eq!(g, &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn gradient_3d() {
+        let size = Rc::new(SpaceSize3d::new(5, 5, 5));
+        let indexer = Rc::new(Indexer3d::new(&size));
+        let gray: Vec<u8> = (0..125).map(|i| ((i * i) % 251) as u8).collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut factor = SpeedFactor3d::new(Rc::clone(&indexer), Rc::clone(&gray));
+        factor.calculate_all(&size);
+
+        let f = |x: i32, y: i32, z: i32| factor.get_value(&Point3d::<i32>::new(x, y, z));
+        let g = factor.get_gradient(&Point3d::<i32>::new(2, 2, 2));
+        assert_eq!(g[0], 0.5 * (f(3, 2, 2) - f(1, 2, 2)));
+        assert_eq!(g[1], 0.5 * (f(2, 3, 2) - f(2, 1, 2)));
+        assert_eq!(g[2], 0.5 * (f(2, 2, 3) - f(2, 2, 1)));
+    }
+
+    #[test]
+    fn edge_functions() {
+        let mut edge_stopping = EdgeStopping::from_parameters(&Parameters::new());
+        assert_eq!(edge_stopping.get_value(3.0), 1.0 / (1.0 + 3.0));
+
+        edge_stopping.k = 2.0;
+        edge_stopping.power = 2.0;
+        assert_eq!(edge_stopping.get_value(3.0), 1.0 / (1.0 + 2.25));
+
+        edge_stopping.function = EdgeFunction::Exponential;
+        assert_eq!(edge_stopping.get_value(3.0), (-2.25f64).exp());
+
+        // falls from 1 to 0 around beta for a negative alpha
+        edge_stopping.function = EdgeFunction::Sigmoid;
+        edge_stopping.alpha = -2.0;
+        edge_stopping.beta = 10.0;
+        assert_eq!(edge_stopping.get_value(10.0), 0.5);
+        assert!(edge_stopping.get_value(0.0) > 0.99);
+        assert!(edge_stopping.get_value(20.0) < 0.01);
+    }
+
+    #[test]
+    fn smoothing_2d() {
+        let size = Rc::new(SpaceSize2d::new(20, 20));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let mut edge_stopping = EdgeStopping::from_parameters(&Parameters::new());
+        edge_stopping.sigma = 1.5;
+
+        // the edges of the space are not taken as the edges of a constant image
+        let gray = Rc::new(RefCell::new(vec![100u8; 400]));
+        let mut factor = SpeedFactor2d::new(Rc::clone(&indexer), Rc::clone(&gray));
+        factor.set_edge_stopping(edge_stopping);
+        factor.calculate_all(&size);
+        for j in 1..19 {
+            for i in 1..19 {
+                let v = factor.get_value(&Point2d::<i32>::new(i, j));
+                assert!((v - 1.0).abs() < 1.0e-9);
+            }
+        }
+
+        // the noise is smoothed out
+        let gray: Vec<u8> = (0..400).map(|i| 90 + ((i * 7919) % 23) as u8).collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut raw = SpeedFactor2d::new(Rc::clone(&indexer), Rc::clone(&gray));
+        raw.calculate_all(&size);
+        let mut smoothed = SpeedFactor2d::new(Rc::clone(&indexer), Rc::clone(&gray));
+        smoothed.set_edge_stopping(edge_stopping);
+        smoothed.calculate_all(&size);
+        let mean = |factor: &SpeedFactor2d| {
+            let points: Vec<Point2d<i32>> = (3..17)
+                .flat_map(|j| (3..17).map(move |i| Point2d::<i32>::new(i, j)))
+                .collect();
+            points.iter().map(|p| factor.get_value(p)).sum::<f64>() / points.len() as f64
+        };
+        assert!(mean(&raw) < 0.3);
+        assert!(mean(&smoothed) > 0.7);
+    }
+
+    #[test]
+    fn smoothing_3d() {
+        // a step along x is still found after the smoothing
+        let size = Rc::new(SpaceSize3d::new(12, 12, 12));
+        let indexer = Rc::new(Indexer3d::new(&size));
+        let gray: Vec<u8> = (0..1728)
+            .map(|i| if i % 12 < 6 { 20 } else { 200 })
+            .collect();
+        let gray = Rc::new(RefCell::new(gray));
+        let mut edge_stopping = EdgeStopping::from_parameters(&Parameters::new());
+        edge_stopping.function = EdgeFunction::Exponential;
+        edge_stopping.k = 20.0;
+        edge_stopping.sigma = 1.0;
+        let mut factor = SpeedFactor3d::new(Rc::clone(&indexer), Rc::clone(&gray));
+        factor.set_edge_stopping(edge_stopping);
+        factor.calculate_all(&size);
+
+        let f = |x: i32| factor.get_value(&Point3d::<i32>::new(x, 6, 6));
+        assert!(f(5) < 0.01);
+        assert!(f(6) < 0.01);
+        assert!(f(1) > 0.99);
+        assert!(f(10) > 0.99);
+        // the factors are symmetric about the step
+        assert!((f(3) - f(8)).abs() < 1.0e-9);
+    }
 }
@@ -0,0 +1,158 @@
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::observer::IterationState;
+use crate::core::parameters::Parameters;
+use crate::core::point::Point2d;
+use crate::core::space_size::SpaceSize2d;
+use crate::core::status::Status;
+use crate::core::stopping_condition::StoppingCondition;
+use crate::core::stopping_criterion::{
+    AllOf, AnyOf, AreaChange, MaxIterations, Oscillation, StoppingCriterion, WallClockBudget,
+    ZeroSpeedFraction,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_state<'a>(
+        iteration: usize,
+        phi: &'a [f64],
+        front: &'a [Point2d<i32>],
+        total_speed: f64,
+        zero_count: i32,
+    ) -> IterationState<'a, Point2d<i32>> {
+        IterationState {
+            iteration,
+            phi,
+            front,
+            statuses: &[],
+            total_speed,
+            zero_count,
+        }
+    }
+
+    #[test]
+    fn stopping_condition() {
+        let mut criterion = StoppingCondition::new();
+        let s = make_state(0, &[], &[], 1.0, 0);
+        assert!(!StoppingCriterion::<Point2d<i32>>::check(
+            &mut criterion,
+            &s
+        ));
+        assert!(!StoppingCriterion::<Point2d<i32>>::check(
+            &mut criterion,
+            &s
+        ));
+        assert!(StoppingCriterion::<Point2d<i32>>::check(&mut criterion, &s));
+    }
+
+    #[test]
+    fn max_iterations() {
+        let mut criterion = MaxIterations::new(3);
+        assert!(!criterion.check(&make_state(2, &[], &[], 0.0, 0)));
+        assert!(criterion.check(&make_state(3, &[], &[], 0.0, 0)));
+    }
+
+    #[test]
+    fn wall_clock_budget() {
+        let mut criterion = WallClockBudget::new(Duration::from_secs(3600));
+        assert!(!criterion.check(&make_state(0, &[], &[], 0.0, 0)));
+        let mut criterion = WallClockBudget::new(Duration::from_secs(0));
+        assert!(criterion.check(&make_state(0, &[], &[], 0.0, 0)));
+    }
+
+    #[test]
+    fn area_change() {
+        let mut criterion = AreaChange::new(0.1, 2);
+        let a = [-1.0; 10];
+        let b = [-1.0, -1.0, -1.0, -1.0, -1.0, -1.0, -1.0, -1.0, -1.0, 1.0];
+        let c = [-1.0, -1.0, -1.0, -1.0, -1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+        // the window is not filled yet
+        assert!(!criterion.check(&make_state(0, &a, &[], 0.0, 0)));
+        assert!(!criterion.check(&make_state(1, &a, &[], 0.0, 0)));
+        // 10 -> 9: 10%
+        assert!(criterion.check(&make_state(2, &b, &[], 0.0, 0)));
+        // 10 -> 5: 50%
+        assert!(!criterion.check(&make_state(3, &c, &[], 0.0, 0)));
+    }
+
+    #[test]
+    fn zero_speed_fraction() {
+        let mut criterion = ZeroSpeedFraction::new(0.5);
+        let front = [Point2d::<i32>::new(0, 0); 4];
+        assert!(!criterion.check(&make_state(0, &[], &front, 0.0, 1)));
+        assert!(criterion.check(&make_state(0, &[], &front, 0.0, 2)));
+        assert!(criterion.check(&make_state(0, &[], &[], 0.0, 0)));
+    }
+
+    #[test]
+    fn oscillation() {
+        let mut criterion = Oscillation::new(1.0e-08, 2, 3);
+        let speeds = [5.0, 4.0, 1.0, 2.0, 1.0, 2.0, 1.0];
+        let flags: Vec<bool> = speeds
+            .iter()
+            .map(|s| criterion.check(&make_state(0, &[], &[], *s, 0)))
+            .collect();
+        assert_eq!(flags, vec![false, false, false, false, false, true, true]);
+
+        // period 3
+        let mut criterion = Oscillation::new(1.0e-08, 3, 3);
+        let speeds = [1.0, 2.0, 3.0, 1.0, 2.0, 3.0];
+        let flags: Vec<bool> = speeds
+            .iter()
+            .map(|s| criterion.check(&make_state(0, &[], &[], *s, 0)))
+            .collect();
+        assert_eq!(flags, vec![false, false, false, false, false, true]);
+
+        // monotonically decreasing speeds do not oscillate
+        let mut criterion = Oscillation::new(1.0e-08, 2, 3);
+        for s in [6.0, 5.0, 4.0, 3.0, 2.0, 1.0] {
+            assert!(!criterion.check(&make_state(0, &[], &[], s, 0)));
+        }
+    }
+
+    #[test]
+    fn any_of_and_all_of() {
+        let mut any = AnyOf::<Point2d<i32>>::new(vec![
+            Box::new(MaxIterations::new(5)),
+            Box::new(MaxIterations::new(3)),
+        ]);
+        assert!(!any.check(&make_state(2, &[], &[], 0.0, 0)));
+        assert!(any.check(&make_state(3, &[], &[], 0.0, 0)));
+
+        let mut all = AllOf::<Point2d<i32>>::new(vec![
+            Box::new(MaxIterations::new(5)),
+            Box::new(MaxIterations::new(3)),
+        ]);
+        assert!(!all.check(&make_state(3, &[], &[], 0.0, 0)));
+        assert!(all.check(&make_state(5, &[], &[], 0.0, 0)));
+    }
+
+    #[test]
+    fn level_set_method_with_criterion() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.constant_speed = -1.0;
+        params.gain = 0.1;
+        params.time_step = 0.5;
+
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(5, 5);
+        initial_front.vertices[1] = Point2d::<i32>::new(15, 15);
+        let size = Rc::new(SpaceSize2d::new(21, 21));
+        let gray = Rc::new(RefCell::new(vec![1u8; size.total as usize]));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.set_stopping_criterion(Box::new(AnyOf::new(vec![
+            Box::new(StoppingCondition::new()),
+            Box::new(MaxIterations::new(2)),
+        ])));
+        lsm.initialize(&initial_front);
+        let result = lsm.run(100);
+        assert_eq!(result.iterations, 2);
+    }
+}
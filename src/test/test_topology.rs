@@ -0,0 +1,124 @@
+use crate::core::indexer::{Indexer2d, IndexerMethod};
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::parameters::{Engine, Parameters};
+use crate::core::point::Point2d;
+use crate::core::space_size::SpaceSize2d;
+use crate::core::topology::{is_simple_2d, is_simple_3d, Topology2d, TopologyMethod};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_neighborhood_2d(inside: &[usize]) -> [bool; 9] {
+        let mut n = [false; 9];
+        for k in inside {
+            n[*k] = true;
+        }
+        n
+    }
+
+    #[test]
+    fn simple_point_2d() {
+        // a new component
+        assert!(!is_simple_2d(&make_neighborhood_2d(&[])));
+        // a new hole
+        assert!(!is_simple_2d(&make_neighborhood_2d(&[
+            0, 1, 2, 3, 5, 6, 7, 8
+        ])));
+        // on a straight boundary
+        assert!(is_simple_2d(&make_neighborhood_2d(&[0, 1, 2])));
+        assert!(is_simple_2d(&make_neighborhood_2d(&[0, 1, 2, 3, 5])));
+        // an end of a line
+        assert!(is_simple_2d(&make_neighborhood_2d(&[3])));
+        // merges two components
+        assert!(!is_simple_2d(&make_neighborhood_2d(&[0, 3, 6, 2, 5, 8])));
+        // diagonal points are 8-connected only through the center
+        assert!(!is_simple_2d(&make_neighborhood_2d(&[0, 8])));
+        // a corner point whose outside is 4-connected around it
+        assert!(is_simple_2d(&make_neighborhood_2d(&[0])));
+    }
+
+    #[test]
+    fn simple_point_3d() {
+        assert!(!is_simple_3d(&[false; 27]));
+        assert!(!is_simple_3d(&[true; 27]));
+
+        // the half space z < 0
+        let mut n = [false; 27];
+        for v in n.iter_mut().take(9) {
+            *v = true;
+        }
+        assert!(is_simple_3d(&n));
+
+        // the planes z < 0 and z > 0 would be connected
+        for v in n.iter_mut().skip(18) {
+            *v = true;
+        }
+        assert!(!is_simple_3d(&n));
+    }
+
+    #[test]
+    fn topology_2d() {
+        let size = SpaceSize2d::new(5, 5);
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let phi = Rc::new(RefCell::new(vec![1.0; 25]));
+        let topology = Topology2d::new(Rc::clone(&phi), Rc::clone(&indexer));
+        let p = Point2d::<i32>::new(2, 2);
+        assert!(!topology.is_simple(&p));
+
+        // a vertical line x = 1
+        for y in 0..5 {
+            phi.borrow_mut()[indexer.get(&Point2d::<i32>::new(1, y)) as usize] = -1.0;
+        }
+        assert!(topology.is_simple(&p));
+        assert!(!topology.is_simple(&Point2d::<i32>::new(1, 2)));
+
+        // and x = 3, which the center would join
+        for y in 0..5 {
+            phi.borrow_mut()[indexer.get(&Point2d::<i32>::new(3, y)) as usize] = -1.0;
+        }
+        assert!(!topology.is_simple(&p));
+    }
+
+    fn run_shrinking_square(engine: Engine, topology_preservation: bool) -> (usize, usize) {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.constant_speed = -1.0;
+        params.time_step = 0.5;
+        params.engine = engine;
+        params.topology_preservation = topology_preservation;
+        let size = Rc::new(SpaceSize2d::new(14, 14));
+        let gray = Rc::new(RefCell::new(vec![0u8; 196]));
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(4, 4);
+        initial_front.vertices[1] = Point2d::<i32>::new(9, 9);
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+        let result = lsm.run(30);
+        if topology_preservation {
+            assert_eq!(result.vetoes.len(), result.iterations);
+        } else {
+            assert!(result.vetoes.is_empty());
+        }
+        let area = result.phi.iter().filter(|v| **v < 0.0).count();
+        (area, result.vetoes.iter().sum())
+    }
+
+    #[test]
+    fn shrinking_square_2d() {
+        for engine in [Engine::NarrowBand, Engine::SparseField] {
+            // the square vanishes without the topology preservation
+            let (area, _) = run_shrinking_square(engine, false);
+            assert_eq!(area, 0);
+
+            // the last component survives
+            let (area, vetoes) = run_shrinking_square(engine, true);
+            assert!(area > 0);
+            assert!(vetoes > 0);
+        }
+    }
+}
@@ -18,7 +18,7 @@ mod tests {
         let space_size = SpaceSize2d::new(1, 2);
         let indexer = Rc::new(Indexer2d::new(&space_size));
         let phi = Rc::new(RefCell::new(vec![0.0, 1.0, 2.0, 3.0, 4.0]));
-        let mut scheme = UpwindScheme2d::new(Rc::clone(&indexer), Rc::clone(&phi));
+        let mut scheme = UpwindScheme2d::new(&space_size, Rc::clone(&indexer), Rc::clone(&phi));
         scheme.position.set_position(&p, Rc::clone(&indexer));
         let r = &scheme.position;
         assert_eq!(r.left, 2);
@@ -42,7 +42,7 @@ mod tests {
             0.0, 3.0, 0.0, 4.0, 2.0, 6.0, 0.0, 5.0, 0.0,
         ]));
         let indexer = Rc::new(Indexer2d::new(&size));
-        let mut scheme = UpwindScheme2d::new(Rc::clone(&indexer), Rc::clone(&phi));
+        let mut scheme = UpwindScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
         let p = Point2d::<i32>::new(1, 1);
         scheme.position.set_position(&p, Rc::clone(&indexer));
         let r = &scheme.position;
@@ -60,7 +60,7 @@ mod tests {
             0.0, 3.0, 0.0, 4.0, 2.0, 6.0, 0.0, 5.0, 0.0,
         ]));
         let indexer = Rc::new(Indexer2d::new(&size));
-        let mut scheme = UpwindScheme2d::new(Rc::clone(&indexer), Rc::clone(&phi));
+        let mut scheme = UpwindScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
 
         let p = Point2d::<i32>::new(1, 1);
 
@@ -81,7 +81,7 @@ mod tests {
         ]));
 
         let indexer = Rc::new(Indexer2d::new(&size));
-        let mut scheme = UpwindScheme2d::new(Rc::clone(&indexer), Rc::clone(&phi));
+        let mut scheme = UpwindScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
 
         let p = Point2d::<i32>::new(1, 1);
 
@@ -102,7 +102,7 @@ mod tests {
             0.0, 3.0, 0.0, 4.0, 2.0, 6.0, 0.0, 5.0, 0.0,
         ]));
         let indexer = Rc::new(Indexer2d::new(&size));
-        let mut scheme = UpwindScheme2d::new(Rc::clone(&indexer), Rc::clone(&phi));
+        let mut scheme = UpwindScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
 
         let p = Point2d::<i32>::new(1, 1);
         let r = scheme.calculate(&p, Speed::Positive);
@@ -122,9 +122,10 @@ mod tests {
         let phi = Rc::new(RefCell::new(vec![0.0, 1.0, 2.0, 3.0, 4.0]));
 
         let mut scheme = <UpwindScheme3d as upwind_scheme::UpwindSchemeMethod<
+            SpaceSize3d,
             Indexer3d,
             Point3d<i32>,
-        >>::new(Rc::clone(&indexer), Rc::clone(&phi));
+        >>::new(&space_size, Rc::clone(&indexer), Rc::clone(&phi));
         scheme.position.set_position(&p, Rc::clone(&indexer));
 
         let r = &scheme.position;
@@ -155,7 +156,7 @@ mod tests {
         ]));
 
         let indexer = Rc::new(Indexer3d::new(&size));
-        let mut scheme = UpwindScheme3d::new(Rc::clone(&indexer), Rc::clone(&phi));
+        let mut scheme = UpwindScheme3d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
 
         let p = Point3d::<i32>::new(1, 1, 1);
 
@@ -164,4 +165,72 @@ mod tests {
         let s = scheme.calculate(&p, Speed::Negative);
         assert!(91.0f64.sqrt() == s);
     }
+
+    #[test]
+    fn calculate_advection_2d() {
+        let size = SpaceSize2d::new(3, 3);
+        let phi = Rc::new(RefCell::new(vec![
+            0.0, 3.0, 0.0, 4.0, 2.0, 6.0, 0.0, 5.0, 0.0,
+        ]));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let mut scheme = UpwindScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+        let p = Point2d::<i32>::new(1, 1);
+
+        // V = (-1, 1): forward difference along x, backward difference along y
+        let r = scheme.calculate_advection(&p, &[1.0, -1.0], 1.0);
+        assert_eq!(r, -(6.0 - 2.0) + 1.0 * (2.0 - 3.0));
+        let r = scheme.calculate_advection(&p, &[1.0, -1.0], 0.0);
+        assert_eq!(r, 0.0);
+    }
+
+    #[test]
+    fn calculate_advection_3d() {
+        let size = SpaceSize3d::new(3, 3, 3);
+        let phi = Rc::new(RefCell::new(vec![
+            0.0, 0.0, 0.0, 0.0, 7.0, 0.0, 0.0, 0.0, 0.0, 0.0, 3.0, 0.0, 4.0, 2.0, 6.0, 0.0, 5.0,
+            0.0, 0.0, 0.0, 0.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.0,
+        ]));
+        let indexer = Rc::new(Indexer3d::new(&size));
+        let mut scheme = UpwindScheme3d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+        let p = Point3d::<i32>::new(1, 1, 1);
+
+        // V = (-0.5, 0.5, -1)
+        let r = scheme.calculate_advection(&p, &[1.0, -1.0, 2.0], 0.5);
+        assert_eq!(
+            r,
+            -0.5 * (6.0 - 2.0) + 0.5 * (2.0 - 3.0) - 1.0 * (8.0 - 2.0)
+        );
+    }
+
+    #[test]
+    fn calculate_subcell_distance_2d() {
+        let size = SpaceSize2d::new(3, 3);
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let phi = Rc::new(RefCell::new(vec![0.0; 9]));
+        let mut scheme = UpwindScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+        let p = Point2d::<i32>::new(1, 1);
+
+        // phi0 = 2(x - 1.25): the zero-levelset is 0.25 away from p
+        let phi0: Vec<f64> = (0..9).map(|i| 2.0 * ((i % 3) as f64 - 1.25)).collect();
+        let d = scheme.calculate_subcell_distance(&p, &phi0).unwrap();
+        assert!((d + 0.25).abs() < 1.0e-12);
+
+        // no sign change around p
+        let phi0: Vec<f64> = (0..9).map(|i| (i % 3) as f64 + 1.0).collect();
+        assert!(scheme.calculate_subcell_distance(&p, &phi0).is_none());
+    }
+
+    #[test]
+    fn calculate_subcell_distance_3d() {
+        let size = SpaceSize3d::new(3, 3, 3);
+        let indexer = Rc::new(Indexer3d::new(&size));
+        let phi = Rc::new(RefCell::new(vec![0.0; 27]));
+        let mut scheme = UpwindScheme3d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+        let p = Point3d::<i32>::new(1, 1, 1);
+
+        // phi0 = 3(z - 0.6)
+        let phi0: Vec<f64> = (0..27).map(|i| 3.0 * ((i / 9) as f64 - 0.6)).collect();
+        let d = scheme.calculate_subcell_distance(&p, &phi0).unwrap();
+        assert!((d - 0.4).abs() < 1.0e-12);
+    }
 }
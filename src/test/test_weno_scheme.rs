@@ -0,0 +1,193 @@
+use crate::core::indexer::{Indexer2d, Indexer3d, IndexerMethod};
+use crate::core::initial_front::InitialFront2d;
+use crate::core::level_set_method::LevelSetMethod2d;
+use crate::core::parameters::{Parameters, SpatialScheme};
+use crate::core::point::{Point2d, Point3d};
+use crate::core::space_size::{SpaceSize2d, SpaceSize3d};
+use crate::core::speed::Speed;
+use crate::core::upwind_scheme::UpwindSchemeMethod;
+use crate::core::weno_scheme::{WenoScheme2d, WenoScheme3d};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_phi_2d(width: i32, height: i32, f: impl Fn(f64, f64) -> f64) -> Vec<f64> {
+        let mut phi = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                phi.push(f(x as f64, y as f64));
+            }
+        }
+        phi
+    }
+
+    #[test]
+    fn derivatives_of_linear_function_2d() {
+        let size = SpaceSize2d::new(9, 9);
+        let phi = Rc::new(RefCell::new(make_phi_2d(9, 9, |x, y| 2.0 * x - 3.0 * y)));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let scheme = WenoScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+
+        let d = scheme.derivatives(&Point2d::<i32>::new(4, 4));
+        assert!((d[0].0 - 2.0).abs() < 1.0e-12);
+        assert!((d[0].1 - 2.0).abs() < 1.0e-12);
+        assert!((d[1].0 + 3.0).abs() < 1.0e-12);
+        assert!((d[1].1 + 3.0).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn derivatives_of_smooth_function_2d() {
+        let size = SpaceSize2d::new(9, 9);
+        let f = |x: f64, y: f64| (0.3 * x).sin() + 0.1 * y * y;
+        let phi = Rc::new(RefCell::new(make_phi_2d(9, 9, f)));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let scheme = WenoScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+
+        // both one-sided approximations are much closer than first-order differences
+        let d = scheme.derivatives(&Point2d::<i32>::new(4, 4));
+        let dx = 0.3 * (0.3f64 * 4.0).cos();
+        let dy = 0.2 * 4.0;
+        assert!((d[0].0 - dx).abs() < 1.0e-3);
+        assert!((d[0].1 - dx).abs() < 1.0e-3);
+        assert!((d[1].0 - dy).abs() < 1.0e-10);
+        assert!((d[1].1 - dy).abs() < 1.0e-10);
+    }
+
+    #[test]
+    fn calculate_2d() {
+        let size = SpaceSize2d::new(9, 9);
+        let phi = Rc::new(RefCell::new(make_phi_2d(9, 9, |x, y| 3.0 * x + 4.0 * y)));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let mut scheme = WenoScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+
+        let p = Point2d::<i32>::new(4, 4);
+        assert!((scheme.calculate(&p, Speed::Positive) - 5.0).abs() < 1.0e-12);
+        assert!((scheme.calculate(&p, Speed::Negative) - 5.0).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn calculate_at_kink_2d() {
+        // |x - 4|: the Godunov Hamiltonian picks the upwind side only
+        let size = SpaceSize2d::new(9, 9);
+        let phi = Rc::new(RefCell::new(make_phi_2d(9, 9, |x, _| (x - 4.0).abs())));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let mut scheme = WenoScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+
+        let p = Point2d::<i32>::new(4, 4);
+        assert_eq!(scheme.calculate(&p, Speed::Positive), 0.0);
+        assert!(scheme.calculate(&p, Speed::Negative) > 0.9);
+    }
+
+    #[test]
+    fn calculate_near_edge_2d() {
+        let size = SpaceSize2d::new(9, 9);
+        let phi = Rc::new(RefCell::new(make_phi_2d(9, 9, |x, y| x + y)));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let mut scheme = WenoScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+
+        let r = scheme.calculate(&Point2d::<i32>::new(1, 1), Speed::Positive);
+        assert!(r.is_finite());
+    }
+
+    #[test]
+    fn calculate_advection_2d() {
+        let size = SpaceSize2d::new(9, 9);
+        let phi = Rc::new(RefCell::new(make_phi_2d(9, 9, |x, y| 3.0 * x + 4.0 * y)));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let mut scheme = WenoScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+
+        // V = (-1, 1)
+        let p = Point2d::<i32>::new(4, 4);
+        let r = scheme.calculate_advection(&p, &[1.0, -1.0], 1.0);
+        assert!((r - (-3.0 + 4.0)).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn calculate_subcell_distance_2d() {
+        let size = SpaceSize2d::new(9, 9);
+        let phi = Rc::new(RefCell::new(vec![0.0; 81]));
+        let indexer = Rc::new(Indexer2d::new(&size));
+        let mut scheme = WenoScheme2d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+
+        let phi0 = make_phi_2d(9, 9, |_, y| 2.0 * (y - 4.5));
+        let p = Point2d::<i32>::new(4, 4);
+        let d = scheme.calculate_subcell_distance(&p, &phi0).unwrap();
+        assert!((d + 0.5).abs() < 1.0e-12);
+        let p = Point2d::<i32>::new(4, 2);
+        assert!(scheme.calculate_subcell_distance(&p, &phi0).is_none());
+    }
+
+    #[test]
+    fn calculate_3d() {
+        let size = SpaceSize3d::new(9, 9, 9);
+        let mut values = Vec::new();
+        for z in 0..9 {
+            for y in 0..9 {
+                for x in 0..9 {
+                    values.push(x as f64 + 2.0 * y as f64 + 2.0 * z as f64);
+                }
+            }
+        }
+        let phi = Rc::new(RefCell::new(values));
+        let indexer = Rc::new(Indexer3d::new(&size));
+        let mut scheme = WenoScheme3d::new(&size, Rc::clone(&indexer), Rc::clone(&phi));
+
+        let p = Point3d::<i32>::new(4, 4, 4);
+        let d = scheme.derivatives(&p);
+        assert!((d[2].0 - 2.0).abs() < 1.0e-12);
+        assert!((scheme.calculate(&p, Speed::Positive) - 3.0).abs() < 1.0e-12);
+        assert!((scheme.calculate(&p, Speed::Negative) - 3.0).abs() < 1.0e-12);
+        let r = scheme.calculate_advection(&p, &[1.0, 1.0, 1.0], 1.0);
+        assert!((r + 5.0).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn narrow_band_too_thin() {
+        let mut params = Parameters::new();
+        params.wband = 2;
+        params.spatial_scheme = SpatialScheme::Weno5;
+        let size = Rc::new(SpaceSize2d::new(11, 11));
+        let gray = Rc::new(RefCell::new(vec![0u8; 121]));
+        assert!(LevelSetMethod2d::new(params, size, gray).is_err());
+    }
+
+    #[test]
+    fn validate_band_width() {
+        let mut params = Parameters::new();
+        params.spatial_scheme = SpatialScheme::Weno5;
+        params.wband = 2;
+        assert_eq!(
+            LevelSetMethod2d::validate_parameters(&params),
+            Err("wband must be at least 3 for Weno5".to_string())
+        );
+        params.wband = 3;
+        assert!(LevelSetMethod2d::validate_parameters(&params).is_ok());
+    }
+
+    #[test]
+    fn run_2d() {
+        let mut params = Parameters::new();
+        params.wband = 3;
+        params.wreset = 1;
+        params.constant_speed = -1.0;
+        params.gain = 0.1;
+        params.time_step = 0.5;
+        params.spatial_scheme = SpatialScheme::Weno5;
+
+        let mut initial_front = InitialFront2d::new();
+        initial_front.vertices[0] = Point2d::<i32>::new(2, 2);
+        initial_front.vertices[1] = Point2d::<i32>::new(12, 12);
+        let size = Rc::new(SpaceSize2d::new(15, 15));
+        let gray = Rc::new(RefCell::new(vec![0u8; 225]));
+        let mut lsm = LevelSetMethod2d::new(params, Rc::clone(&size), Rc::clone(&gray)).unwrap();
+        lsm.initialize(&initial_front);
+
+        let result = lsm.run(5);
+        assert_eq!(result.iterations, 5);
+        assert!(result.phi.iter().all(|v| v.is_finite()));
+        assert!(!result.front.is_empty());
+    }
+}